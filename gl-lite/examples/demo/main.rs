@@ -1,32 +1,19 @@
-use glutin::{ContextBuilder, Event, EventsLoop, WindowBuilder, WindowEvent};
-use glutin::dpi::{LogicalSize};
-use glutin::ContextTrait;
-use gllite;
+use gllite::app::{App, AppConfig};
 use gllite::gli;
-use gllite::texture::Texture;
+use gllite::texture::{FilterMode, Texture, WrapMode};
 use gllite::uniforms::UniformValue;
-use gl;
 use std::rc::Rc;
-use std::thread;
-use std::time::{self, SystemTime};
 
 fn main() {
-  let mut events_loop = EventsLoop::new();
-  let wb = WindowBuilder::new()
-    .with_title("Demo")
-    .with_resizable(false)
-    .with_dimensions(LogicalSize::from((400, 400)));
-  let context = ContextBuilder::new()
-    .with_vsync(true)
-    .build_windowed(wb, &events_loop)
-    .unwrap();
-
-  unsafe {
-    context.make_current().unwrap();
-    gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
-  }
-
-  gllite::gli::clear_color(0.0, 0.0, 0.0, 1.0);
+  let config = AppConfig {
+    title: String::from("Demo"),
+    width: 400,
+    height: 400,
+    resizable: false,
+    max_fps: Some(60),
+    ..AppConfig::default()
+  };
+  let app = App::new(config).unwrap();
 
   let shader_frag = "#version 330
 precision mediump float;
@@ -68,7 +55,7 @@ void main() {
   node.buffer_data(&vertices);
   node.set_uniform(String::from("color"), UniformValue::FloatVec4(1.0, 1.0, 0.0, 1.0));
 
-  let tex = Texture::new();
+  let mut tex = Texture::new();
   let check: [u8;16] = [
     30, 30, 30, 255,
     200, 200, 200, 255,
@@ -76,44 +63,12 @@ void main() {
     30, 30, 30, 255,
   ];
   tex.set_from_bytes(gli::RGBA, 2, 2, gli::RGBA, &check);
-  tex.set_wrap_mode(gli::REPEAT, gli::REPEAT);
-  tex.set_filter_mode(gli::NEAREST, gli::NEAREST);
+  tex.set_wrap_mode(WrapMode::Repeat, WrapMode::Repeat);
+  tex.set_filter_mode(FilterMode::Nearest, FilterMode::Nearest);
   node.set_uniform(String::from("tex"), tex.as_uniform_value());
 
-  let mut last_frame_time = SystemTime::now();
-  loop {
-    let now = SystemTime::now();
-    let delta = match now.duration_since(last_frame_time) {
-      Ok(n) => n.as_millis(),
-      Err(_) => 1,
-    };
-    last_frame_time = now;
-
-    if delta < 16 {
-      let diff = 16 - delta;
-      let sleeptime = time::Duration::from_millis(diff as u64);
-      thread::sleep(sleeptime);
-    }
-
-    let mut should_exit = false;
-    events_loop.poll_events(|event| {
-      match event {
-        Event::WindowEvent {event, ..} => match event {
-          WindowEvent::CloseRequested => should_exit = true,
-          _ => (),
-        },
-        _ => (),
-      }
-    });
-    if should_exit {
-      break;
-    }
-
+  app.start(|_| {
     p.make_current();
-    unsafe {
-      gl::Clear(gl::COLOR_BUFFER_BIT);
-      node.draw();
-    }
-    context.swap_buffers().unwrap();
-  }
-}
\ No newline at end of file
+    node.draw();
+  }).unwrap();
+}
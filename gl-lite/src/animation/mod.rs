@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use super::math::{Quat, Vec3};
+use super::scene::{NodeId, Scene, Transform};
+use super::uniforms::UniformValue;
+
+mod skeleton;
+
+pub use self::skeleton::{JointUpload, Skeleton};
+
+// Uniform the morph target weights of animated nodes are written to, unless
+// the mixer is told otherwise
+pub const MORPH_WEIGHTS_UNIFORM: &str = "u_morph_weights";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+  Step,
+  Linear,
+  // Hermite spline through the values with explicit tangents
+  CubicSpline,
+}
+
+// What a channel animates
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Property {
+  Translation,
+  Rotation,
+  Scale,
+  // The given number of morph target weights
+  MorphWeights(usize),
+}
+
+impl Property {
+  // Number of floats in one value
+  pub fn width(self) -> usize {
+    match self {
+      Property::Translation | Property::Scale => 3,
+      Property::Rotation => 4,
+      Property::MorphWeights(count) => count,
+    }
+  }
+}
+
+fn vec3(v: &[f32]) -> Vec3 {
+  Vec3::new(v[0], v[1], v[2])
+}
+
+fn quat(v: &[f32]) -> Quat {
+  Quat::new(v[0], v[1], v[2], v[3])
+}
+
+// Keyframes for one property of one node
+#[derive(Clone, Debug)]
+pub struct Channel {
+  pub target: NodeId,
+  pub property: Property,
+  pub interpolation: Interpolation,
+  times: Vec<f32>,
+  values: Vec<f32>,
+}
+
+impl Channel {
+  // `values` holds property.width() floats per keyframe, with rotations as
+  // x, y, z, w. Cubic spline keyframes are an in-tangent, the value and an
+  // out-tangent, in that order.
+  pub fn new(target: NodeId, property: Property, interpolation: Interpolation, times: Vec<f32>, values: Vec<f32>) -> Channel {
+    if times.is_empty() {
+      panic!("Cannot create an animation channel without keyframes");
+    }
+    if times.windows(2).any(|w| w[1] < w[0]) {
+      panic!("Keyframe times must not decrease");
+    }
+    let expected = times.len() * Channel::elements(interpolation) * property.width();
+    if values.len() != expected {
+      panic!("Expected {} values for {} keyframes of {:?}, got {}", expected, times.len(), property, values.len());
+    }
+    Channel {
+      target,
+      property,
+      interpolation,
+      times,
+      values,
+    }
+  }
+
+  // Values stored per keyframe
+  fn elements(interpolation: Interpolation) -> usize {
+    match interpolation {
+      Interpolation::CubicSpline => 3,
+      _ => 1,
+    }
+  }
+
+  pub fn times(&self) -> &[f32] {
+    &self.times
+  }
+
+  pub fn values(&self) -> &[f32] {
+    &self.values
+  }
+
+  // Time of the last keyframe
+  pub fn end(&self) -> f32 {
+    self.times[self.times.len() - 1]
+  }
+
+  // The value at `time`, holding the first and last keyframes outside the
+  // keyframed range. Rotations come out normalized.
+  pub fn sample(&self, time: f32) -> Vec<f32> {
+    let width = self.property.width();
+    let elements = Channel::elements(self.interpolation);
+    let element = |key: usize, part: usize| {
+      let start = (key * elements + part) * width;
+      &self.values[start..start + width]
+    };
+    // Index of the value within a keyframe
+    let value = elements / 2;
+
+    let last = self.times.len() - 1;
+    if time <= self.times[0] {
+      return element(0, value).to_vec();
+    }
+    if time >= self.times[last] {
+      return element(last, value).to_vec();
+    }
+    let next = self.times.partition_point(|t| *t <= time);
+    let prev = next - 1;
+    let span = self.times[next] - self.times[prev];
+    let t = if span > 0.0 { (time - self.times[prev]) / span } else { 0.0 };
+
+    let result = match self.interpolation {
+      Interpolation::Step => return element(prev, 0).to_vec(),
+      Interpolation::Linear if self.property == Property::Rotation => {
+        let q = quat(element(prev, 0)).slerp(quat(element(next, 0)), t);
+        return vec![q.x, q.y, q.z, q.w];
+      },
+      Interpolation::Linear => {
+        element(prev, 0).iter().zip(element(next, 0)).map(|(a, b)| a + (b - a) * t).collect()
+      },
+      Interpolation::CubicSpline => {
+        let (t2, t3) = (t * t, t * t * t);
+        let (p0, m0) = (element(prev, 1), element(prev, 2));
+        let (a1, p1) = (element(next, 0), element(next, 1));
+        (0..width).map(|i| {
+          (2.0 * t3 - 3.0 * t2 + 1.0) * p0[i]
+            + (t3 - 2.0 * t2 + t) * span * m0[i]
+            + (-2.0 * t3 + 3.0 * t2) * p1[i]
+            + (t3 - t2) * span * a1[i]
+        }).collect::<Vec<f32>>()
+      },
+    };
+    if self.property == Property::Rotation {
+      let q = quat(&result).normalize();
+      return vec![q.x, q.y, q.z, q.w];
+    }
+    result
+  }
+}
+
+// A named set of channels played together
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+  pub name: String,
+  channels: Vec<Channel>,
+  duration: f32,
+}
+
+impl AnimationClip {
+  pub fn new(name: String) -> AnimationClip {
+    AnimationClip {
+      name,
+      channels: Vec::new(),
+      duration: 0.0,
+    }
+  }
+
+  pub fn add_channel(&mut self, channel: Channel) {
+    self.duration = self.duration.max(channel.end());
+    self.channels.push(channel);
+  }
+
+  pub fn channels(&self) -> &[Channel] {
+    &self.channels
+  }
+
+  // Time of the last keyframe of any channel
+  pub fn duration(&self) -> f32 {
+    self.duration
+  }
+}
+
+// A clip being played by a mixer
+pub struct AnimationLayer {
+  pub clip: Rc<AnimationClip>,
+  pub time: f32,
+  pub speed: f32,
+  // How much the layer overrides the ones before it, from 0 to 1
+  pub weight: f32,
+  pub looping: bool,
+  // Change in weight per second while fading in
+  fade: f32,
+}
+
+impl AnimationLayer {
+  pub fn new(clip: Rc<AnimationClip>) -> AnimationLayer {
+    AnimationLayer {
+      clip,
+      time: 0.0,
+      speed: 1.0,
+      weight: 1.0,
+      looping: true,
+      fade: 0.0,
+    }
+  }
+
+  pub fn is_fading(&self) -> bool {
+    self.fade != 0.0
+  }
+
+  // True once a non-looping layer has reached the end of its clip
+  pub fn is_finished(&self) -> bool {
+    !self.looping && self.time >= self.clip.duration()
+  }
+
+  // Returns true when the layer finished fading in
+  fn advance(&mut self, dt: f32) -> bool {
+    let duration = self.clip.duration();
+    self.time += dt * self.speed;
+    self.time = if self.looping && duration > 0.0 {
+      self.time.rem_euclid(duration)
+    } else {
+      self.time.clamp(0.0, duration)
+    };
+    if self.fade == 0.0 {
+      return false;
+    }
+    self.weight += self.fade * dt;
+    if self.weight < 1.0 {
+      return false;
+    }
+    self.weight = 1.0;
+    self.fade = 0.0;
+    true
+  }
+}
+
+// Current values of an animated node
+#[derive(Clone)]
+struct Pose {
+  transform: Transform,
+  weights: Option<Vec<f32>>,
+}
+
+// Plays clips on a scene. Layers are applied in order, each blending from
+// the result of the ones before it by its weight, starting from the pose the
+// nodes had when the mixer first animated them. Morph weights are written to
+// the nodes' morph uniform.
+pub struct AnimationMixer {
+  layers: Vec<AnimationLayer>,
+  rest: HashMap<NodeId, Pose>,
+  morph_uniform: String,
+}
+
+impl Default for AnimationMixer {
+  fn default() -> AnimationMixer {
+    AnimationMixer::new()
+  }
+}
+
+impl AnimationMixer {
+  pub fn new() -> AnimationMixer {
+    AnimationMixer {
+      layers: Vec::new(),
+      rest: HashMap::new(),
+      morph_uniform: String::from(MORPH_WEIGHTS_UNIFORM),
+    }
+  }
+
+  pub fn set_morph_uniform(&mut self, name: &str) {
+    self.morph_uniform = String::from(name);
+  }
+
+  pub fn morph_uniform(&self) -> &str {
+    &self.morph_uniform
+  }
+
+  // Adds a looping layer at full weight on top of the others, returning its
+  // index
+  pub fn play(&mut self, clip: Rc<AnimationClip>) -> usize {
+    self.layers.push(AnimationLayer::new(clip));
+    self.layers.len() - 1
+  }
+
+  // Fades `clip` in over `duration` seconds on top of the current layers,
+  // which are removed once it reaches full weight
+  pub fn cross_fade(&mut self, clip: Rc<AnimationClip>, duration: f32) -> usize {
+    if duration <= 0.0 {
+      self.layers.clear();
+      return self.play(clip);
+    }
+    let mut layer = AnimationLayer::new(clip);
+    layer.weight = 0.0;
+    layer.fade = 1.0 / duration;
+    self.layers.push(layer);
+    self.layers.len() - 1
+  }
+
+  pub fn layers(&self) -> &[AnimationLayer] {
+    &self.layers
+  }
+
+  pub fn layer_mut(&mut self, index: usize) -> &mut AnimationLayer {
+    &mut self.layers[index]
+  }
+
+  pub fn stop(&mut self, index: usize) -> AnimationLayer {
+    self.layers.remove(index)
+  }
+
+  pub fn stop_all(&mut self) {
+    self.layers.clear();
+  }
+
+  // Advances every layer by `dt` seconds. A layer that finishes fading in
+  // hides everything below it, so those layers are dropped.
+  pub fn update(&mut self, dt: f32) {
+    let mut faded_in = None;
+    for (i, layer) in self.layers.iter_mut().enumerate() {
+      if layer.advance(dt) {
+        faded_in = Some(i);
+      }
+    }
+    if let Some(i) = faded_in {
+      self.layers.drain(..i);
+    }
+  }
+
+  // Writes the blended pose of every animated node to the scene. Call
+  // scene.update() afterwards before reading world matrices.
+  pub fn apply(&mut self, scene: &mut Scene) {
+    let AnimationMixer { layers, rest, morph_uniform } = self;
+    let mut poses: HashMap<NodeId, Pose> = HashMap::new();
+    for layer in layers.iter() {
+      let weight = layer.weight.min(1.0);
+      if weight <= 0.0 {
+        continue;
+      }
+      for channel in layer.clip.channels() {
+        let target = channel.target;
+        if !scene.contains(target) {
+          continue;
+        }
+        let pose = poses.entry(target).or_insert_with(|| rest.entry(target).or_insert_with(|| {
+          let weights = match scene.node(target).and_then(|n| n.uniform(morph_uniform.as_str())) {
+            Some(UniformValue::FloatArray(w)) => Some(w.clone()),
+            _ => None,
+          };
+          Pose {
+            transform: *scene.transform(target),
+            weights,
+          }
+        }).clone());
+        let value = channel.sample(layer.time);
+        let transform = &mut pose.transform;
+        match channel.property {
+          Property::Translation => transform.translation = transform.translation.lerp(vec3(&value), weight),
+          Property::Rotation => transform.rotation = transform.rotation.slerp(quat(&value), weight),
+          Property::Scale => transform.scale = transform.scale.lerp(vec3(&value), weight),
+          Property::MorphWeights(count) => {
+            let current = pose.weights.get_or_insert_with(Vec::new);
+            current.resize(count, 0.0);
+            for (c, v) in current.iter_mut().zip(value) {
+              *c += (v - *c) * weight;
+            }
+          },
+        }
+      }
+    }
+    for (id, pose) in poses {
+      scene.set_transform(id, pose.transform);
+      if let (Some(weights), Some(node)) = (pose.weights, scene.node_mut(id)) {
+        node.set_uniform(morph_uniform.clone(), UniformValue::FloatArray(weights));
+      }
+    }
+  }
+
+  // Puts every node the mixer animated back in the pose it had before, and
+  // forgets it
+  pub fn reset(&mut self, scene: &mut Scene) {
+    for (id, pose) in self.rest.drain() {
+      if !scene.contains(id) {
+        continue;
+      }
+      scene.set_transform(id, pose.transform);
+      if let Some(node) = scene.node_mut(id) {
+        match pose.weights {
+          Some(weights) => node.set_uniform(self.morph_uniform.clone(), UniformValue::FloatArray(weights)),
+          None => {
+            node.remove_uniform(&self.morph_uniform);
+          },
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+      assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+    }
+  }
+
+  fn translation(interpolation: Interpolation, times: Vec<f32>, values: Vec<f32>) -> Channel {
+    let mut scene = Scene::new();
+    Channel::new(scene.add_empty(), Property::Translation, interpolation, times, values)
+  }
+
+  #[test]
+  fn step_holds_the_previous_keyframe() {
+    let channel = translation(Interpolation::Step, vec![0.0, 1.0, 2.0], vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0]);
+    assert_close(&channel.sample(0.99), &[0.0, 0.0, 0.0]);
+    assert_close(&channel.sample(1.0), &[1.0, 1.0, 1.0]);
+    assert_close(&channel.sample(1.5), &[1.0, 1.0, 1.0]);
+    assert_close(&channel.sample(-1.0), &[0.0, 0.0, 0.0]);
+    assert_close(&channel.sample(5.0), &[2.0, 2.0, 2.0]);
+  }
+
+  #[test]
+  fn linear_interpolates_between_keyframes() {
+    let channel = translation(Interpolation::Linear, vec![1.0, 3.0], vec![0.0, 2.0, -4.0, 4.0, 2.0, 0.0]);
+    assert_close(&channel.sample(1.5), &[1.0, 2.0, -3.0]);
+    assert_close(&channel.sample(2.0), &[2.0, 2.0, -2.0]);
+    assert_close(&channel.sample(0.0), &[0.0, 2.0, -4.0]);
+  }
+
+  #[test]
+  fn linear_rotations_slerp() {
+    let mut scene = Scene::new();
+    let half = std::f32::consts::FRAC_1_SQRT_2;
+    // Identity to 90 degrees around z
+    let channel = Channel::new(scene.add_empty(), Property::Rotation, Interpolation::Linear, vec![0.0, 1.0], vec![
+      0.0, 0.0, 0.0, 1.0,
+      0.0, 0.0, half, half,
+    ]);
+    let angle = std::f32::consts::FRAC_PI_8;
+    assert_close(&channel.sample(0.5), &[0.0, 0.0, angle.sin(), angle.cos()]);
+  }
+
+  #[test]
+  fn cubic_splines_use_the_tangents() {
+    // In-tangent, value, out-tangent per keyframe. Flat tangents ease in
+    // and out.
+    let flat = translation(Interpolation::CubicSpline, vec![0.0, 2.0], vec![
+      0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+      0.0, 0.0, 0.0, 4.0, 4.0, 4.0, 0.0, 0.0, 0.0,
+    ]);
+    assert_close(&flat.sample(1.0), &[2.0, 2.0, 2.0]);
+    assert_close(&flat.sample(0.5), &[0.625, 0.625, 0.625]);
+    assert_close(&flat.sample(2.0), &[4.0, 4.0, 4.0]);
+
+    // Tangents matching the slope, in units per second, give a straight line
+    let straight = translation(Interpolation::CubicSpline, vec![0.0, 2.0], vec![
+      2.0, 2.0, 2.0, 0.0, 0.0, 0.0, 2.0, 2.0, 2.0,
+      2.0, 2.0, 2.0, 4.0, 4.0, 4.0, 2.0, 2.0, 2.0,
+    ]);
+    assert_close(&straight.sample(0.5), &[1.0, 1.0, 1.0]);
+    assert_close(&straight.sample(1.5), &[3.0, 3.0, 3.0]);
+  }
+
+  // A clip holding a node at `x` along the x axis
+  fn hold(target: NodeId, x: f32) -> Rc<AnimationClip> {
+    let mut clip = AnimationClip::new(format!("hold {}", x));
+    clip.add_channel(Channel::new(target, Property::Translation, Interpolation::Step, vec![0.0, 1.0], vec![x, 0.0, 0.0, x, 0.0, 0.0]));
+    Rc::new(clip)
+  }
+
+  #[test]
+  fn cross_fades_blend_then_drop_the_old_layers() {
+    let mut scene = Scene::new();
+    let node = scene.add_empty();
+    let mut mixer = AnimationMixer::new();
+    mixer.play(hold(node, 2.0));
+    mixer.cross_fade(hold(node, 4.0), 1.0);
+
+    mixer.update(0.5);
+    assert_eq!(mixer.layers().len(), 2);
+    assert!(mixer.layers()[1].is_fading());
+    mixer.apply(&mut scene);
+    assert_close(&[scene.transform(node).translation.x], &[3.0]);
+
+    mixer.update(0.6);
+    assert_eq!(mixer.layers().len(), 1);
+    assert_eq!(mixer.layers()[0].clip.name, "hold 4");
+    assert!(!mixer.layers()[0].is_fading());
+    mixer.apply(&mut scene);
+    assert_close(&[scene.transform(node).translation.x], &[4.0]);
+  }
+
+  #[test]
+  fn layers_blend_from_the_rest_pose() {
+    let mut scene = Scene::new();
+    let node = scene.add_empty();
+    scene.set_translation(node, Vec3::new(1.0, 0.0, 0.0));
+    let mut mixer = AnimationMixer::new();
+    let layer = mixer.play(hold(node, 3.0));
+    mixer.layer_mut(layer).weight = 0.25;
+    mixer.apply(&mut scene);
+    assert_close(&[scene.transform(node).translation.x], &[1.5]);
+
+    mixer.reset(&mut scene);
+    assert_close(&[scene.transform(node).translation.x], &[1.0]);
+  }
+}
@@ -0,0 +1,104 @@
+use super::super::format::{Channels, TextureFormat};
+use super::super::math::Mat4;
+use super::super::node::Node;
+use super::super::scene::{NodeId, Scene};
+use super::super::texture::{FilterMode, Texture};
+use super::super::uniforms::UniformValue;
+
+// How joint matrices reach the shader
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JointUpload {
+  // A mat4 array uniform with room for every joint
+  Uniform(String),
+  // An RGBA32F texture four texels wide with one row per joint, each texel a
+  // matrix column, for skeletons too large for a uniform array
+  Texture(String),
+}
+
+// Joints driving skinned geometry. Each vertex is moved by its a_joints
+// matrices, mixed by its a_weights.
+pub struct Skeleton {
+  joints: Vec<NodeId>,
+  inverse_bind_matrices: Vec<Mat4>,
+  matrices: Vec<[f32; 16]>,
+  upload: JointUpload,
+  texture: Option<Texture>,
+}
+
+impl Skeleton {
+  // `inverse_bind_matrices` take the mesh into each joint's space at bind
+  // time. An empty list means they are all the identity.
+  pub fn new(joints: Vec<NodeId>, inverse_bind_matrices: Vec<Mat4>) -> Skeleton {
+    let inverse_bind_matrices = if inverse_bind_matrices.is_empty() {
+      vec![Mat4::IDENTITY; joints.len()]
+    } else {
+      inverse_bind_matrices
+    };
+    if inverse_bind_matrices.len() != joints.len() {
+      panic!("Expected {} inverse bind matrices, got {}", joints.len(), inverse_bind_matrices.len());
+    }
+    Skeleton {
+      matrices: vec![Mat4::IDENTITY.to_array(); joints.len()],
+      joints,
+      inverse_bind_matrices,
+      upload: JointUpload::Uniform(String::from("u_joint_matrices")),
+      texture: None,
+    }
+  }
+
+  pub fn joints(&self) -> &[NodeId] {
+    &self.joints
+  }
+
+  pub fn inverse_bind_matrices(&self) -> &[Mat4] {
+    &self.inverse_bind_matrices
+  }
+
+  pub fn upload(&self) -> &JointUpload {
+    &self.upload
+  }
+
+  pub fn set_upload(&mut self, upload: JointUpload) {
+    self.upload = upload;
+  }
+
+  // Recomputes the joint matrices from the scene's world matrices, so call
+  // it after scene.update(). They are relative to `mesh`, the node drawing
+  // the skinned geometry, as its model matrix still applies on top.
+  pub fn update(&mut self, scene: &Scene, mesh: Option<NodeId>) {
+    let to_mesh = match mesh {
+      Some(mesh) => scene.world_matrix(mesh).inverse().unwrap_or(Mat4::IDENTITY),
+      None => Mat4::IDENTITY,
+    };
+    for (i, joint) in self.joints.iter().enumerate() {
+      self.matrices[i] = (to_mesh * scene.world_matrix(*joint) * self.inverse_bind_matrices[i]).to_array();
+    }
+  }
+
+  // Column-major joint matrices as of the last update
+  pub fn joint_matrices(&self) -> &[[f32; 16]] {
+    &self.matrices
+  }
+
+  // Hands the joint matrices to `node` the way set_upload asked. The
+  // texture is shared by every node the skeleton is applied to.
+  pub fn apply(&mut self, node: &mut Node) {
+    match &self.upload {
+      JointUpload::Uniform(name) => {
+        node.set_uniform(name.clone(), UniformValue::FloatMat4Array(self.matrices.clone()));
+      },
+      JointUpload::Texture(name) => {
+        let texture = self.texture.get_or_insert_with(|| {
+          let mut texture = Texture::new();
+          texture.set_filter_mode(FilterMode::Nearest, FilterMode::Nearest);
+          texture
+        });
+        let rows = self.matrices.len().max(1) as i32;
+        let mut data = self.matrices.as_flattened().to_vec();
+        data.resize(rows as usize * 16, 0.0);
+        texture.set_data(TextureFormat::Float32(Channels::RGBA), 4, rows, &data);
+        node.set_uniform(name.clone(), texture.as_uniform_value());
+      },
+    }
+  }
+}
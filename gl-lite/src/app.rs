@@ -0,0 +1,254 @@
+use std::error::Error;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+use glutin::{Api, ContextBuilder, ContextTrait, Event, EventsLoop, GlProfile, GlRequest, WindowBuilder, WindowEvent, WindowedContext};
+use glutin::dpi::LogicalSize;
+use super::gli;
+
+pub struct AppConfig {
+  pub title: String,
+  // Logical size of the window's drawable area
+  pub width: u32,
+  pub height: u32,
+  pub resizable: bool,
+  pub vsync: bool,
+  // Sleeps between frames to stay under this rate; mostly useful without
+  // vsync
+  pub max_fps: Option<u32>,
+  pub gl_version: (u8, u8),
+  pub multisampling: u16,
+  pub depth_bits: u8,
+  pub clear_color: [f32; 4],
+}
+
+impl Default for AppConfig {
+  fn default() -> AppConfig {
+    AppConfig {
+      title: String::from("gl-lite"),
+      width: 800,
+      height: 600,
+      resizable: true,
+      vsync: true,
+      max_fps: None,
+      gl_version: (3, 3),
+      multisampling: 0,
+      depth_bits: 24,
+      clear_color: [0.0, 0.0, 0.0, 1.0],
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum AppError {
+  Creation(String),
+  Context(String),
+}
+
+impl fmt::Display for AppError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      AppError::Creation(msg) => write!(f, "Failed to create window: {}", msg),
+      AppError::Context(msg) => write!(f, "GL context error: {}", msg),
+    }
+  }
+}
+
+impl Error for AppError {}
+
+// State handed to the frame callback. The framebuffer has already been
+// cleared and the viewport covers the whole window.
+pub struct Frame<'a> {
+  delta: Duration,
+  elapsed: Duration,
+  index: u64,
+  width: i32,
+  height: i32,
+  resized: bool,
+  events: &'a [WindowEvent],
+  exit: bool,
+}
+
+impl<'a> Frame<'a> {
+  // Time since the previous frame started
+  pub fn delta(&self) -> Duration {
+    self.delta
+  }
+
+  pub fn delta_seconds(&self) -> f32 {
+    self.delta.as_secs_f32()
+  }
+
+  // Time since the first frame started
+  pub fn elapsed(&self) -> Duration {
+    self.elapsed
+  }
+
+  pub fn elapsed_seconds(&self) -> f32 {
+    self.elapsed.as_secs_f32()
+  }
+
+  pub fn index(&self) -> u64 {
+    self.index
+  }
+
+  // Drawable size in physical pixels
+  pub fn size(&self) -> (i32, i32) {
+    (self.width, self.height)
+  }
+
+  pub fn aspect_ratio(&self) -> f32 {
+    self.width as f32 / self.height.max(1) as f32
+  }
+
+  // True on the first frame and after the window changed size
+  pub fn resized(&self) -> bool {
+    self.resized
+  }
+
+  // Window events received since the previous frame
+  pub fn events(&self) -> &[WindowEvent] {
+    self.events
+  }
+
+  // Ends the loop after this frame is presented
+  pub fn exit(&mut self) {
+    self.exit = true;
+  }
+}
+
+// Owns the window, its GL context and the event loop. The context is
+// current as soon as the App exists, so GL resources can be created
+// between App::new and App::start.
+pub struct App {
+  events_loop: EventsLoop,
+  context: WindowedContext,
+  clear_color: [f32; 4],
+  clear_mask: u32,
+  min_frame_time: Option<Duration>,
+}
+
+impl App {
+  pub fn new(config: AppConfig) -> Result<App, AppError> {
+    let events_loop = EventsLoop::new();
+    let window = WindowBuilder::new()
+      .with_title(config.title.as_str())
+      .with_resizable(config.resizable)
+      .with_dimensions(LogicalSize::new(config.width as f64, config.height as f64));
+    let context = ContextBuilder::new()
+      .with_gl(GlRequest::Specific(Api::OpenGl, config.gl_version))
+      .with_gl_profile(GlProfile::Core)
+      .with_vsync(config.vsync)
+      .with_multisampling(config.multisampling)
+      .with_depth_buffer(config.depth_bits)
+      .build_windowed(window, &events_loop)
+      .map_err(|e| AppError::Creation(e.to_string()))?;
+
+    unsafe {
+      context.make_current().map_err(|e| AppError::Context(format!("{:?}", e)))?;
+    }
+    gl::load_with(|symbol| context.get_proc_address(symbol) as *const _);
+
+    let mut clear_mask = gli::COLOR_BUFFER_BIT;
+    if config.depth_bits > 0 {
+      clear_mask |= gli::DEPTH_BUFFER_BIT;
+    }
+    Ok(App {
+      events_loop,
+      context,
+      clear_color: config.clear_color,
+      clear_mask,
+      min_frame_time: config.max_fps.map(|fps| Duration::from_secs(1) / fps.max(1)),
+    })
+  }
+
+  // Opens a window and calls `draw` once per frame until the window is
+  // closed or the callback asks to exit
+  pub fn run<F: FnMut(&mut Frame)>(config: AppConfig, draw: F) -> Result<(), AppError> {
+    App::new(config)?.start(draw)
+  }
+
+  // Runs the frame loop. Resizes update the context before the next frame,
+  // each frame starts on the window's framebuffer with a full-window
+  // viewport, and the context is released when the loop ends.
+  pub fn start<F: FnMut(&mut Frame)>(self, mut draw: F) -> Result<(), AppError> {
+    let App { mut events_loop, context, clear_color, clear_mask, min_frame_time } = self;
+    let mut dpi_factor = context.get_hidpi_factor();
+    let mut logical_size = context.get_inner_size().unwrap_or_else(|| LogicalSize::new(1.0, 1.0));
+    let mut resized = true;
+    let start = Instant::now();
+    let mut last_frame = start;
+    let mut index = 0;
+    let mut events = Vec::new();
+
+    loop {
+      let mut close_requested = false;
+      events.clear();
+      events_loop.poll_events(|event| {
+        if let Event::WindowEvent { event, .. } = event {
+          match event {
+            WindowEvent::CloseRequested | WindowEvent::Destroyed => close_requested = true,
+            WindowEvent::Resized(size) => {
+              logical_size = size;
+              resized = true;
+            },
+            WindowEvent::HiDpiFactorChanged(factor) => {
+              dpi_factor = factor;
+              resized = true;
+            },
+            _ => (),
+          }
+          events.push(event);
+        }
+      });
+      if close_requested {
+        break;
+      }
+
+      let physical = logical_size.to_physical(dpi_factor);
+      let (width, height) = (physical.width.round() as i32, physical.height.round() as i32);
+      if resized {
+        context.resize(physical);
+      }
+
+      let now = Instant::now();
+      let mut frame = Frame {
+        delta: now - last_frame,
+        elapsed: now - start,
+        index,
+        width,
+        height,
+        resized,
+        events: &events,
+        exit: false,
+      };
+      last_frame = now;
+      resized = false;
+      index += 1;
+
+      // The last frame's callback may have left an offscreen target, another
+      // viewport or another clear color in place
+      let [r, g, b, a] = clear_color;
+      gli::bind_framebuffer(0);
+      gli::viewport(0, 0, width, height);
+      gli::clear_color(r, g, b, a);
+      gli::clear(clear_mask);
+      draw(&mut frame);
+      let exit = frame.exit;
+      context.swap_buffers().map_err(|e| AppError::Context(format!("{:?}", e)))?;
+      if exit {
+        break;
+      }
+
+      if let Some(min) = min_frame_time {
+        let spent = now.elapsed();
+        if spent < min {
+          thread::sleep(min - spent);
+        }
+      }
+    }
+    // Release the context before the window and event loop go away
+    drop(context);
+    Ok(())
+  }
+}
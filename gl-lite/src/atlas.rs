@@ -0,0 +1,359 @@
+use super::format::{Channels, TextureFormat};
+use super::gli;
+use super::texture::Texture;
+
+pub struct AtlasOptions {
+  // Pages start at this size and double until they reach `max_size`
+  pub initial_size: i32,
+  pub max_size: i32,
+  // Empty pixels kept between entries to stop filtering bleeding across them
+  pub padding: i32,
+}
+
+impl Default for AtlasOptions {
+  fn default() -> AtlasOptions {
+    AtlasOptions {
+      initial_size: 256,
+      max_size: 2048,
+      padding: 1,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasId(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasEntry {
+  pub page: usize,
+  pub x: i32,
+  pub y: i32,
+  pub width: i32,
+  pub height: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+  pub u0: f32,
+  pub v0: f32,
+  pub u1: f32,
+  pub v1: f32,
+}
+
+#[derive(Clone, Copy)]
+struct SkylineSegment {
+  x: i32,
+  y: i32,
+  width: i32,
+}
+
+// Bottom-left skyline packer. The skyline tracks the highest occupied row for
+// each horizontal span, and each rectangle goes wherever it ends up lowest.
+struct Skyline {
+  width: i32,
+  height: i32,
+  segments: Vec<SkylineSegment>,
+}
+
+impl Skyline {
+  fn new(width: i32, height: i32) -> Skyline {
+    Skyline {
+      width,
+      height,
+      segments: vec![SkylineSegment { x: 0, y: 0, width }],
+    }
+  }
+
+  // Height at which a rectangle starting at segment `index` would sit
+  fn fit(&self, index: usize, width: i32, height: i32) -> Option<i32> {
+    let x = self.segments[index].x;
+    if x + width > self.width {
+      return None;
+    }
+    let mut y = 0;
+    let mut remaining = width;
+    for segment in &self.segments[index..] {
+      if remaining <= 0 {
+        break;
+      }
+      y = y.max(segment.y);
+      remaining -= segment.width;
+    }
+    if y + height > self.height {
+      return None;
+    }
+    Some(y)
+  }
+
+  fn insert(&mut self, width: i32, height: i32) -> Option<(i32, i32)> {
+    let mut best: Option<(usize, i32, i32)> = None;
+    for index in 0..self.segments.len() {
+      if let Some(y) = self.fit(index, width, height) {
+        let segment_width = self.segments[index].width;
+        let better = match best {
+          Some((_, best_y, best_width)) => y < best_y || (y == best_y && segment_width < best_width),
+          None => true,
+        };
+        if better {
+          best = Some((index, y, segment_width));
+        }
+      }
+    }
+    let (index, y, _) = best?;
+    let x = self.segments[index].x;
+    self.place(index, x, y + height, width);
+    Some((x, y))
+  }
+
+  fn place(&mut self, index: usize, x: i32, top: i32, width: i32) {
+    self.segments.insert(index, SkylineSegment { x, y: top, width });
+    let right = x + width;
+    // Trim or drop the segments now covered by the new one
+    while index + 1 < self.segments.len() {
+      let next = &mut self.segments[index + 1];
+      if next.x >= right {
+        break;
+      }
+      let overlap = right - next.x;
+      if overlap < next.width {
+        next.x += overlap;
+        next.width -= overlap;
+        break;
+      }
+      self.segments.remove(index + 1);
+    }
+    let mut i = 0;
+    while i + 1 < self.segments.len() {
+      if self.segments[i].y == self.segments[i + 1].y {
+        self.segments[i].width += self.segments[i + 1].width;
+        self.segments.remove(i + 1);
+      } else {
+        i += 1;
+      }
+    }
+  }
+
+  // Extends the packing area; existing placements stay where they are
+  fn grow(&mut self, width: i32, height: i32) {
+    if width > self.width {
+      self.segments.push(SkylineSegment { x: self.width, y: 0, width: width - self.width });
+    }
+    self.width = width;
+    self.height = height;
+  }
+}
+
+struct AtlasPage {
+  texture: Texture,
+  skyline: Skyline,
+}
+
+impl AtlasPage {
+  fn new(size: i32) -> AtlasPage {
+    AtlasPage {
+      texture: blank_texture(size),
+      skyline: Skyline::new(size, size),
+    }
+  }
+}
+
+fn blank_texture(size: i32) -> Texture {
+  let mut texture = Texture::new();
+  let pixels = vec![0u8; size as usize * size as usize * 4];
+  texture.set_data(TextureFormat::Unorm8(Channels::RGBA), size, size, &pixels);
+  texture
+}
+
+// Packs RGBA8 images into one or more atlas textures. Each insertion only
+// uploads the new image's region. When a page fills up it is doubled in size
+// with a GPU-side copy if the context can, and a new page is opened once the
+// maximum size is reached. UVs are derived from the current page size, so
+// they should be looked up again after insertions that may have grown a page.
+pub struct TextureAtlas {
+  options: AtlasOptions,
+  pages: Vec<AtlasPage>,
+  entries: Vec<AtlasEntry>,
+}
+
+impl TextureAtlas {
+  pub fn new(options: AtlasOptions) -> TextureAtlas {
+    if options.initial_size < 1 || options.max_size < options.initial_size {
+      panic!("Invalid atlas sizes: initial {}, max {}", options.initial_size, options.max_size);
+    }
+    if options.padding < 0 {
+      panic!("Atlas padding cannot be negative");
+    }
+    TextureAtlas {
+      options,
+      pages: Vec::new(),
+      entries: Vec::new(),
+    }
+  }
+
+  pub fn insert(&mut self, width: i32, height: i32, data: &[u8]) -> AtlasId {
+    if width < 1 || height < 1 {
+      panic!("Cannot insert an image of size {}x{} into an atlas", width, height);
+    }
+    let expected = width as usize * height as usize * 4;
+    if data.len() != expected {
+      panic!("Expected {} bytes for a {}x{} RGBA image, got {}", expected, width, height, data.len());
+    }
+    let padded_width = width + self.options.padding;
+    let padded_height = height + self.options.padding;
+    if width > self.options.max_size || height > self.options.max_size {
+      panic!("A {}x{} image does not fit in an atlas of at most {}x{}", width, height, self.options.max_size, self.options.max_size);
+    }
+
+    let (page, x, y) = self.allocate(padded_width.min(self.options.max_size), padded_height.min(self.options.max_size));
+    self.pages[page].texture.update_region(x, y, width, height, data);
+    self.entries.push(AtlasEntry { page, x, y, width, height });
+    AtlasId(self.entries.len() - 1)
+  }
+
+  fn allocate(&mut self, width: i32, height: i32) -> (usize, i32, i32) {
+    for (index, page) in self.pages.iter_mut().enumerate() {
+      if let Some((x, y)) = page.skyline.insert(width, height) {
+        return (index, x, y);
+      }
+    }
+    // Only the newest page can still be below the maximum size
+    while let Some(last) = self.pages.len().checked_sub(1) {
+      if !self.grow_page(last) {
+        break;
+      }
+      if let Some((x, y)) = self.pages[last].skyline.insert(width, height) {
+        return (last, x, y);
+      }
+    }
+    let mut size = self.options.initial_size;
+    while size < width.max(height) {
+      size = (size * 2).min(self.options.max_size);
+    }
+    let mut page = AtlasPage::new(size);
+    let (x, y) = match page.skyline.insert(width, height) {
+      Some(position) => position,
+      None => panic!("A {}x{} region does not fit in an empty {}x{} atlas page", width, height, size, size),
+    };
+    self.pages.push(page);
+    (self.pages.len() - 1, x, y)
+  }
+
+  fn grow_page(&mut self, index: usize) -> bool {
+    let page = &mut self.pages[index];
+    let size = page.texture.width();
+    if size >= self.options.max_size || !gli::supports_copy_image() {
+      return false;
+    }
+    let new_size = (size * 2).min(self.options.max_size);
+    let mut texture = blank_texture(new_size);
+    texture.copy_from(&page.texture);
+    let (wrap_s, wrap_t) = page.texture.wrap_mode();
+    let (min_filter, mag_filter) = page.texture.filter_mode();
+    texture.set_wrap_mode(wrap_s, wrap_t);
+    texture.set_filter_mode(min_filter, mag_filter);
+    // The old page texture is deleted as it is dropped here
+    page.texture = texture;
+    page.skyline.grow(new_size, new_size);
+    true
+  }
+
+  pub fn entry(&self, id: AtlasId) -> AtlasEntry {
+    self.entries[id.0]
+  }
+
+  pub fn uv_rect(&self, id: AtlasId) -> UvRect {
+    let entry = self.entries[id.0];
+    let texture = &self.pages[entry.page].texture;
+    let width = texture.width() as f32;
+    let height = texture.height() as f32;
+    UvRect {
+      u0: entry.x as f32 / width,
+      v0: entry.y as f32 / height,
+      u1: (entry.x + entry.width) as f32 / width,
+      v1: (entry.y + entry.height) as f32 / height,
+    }
+  }
+
+  pub fn texture_for(&self, id: AtlasId) -> &Texture {
+    &self.pages[self.entries[id.0].page].texture
+  }
+
+  pub fn page_count(&self) -> usize {
+    self.pages.len()
+  }
+
+  pub fn page(&self, index: usize) -> &Texture {
+    &self.pages[index].texture
+  }
+
+  // Mutable access for changing sampling state; resizing the texture
+  // directly would invalidate the atlas layout
+  pub fn page_mut(&mut self, index: usize) -> &mut Texture {
+    &mut self.pages[index].texture
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn overlaps(a: (i32, i32, i32, i32), b: (i32, i32, i32, i32)) -> bool {
+    a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+  }
+
+  #[test]
+  fn places_rectangles_bottom_left() {
+    let mut skyline = Skyline::new(16, 16);
+    assert_eq!(skyline.insert(8, 4), Some((0, 0)));
+    assert_eq!(skyline.insert(8, 2), Some((8, 0)));
+    // The lowest spot is on top of the shorter rectangle
+    assert_eq!(skyline.insert(8, 8), Some((8, 2)));
+    assert_eq!(skyline.insert(16, 4), Some((0, 10)));
+  }
+
+  #[test]
+  fn packed_rectangles_never_overlap() {
+    let mut skyline = Skyline::new(64, 64);
+    let mut placed = Vec::new();
+    for i in 0..40 {
+      let (width, height) = (3 + i * 7 % 11, 2 + i * 5 % 9);
+      if let Some((x, y)) = skyline.insert(width, height) {
+        let rect = (x, y, width, height);
+        assert!(x >= 0 && y >= 0 && x + width <= 64 && y + height <= 64);
+        assert!(placed.iter().all(|other| !overlaps(rect, *other)));
+        placed.push(rect);
+      }
+    }
+    assert!(placed.len() > 20);
+  }
+
+  #[test]
+  fn fails_when_full_or_too_large() {
+    let mut skyline = Skyline::new(8, 8);
+    assert_eq!(skyline.insert(9, 1), None);
+    assert_eq!(skyline.insert(1, 9), None);
+    assert_eq!(skyline.insert(8, 6), Some((0, 0)));
+    assert_eq!(skyline.insert(4, 3), None);
+    assert_eq!(skyline.insert(4, 2), Some((0, 6)));
+  }
+
+  #[test]
+  fn growing_keeps_placements_and_adds_space() {
+    let mut skyline = Skyline::new(8, 8);
+    assert_eq!(skyline.insert(8, 8), Some((0, 0)));
+    assert_eq!(skyline.insert(4, 4), None);
+    skyline.grow(16, 16);
+    // The new column to the right is lower than the top of the first rectangle
+    assert_eq!(skyline.insert(8, 8), Some((8, 0)));
+    assert_eq!(skyline.insert(16, 8), Some((0, 8)));
+    assert_eq!(skyline.insert(1, 1), None);
+  }
+}
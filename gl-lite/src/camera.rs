@@ -0,0 +1,619 @@
+use std::f32::consts::FRAC_PI_2;
+use super::math::{Mat4, Vec3};
+use super::program::Program;
+
+// Keeps pitch just short of straight up or down, where look_at degenerates
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+  pub origin: Vec3,
+  // Unit length
+  pub direction: Vec3,
+}
+
+impl Ray {
+  pub fn at(&self, t: f32) -> Vec3 {
+    self.origin + self.direction * t
+  }
+}
+
+pub trait Camera {
+  fn view_matrix(&self) -> Mat4;
+
+  fn projection_matrix(&self) -> Mat4;
+
+  fn position(&self) -> Vec3;
+
+  fn look_at(&mut self, eye: Vec3, target: Vec3, up: Vec3);
+
+  // Updates the aspect ratio for a viewport of this size in pixels
+  fn resize(&mut self, width: i32, height: i32);
+
+  fn view_projection_matrix(&self) -> Mat4 {
+    self.projection_matrix() * self.view_matrix()
+  }
+
+  // Ray through a point in window coordinates, with the origin at the top
+  // left of a viewport of the given size. The ray starts on the near plane.
+  fn unproject(&self, x: f32, y: f32, width: i32, height: i32) -> Ray {
+    let inverse = match self.view_projection_matrix().inverse() {
+      Some(inverse) => inverse,
+      None => panic!("Cannot unproject through a singular view-projection matrix"),
+    };
+    let ndc_x = 2.0 * x / width.max(1) as f32 - 1.0;
+    let ndc_y = 1.0 - 2.0 * y / height.max(1) as f32;
+    let near = inverse.project_point(Vec3::new(ndc_x, ndc_y, -1.0));
+    let far = inverse.project_point(Vec3::new(ndc_x, ndc_y, 1.0));
+    Ray {
+      origin: near,
+      direction: (far - near).normalize(),
+    }
+  }
+}
+
+fn aspect_ratio(width: i32, height: i32) -> f32 {
+  width.max(1) as f32 / height.max(1) as f32
+}
+
+pub struct PerspectiveCamera {
+  pub eye: Vec3,
+  pub target: Vec3,
+  pub up: Vec3,
+  // Vertical field of view in radians
+  pub fov_y: f32,
+  pub aspect: f32,
+  pub near: f32,
+  pub far: f32,
+}
+
+impl Default for PerspectiveCamera {
+  fn default() -> PerspectiveCamera {
+    PerspectiveCamera::new(FRAC_PI_2 * 0.5, 1.0, 0.1, 100.0)
+  }
+}
+
+impl PerspectiveCamera {
+  // Starts at the origin looking down -Z
+  pub fn new(fov_y: f32, aspect: f32, near: f32, far: f32) -> PerspectiveCamera {
+    if near <= 0.0 || far <= near {
+      panic!("Invalid perspective depth range {}..{}", near, far);
+    }
+    PerspectiveCamera {
+      eye: Vec3::ZERO,
+      target: -Vec3::Z,
+      up: Vec3::Y,
+      fov_y,
+      aspect,
+      near,
+      far,
+    }
+  }
+}
+
+impl Camera for PerspectiveCamera {
+  fn view_matrix(&self) -> Mat4 {
+    Mat4::look_at(self.eye, self.target, self.up)
+  }
+
+  fn projection_matrix(&self) -> Mat4 {
+    Mat4::perspective(self.fov_y, self.aspect, self.near, self.far)
+  }
+
+  fn position(&self) -> Vec3 {
+    self.eye
+  }
+
+  fn look_at(&mut self, eye: Vec3, target: Vec3, up: Vec3) {
+    self.eye = eye;
+    self.target = target;
+    self.up = up;
+  }
+
+  fn resize(&mut self, width: i32, height: i32) {
+    self.aspect = aspect_ratio(width, height);
+  }
+}
+
+// The visible region is `height` world units tall and as wide as the aspect
+// ratio allows, centered on the view direction
+pub struct OrthographicCamera {
+  pub eye: Vec3,
+  pub target: Vec3,
+  pub up: Vec3,
+  pub height: f32,
+  pub aspect: f32,
+  pub near: f32,
+  pub far: f32,
+}
+
+impl Default for OrthographicCamera {
+  fn default() -> OrthographicCamera {
+    OrthographicCamera::new(2.0, 1.0, -1.0, 1.0)
+  }
+}
+
+impl OrthographicCamera {
+  // Starts at the origin looking down -Z
+  pub fn new(height: f32, aspect: f32, near: f32, far: f32) -> OrthographicCamera {
+    if far <= near {
+      panic!("Invalid orthographic depth range {}..{}", near, far);
+    }
+    OrthographicCamera {
+      eye: Vec3::ZERO,
+      target: -Vec3::Z,
+      up: Vec3::Y,
+      height,
+      aspect,
+      near,
+      far,
+    }
+  }
+}
+
+impl Camera for OrthographicCamera {
+  fn view_matrix(&self) -> Mat4 {
+    Mat4::look_at(self.eye, self.target, self.up)
+  }
+
+  fn projection_matrix(&self) -> Mat4 {
+    let half_height = self.height * 0.5;
+    let half_width = half_height * self.aspect;
+    Mat4::orthographic(-half_width, half_width, -half_height, half_height, self.near, self.far)
+  }
+
+  fn position(&self) -> Vec3 {
+    self.eye
+  }
+
+  fn look_at(&mut self, eye: Vec3, target: Vec3, up: Vec3) {
+    self.eye = eye;
+    self.target = target;
+    self.up = up;
+  }
+
+  fn resize(&mut self, width: i32, height: i32) {
+    self.aspect = aspect_ratio(width, height);
+  }
+}
+
+// Uniform names that receive camera state. Set any of them to None to skip
+// that value.
+pub struct CameraUniforms {
+  pub view: Option<String>,
+  pub projection: Option<String>,
+  pub view_projection: Option<String>,
+  pub position: Option<String>,
+}
+
+impl Default for CameraUniforms {
+  fn default() -> CameraUniforms {
+    CameraUniforms::new()
+  }
+}
+
+impl CameraUniforms {
+  pub fn new() -> CameraUniforms {
+    CameraUniforms {
+      view: Some(String::from("u_view")),
+      projection: Some(String::from("u_projection")),
+      view_projection: Some(String::from("u_view_projection")),
+      position: Some(String::from("u_camera_position")),
+    }
+  }
+
+  // Uploads the camera's matrices to whichever of the uniforms the program
+  // declares
+  pub fn apply(&self, camera: &dyn Camera, program: &Program) {
+    if let Some(name) = &self.view {
+      program.set_uniform(name, &camera.view_matrix().into());
+    }
+    if let Some(name) = &self.projection {
+      program.set_uniform(name, &camera.projection_matrix().into());
+    }
+    if let Some(name) = &self.view_projection {
+      program.set_uniform(name, &camera.view_projection_matrix().into());
+    }
+    if let Some(name) = &self.position {
+      program.set_uniform(name, &camera.position().into());
+    }
+  }
+}
+
+// Direction for a yaw around +Y and a pitch above the horizon. Zero yaw
+// and pitch face -Z; positive yaw turns towards +X.
+fn direction(yaw: f32, pitch: f32) -> Vec3 {
+  let (sin_yaw, cos_yaw) = yaw.sin_cos();
+  let (sin_pitch, cos_pitch) = pitch.sin_cos();
+  Vec3::new(cos_pitch * sin_yaw, sin_pitch, -cos_pitch * cos_yaw)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct PointerState {
+  rotating: bool,
+  panning: bool,
+  last: Option<(f64, f64)>,
+}
+
+impl PointerState {
+  // Records a pointer position and returns the movement since the last one
+  fn moved(&mut self, x: f64, y: f64) -> Option<(f32, f32)> {
+    let delta = self.last.map(|(lx, ly)| ((x - lx) as f32, (y - ly) as f32));
+    self.last = Some((x, y));
+    delta
+  }
+}
+
+// Circles a target point, with yaw/pitch for rotation and distance for zoom
+pub struct OrbitController {
+  pub target: Vec3,
+  pub distance: f32,
+  pub yaw: f32,
+  pub pitch: f32,
+  pub min_distance: f32,
+  pub max_distance: f32,
+  // Radians per pixel of drag
+  pub rotate_speed: f32,
+  // Fraction of the distance covered per unit of zoom
+  pub zoom_speed: f32,
+  // World units per pixel of pan, scaled by the distance
+  pub pan_speed: f32,
+  pointer: PointerState,
+}
+
+impl Default for OrbitController {
+  fn default() -> OrbitController {
+    OrbitController::new(Vec3::ZERO, 5.0)
+  }
+}
+
+impl OrbitController {
+  pub fn new(target: Vec3, distance: f32) -> OrbitController {
+    OrbitController {
+      target,
+      distance,
+      yaw: 0.0,
+      pitch: 0.0,
+      min_distance: 0.01,
+      max_distance: f32::INFINITY,
+      rotate_speed: 0.005,
+      zoom_speed: 0.1,
+      pan_speed: 0.001,
+      pointer: PointerState::default(),
+    }
+  }
+
+  pub fn eye(&self) -> Vec3 {
+    self.target - direction(self.yaw, self.pitch) * self.distance
+  }
+
+  // Drag deltas in pixels; dragging right turns the camera to the right
+  pub fn rotate(&mut self, dx: f32, dy: f32) {
+    self.yaw += dx * self.rotate_speed;
+    self.pitch = (self.pitch - dy * self.rotate_speed).clamp(-MAX_PITCH, MAX_PITCH);
+  }
+
+  // Positive amounts move closer to the target
+  pub fn zoom(&mut self, amount: f32) {
+    let scale = (1.0 - amount * self.zoom_speed).max(0.01);
+    self.distance = (self.distance * scale).max(self.min_distance).min(self.max_distance);
+  }
+
+  // Moves the target in the view plane, so the scene follows the pointer
+  pub fn pan(&mut self, dx: f32, dy: f32) {
+    let forward = direction(self.yaw, self.pitch);
+    let right = forward.cross(Vec3::Y).normalize();
+    let up = right.cross(forward);
+    let scale = self.pan_speed * self.distance;
+    self.target = self.target - right * (dx * scale) + up * (dy * scale);
+  }
+
+  pub fn apply(&self, camera: &mut dyn Camera) {
+    camera.look_at(self.eye(), self.target, Vec3::Y);
+  }
+
+  // While set, pointer movement rotates around the target
+  pub fn set_rotating(&mut self, rotating: bool) {
+    self.pointer.rotating = rotating;
+  }
+
+  // While set, pointer movement pans; rotation takes priority
+  pub fn set_panning(&mut self, panning: bool) {
+    self.pointer.panning = panning;
+  }
+
+  // Feeds a pointer position in pixels from any input source
+  pub fn pointer_moved(&mut self, x: f64, y: f64) {
+    if let Some((dx, dy)) = self.pointer.moved(x, y) {
+      if self.pointer.rotating {
+        self.rotate(dx, dy);
+      } else if self.pointer.panning {
+        self.pan(dx, dy);
+      }
+    }
+  }
+
+  // Drops any held buttons, e.g. when the window loses focus
+  pub fn release(&mut self) {
+    self.pointer = PointerState::default();
+  }
+
+  // Left drag rotates, right drag pans and the wheel zooms
+  #[cfg(all(feature = "app", not(target_arch = "wasm32")))]
+  pub fn handle_event(&mut self, event: &glutin::WindowEvent) {
+    use glutin::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+    match event {
+      WindowEvent::MouseInput { state, button, .. } => {
+        let pressed = *state == ElementState::Pressed;
+        match button {
+          MouseButton::Left => self.set_rotating(pressed),
+          MouseButton::Right => self.set_panning(pressed),
+          _ => (),
+        }
+      },
+      WindowEvent::CursorMoved { position, .. } => self.pointer_moved(position.x, position.y),
+      WindowEvent::MouseWheel { delta, .. } => match delta {
+        MouseScrollDelta::LineDelta(_, y) => self.zoom(*y),
+        MouseScrollDelta::PixelDelta(p) => self.zoom(p.y as f32 / 20.0),
+      },
+      WindowEvent::Focused(false) => self.release(),
+      _ => (),
+    }
+  }
+}
+
+// First-person movement: yaw/pitch for looking and translation relative to
+// the view direction
+pub struct FlyController {
+  pub position: Vec3,
+  pub yaw: f32,
+  pub pitch: f32,
+  // World units per second
+  pub move_speed: f32,
+  // Radians per pixel of mouse movement
+  pub look_speed: f32,
+  // Continuous movement as (forward, right, up), each usually -1, 0 or 1
+  movement: [f32; 3],
+  pointer: PointerState,
+}
+
+impl Default for FlyController {
+  fn default() -> FlyController {
+    FlyController::new(Vec3::ZERO)
+  }
+}
+
+impl FlyController {
+  pub fn new(position: Vec3) -> FlyController {
+    FlyController {
+      position,
+      yaw: 0.0,
+      pitch: 0.0,
+      move_speed: 5.0,
+      look_speed: 0.003,
+      movement: [0.0; 3],
+      pointer: PointerState::default(),
+    }
+  }
+
+  pub fn forward(&self) -> Vec3 {
+    direction(self.yaw, self.pitch)
+  }
+
+  pub fn look(&mut self, dx: f32, dy: f32) {
+    self.yaw += dx * self.look_speed;
+    self.pitch = (self.pitch - dy * self.look_speed).clamp(-MAX_PITCH, MAX_PITCH);
+  }
+
+  // Moves by world-unit distances along the view direction, the camera's
+  // right and world up
+  pub fn translate(&mut self, forward: f32, right: f32, up: f32) {
+    let f = self.forward();
+    let r = f.cross(Vec3::Y).normalize();
+    self.position = self.position + f * forward + r * right + Vec3::Y * up;
+  }
+
+  // Sets the continuous movement applied by update, e.g. from held keys
+  pub fn set_movement(&mut self, forward: f32, right: f32, up: f32) {
+    self.movement = [forward, right, up];
+  }
+
+  // While set, pointer movement turns the view
+  pub fn set_looking(&mut self, looking: bool) {
+    self.pointer.rotating = looking;
+  }
+
+  // Feeds a pointer position in pixels from any input source
+  pub fn pointer_moved(&mut self, x: f64, y: f64) {
+    if let Some((dx, dy)) = self.pointer.moved(x, y) {
+      if self.pointer.rotating {
+        self.look(dx, dy);
+      }
+    }
+  }
+
+  // Stops all movement and drops any held buttons
+  pub fn release(&mut self) {
+    self.movement = [0.0; 3];
+    self.pointer = PointerState::default();
+  }
+
+  // Applies the current movement for a frame of `dt` seconds
+  pub fn update(&mut self, dt: f32) {
+    let [forward, right, up] = self.movement;
+    let step = self.move_speed * dt;
+    self.translate(forward * step, right * step, up * step);
+  }
+
+  pub fn apply(&self, camera: &mut dyn Camera) {
+    camera.look_at(self.position, self.position + self.forward(), Vec3::Y);
+  }
+
+  // WASD moves, Space and LShift rise and fall, and dragging with the left
+  // button looks around
+  #[cfg(all(feature = "app", not(target_arch = "wasm32")))]
+  pub fn handle_event(&mut self, event: &glutin::WindowEvent) {
+    use glutin::{ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+    match event {
+      WindowEvent::KeyboardInput { input, .. } => {
+        let amount = if input.state == ElementState::Pressed { 1.0 } else { 0.0 };
+        match input.virtual_keycode {
+          Some(VirtualKeyCode::W) => self.movement[0] = amount,
+          Some(VirtualKeyCode::S) => self.movement[0] = -amount,
+          Some(VirtualKeyCode::D) => self.movement[1] = amount,
+          Some(VirtualKeyCode::A) => self.movement[1] = -amount,
+          Some(VirtualKeyCode::Space) => self.movement[2] = amount,
+          Some(VirtualKeyCode::LShift) => self.movement[2] = -amount,
+          _ => (),
+        }
+      },
+      WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
+        self.set_looking(*state == ElementState::Pressed);
+      },
+      WindowEvent::CursorMoved { position, .. } => self.pointer_moved(position.x, position.y),
+      WindowEvent::Focused(false) => self.release(),
+      _ => (),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::{FRAC_PI_4, PI};
+
+  const EPSILON: f32 = 1e-4;
+
+  fn assert_vec3_eq(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < EPSILON, "{:?} != {:?}", a, b);
+  }
+
+  fn assert_close(a: f32, b: f32) {
+    assert!((a - b).abs() < EPSILON, "{} != {}", a, b);
+  }
+
+  #[test]
+  fn unproject_through_the_center_follows_the_view_direction() {
+    let mut camera = PerspectiveCamera::new(FRAC_PI_4, 1.0, 0.1, 100.0);
+    camera.look_at(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+    let ray = camera.unproject(50.0, 50.0, 100, 100);
+    assert_vec3_eq(ray.origin, Vec3::new(0.0, 0.0, 4.9));
+    assert_vec3_eq(ray.direction, -Vec3::Z);
+    assert_vec3_eq(ray.at(5.0), Vec3::new(0.0, 0.0, -0.1));
+  }
+
+  #[test]
+  fn unproject_starts_on_the_near_plane_and_reaches_the_far_corner() {
+    let camera = PerspectiveCamera::new(FRAC_PI_4, 2.0, 0.1, 100.0);
+    // Window y grows downwards, so the top left pixel is up and to the left
+    let ray = camera.unproject(0.0, 0.0, 200, 100);
+    let half_height = (FRAC_PI_4 * 0.5).tan();
+    let near = Vec3::new(-0.1 * half_height * 2.0, 0.1 * half_height, -0.1);
+    let far = Vec3::new(-100.0 * half_height * 2.0, 100.0 * half_height, -100.0);
+    assert_vec3_eq(ray.origin, near);
+    assert_vec3_eq(ray.direction, (far - near).normalize());
+    let ray = camera.unproject(200.0, 100.0, 200, 100);
+    assert!(ray.direction.x > 0.0 && ray.direction.y < 0.0, "{:?}", ray.direction);
+  }
+
+  #[test]
+  fn orthographic_rays_are_parallel() {
+    let mut camera = OrthographicCamera::new(4.0, 1.0, 0.5, 10.0);
+    camera.look_at(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+    camera.resize(200, 100);
+    let top_left = camera.unproject(0.0, 0.0, 200, 100);
+    let bottom_right = camera.unproject(200.0, 100.0, 200, 100);
+    assert_vec3_eq(top_left.origin, Vec3::new(-4.0, 2.0, 4.5));
+    assert_vec3_eq(bottom_right.origin, Vec3::new(4.0, -2.0, 4.5));
+    assert_vec3_eq(top_left.direction, -Vec3::Z);
+    assert_vec3_eq(bottom_right.direction, -Vec3::Z);
+  }
+
+  #[test]
+  fn perspective_resize_keeps_the_vertical_field_of_view() {
+    let mut camera = PerspectiveCamera::new(FRAC_PI_4, 1.0, 0.1, 100.0);
+    camera.resize(1920, 1080);
+    assert_close(camera.aspect, 1920.0 / 1080.0);
+    let half_height = (FRAC_PI_4 * 0.5).tan();
+    let corner = Vec3::new(half_height * camera.aspect, half_height, -1.0);
+    let ndc = camera.projection_matrix().project_point(corner);
+    assert_close(ndc.x, 1.0);
+    assert_close(ndc.y, 1.0);
+    // Degenerate sizes clamp instead of dividing by zero
+    camera.resize(0, 0);
+    assert_eq!(camera.aspect, 1.0);
+  }
+
+  #[test]
+  fn orthographic_resize_keeps_the_height() {
+    let mut camera = OrthographicCamera::new(4.0, 1.0, -1.0, 1.0);
+    camera.resize(300, 100);
+    let ndc = camera.projection_matrix().project_point(Vec3::new(6.0, 2.0, 0.0));
+    assert_close(ndc.x, 1.0);
+    assert_close(ndc.y, 1.0);
+  }
+
+  #[test]
+  fn orbit_pitch_stops_short_of_the_poles() {
+    let mut orbit = OrbitController::new(Vec3::ZERO, 5.0);
+    assert_vec3_eq(orbit.eye(), Vec3::new(0.0, 0.0, 5.0));
+    orbit.rotate(0.0, -100_000.0);
+    assert_eq!(orbit.pitch, MAX_PITCH);
+    orbit.rotate(0.0, 100_000.0);
+    assert_eq!(orbit.pitch, -MAX_PITCH);
+    assert_close((orbit.eye() - orbit.target).length(), 5.0);
+
+    let mut camera = PerspectiveCamera::default();
+    orbit.apply(&mut camera);
+    let view = camera.view_matrix().to_array();
+    assert!(view.iter().all(|v| v.is_finite()), "{:?}", view);
+  }
+
+  #[test]
+  fn orbit_zoom_respects_the_distance_limits() {
+    let mut orbit = OrbitController::new(Vec3::new(1.0, 2.0, 3.0), 5.0);
+    orbit.min_distance = 2.0;
+    orbit.max_distance = 20.0;
+    orbit.zoom(1.0);
+    assert_close(orbit.distance, 4.5);
+    orbit.zoom(1000.0);
+    assert_eq!(orbit.distance, 2.0);
+    for _ in 0..100 {
+      orbit.zoom(-5.0);
+    }
+    assert_eq!(orbit.distance, 20.0);
+    assert_close((orbit.eye() - orbit.target).length(), 20.0);
+  }
+
+  #[test]
+  fn fly_controller_moves_along_the_view_direction() {
+    let mut fly = FlyController::new(Vec3::ZERO);
+    assert_vec3_eq(fly.forward(), -Vec3::Z);
+    fly.translate(2.0, 1.0, 0.5);
+    assert_vec3_eq(fly.position, Vec3::new(1.0, 0.5, -2.0));
+
+    fly.yaw = PI * 0.5;
+    fly.position = Vec3::ZERO;
+    fly.set_movement(1.0, 0.0, 0.0);
+    fly.update(0.5);
+    assert_vec3_eq(fly.position, Vec3::new(2.5, 0.0, 0.0));
+    fly.release();
+    fly.update(0.5);
+    assert_vec3_eq(fly.position, Vec3::new(2.5, 0.0, 0.0));
+  }
+
+  #[test]
+  fn fly_pitch_is_clamped_and_only_changes_while_looking() {
+    let mut fly = FlyController::new(Vec3::ZERO);
+    fly.pointer_moved(0.0, 0.0);
+    fly.pointer_moved(0.0, -100.0);
+    assert_eq!(fly.pitch, 0.0);
+
+    fly.set_looking(true);
+    fly.pointer_moved(0.0, -100_000.0);
+    assert_eq!(fly.pitch, MAX_PITCH);
+    fly.look(0.0, 100_000.0);
+    assert_eq!(fly.pitch, -MAX_PITCH);
+    assert!(fly.forward().y > -1.0);
+  }
+}
@@ -0,0 +1,130 @@
+use super::{CompressedError, CompressedFormat, CompressedImage, MipLevel, Reader, check_mip_chain, mip_dimension};
+
+pub const DDS_MAGIC: &[u8] = b"DDS ";
+
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+const DX10_MISC_TEXTURECUBE: u32 = 0x4;
+
+fn fourcc(code: &[u8; 4]) -> u32 {
+  u32::from_le_bytes(*code)
+}
+
+fn format_from_fourcc(code: u32) -> Option<CompressedFormat> {
+  use super::CompressedFormat::*;
+  let format = if code == fourcc(b"DXT1") {
+    Bc1 { alpha: true, srgb: false }
+  } else if code == fourcc(b"DXT2") || code == fourcc(b"DXT3") {
+    Bc2 { srgb: false }
+  } else if code == fourcc(b"DXT4") || code == fourcc(b"DXT5") {
+    Bc3 { srgb: false }
+  } else if code == fourcc(b"ATI1") || code == fourcc(b"BC4U") {
+    Bc4 { signed: false }
+  } else if code == fourcc(b"BC4S") {
+    Bc4 { signed: true }
+  } else if code == fourcc(b"ATI2") || code == fourcc(b"BC5U") {
+    Bc5 { signed: false }
+  } else if code == fourcc(b"BC5S") {
+    Bc5 { signed: true }
+  } else {
+    return None;
+  };
+  Some(format)
+}
+
+fn format_from_dxgi(dxgi: u32) -> Option<CompressedFormat> {
+  use super::CompressedFormat::*;
+  let format = match dxgi {
+    70 | 71 => Bc1 { alpha: true, srgb: false },
+    72 => Bc1 { alpha: true, srgb: true },
+    73 | 74 => Bc2 { srgb: false },
+    75 => Bc2 { srgb: true },
+    76 | 77 => Bc3 { srgb: false },
+    78 => Bc3 { srgb: true },
+    79 | 80 => Bc4 { signed: false },
+    81 => Bc4 { signed: true },
+    82 | 83 => Bc5 { signed: false },
+    84 => Bc5 { signed: true },
+    94 | 95 => Bc6h { signed: false },
+    96 => Bc6h { signed: true },
+    97 | 98 => Bc7 { srgb: false },
+    99 => Bc7 { srgb: true },
+    _ => return None,
+  };
+  Some(format)
+}
+
+pub fn parse_dds(bytes: &[u8]) -> Result<CompressedImage, CompressedError> {
+  let mut reader = Reader::new(bytes);
+  reader.skip(DDS_MAGIC.len())?;
+  let header_size = reader.u32()?;
+  if header_size != 124 {
+    return Err(CompressedError::Corrupt(format!("header size is {}, expected 124", header_size)));
+  }
+  let _flags = reader.u32()?;
+  let height = reader.u32()?;
+  let width = reader.u32()?;
+  let _pitch = reader.u32()?;
+  let _depth = reader.u32()?;
+  let level_count = reader.u32()?.max(1) as usize;
+  reader.skip(11 * 4)?;
+  let _pf_size = reader.u32()?;
+  let pf_flags = reader.u32()?;
+  let pf_fourcc = reader.u32()?;
+  reader.skip(5 * 4)?;
+  let _caps = reader.u32()?;
+  let caps2 = reader.u32()?;
+  reader.skip(3 * 4)?;
+
+  if width == 0 || height == 0 {
+    return Err(CompressedError::Corrupt(format!("invalid size {}x{}", width, height)));
+  }
+  check_mip_chain(width, height, level_count)?;
+  if pf_flags & DDPF_FOURCC == 0 {
+    return Err(CompressedError::Unsupported(String::from("uncompressed DDS data")));
+  }
+  if caps2 & DDSCAPS2_VOLUME != 0 {
+    return Err(CompressedError::Unsupported(String::from("volume textures")));
+  }
+
+  let mut cube = caps2 & DDSCAPS2_CUBEMAP != 0;
+  let format = if pf_fourcc == fourcc(b"DX10") {
+    let dxgi = reader.u32()?;
+    let _dimension = reader.u32()?;
+    let misc = reader.u32()?;
+    let array_size = reader.u32()?;
+    let _misc2 = reader.u32()?;
+    if array_size > 1 {
+      return Err(CompressedError::Unsupported(String::from("texture arrays")));
+    }
+    cube = cube || misc & DX10_MISC_TEXTURECUBE != 0;
+    format_from_dxgi(dxgi).ok_or_else(|| CompressedError::Unsupported(format!("DXGI format {}", dxgi)))?
+  } else {
+    format_from_fourcc(pf_fourcc).ok_or_else(|| CompressedError::Unsupported(format!("FourCC {:#x}", pf_fourcc)))?
+  };
+
+  let face_count = if cube { 6 } else { 1 };
+  let mut levels: Vec<MipLevel> = (0..level_count).map(|level| {
+    MipLevel {
+      width: mip_dimension(width as i32, level),
+      height: mip_dimension(height as i32, level),
+      faces: Vec::with_capacity(face_count),
+    }
+  }).collect();
+  // DDS stores each face's full mip chain before the next face
+  for _ in 0..face_count {
+    for level in levels.iter_mut() {
+      let size = format.level_size(level.width, level.height);
+      level.faces.push(reader.bytes(size)?.to_vec());
+    }
+  }
+
+  Ok(CompressedImage {
+    format,
+    width: width as i32,
+    height: height as i32,
+    face_count,
+    levels,
+  })
+}
@@ -0,0 +1,168 @@
+use super::{CompressedError, CompressedFormat, CompressedImage, MipLevel, Reader, check_mip_chain, mip_dimension};
+
+pub const KTX1_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'1', b'1', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+pub const KTX2_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+
+const KTX1_ENDIANNESS: u32 = 0x04030201;
+
+fn check_layout(width: u32, height: u32, depth: u32, layers: u32, faces: u32, level_count: usize) -> Result<(), CompressedError> {
+  if width == 0 || height == 0 {
+    return Err(CompressedError::Corrupt(format!("invalid size {}x{}", width, height)));
+  }
+  if depth > 1 {
+    return Err(CompressedError::Unsupported(String::from("3D textures")));
+  }
+  if layers > 1 {
+    return Err(CompressedError::Unsupported(String::from("texture arrays")));
+  }
+  if faces != 1 && faces != 6 {
+    return Err(CompressedError::Corrupt(format!("invalid face count {}", faces)));
+  }
+  check_mip_chain(width, height, level_count)
+}
+
+pub fn parse_ktx1(bytes: &[u8]) -> Result<CompressedImage, CompressedError> {
+  let mut reader = Reader::new(bytes);
+  reader.skip(KTX1_IDENTIFIER.len())?;
+  let endianness = reader.u32()?;
+  if endianness != KTX1_ENDIANNESS {
+    reader.big_endian = true;
+  }
+  let gl_type = reader.u32()?;
+  let _type_size = reader.u32()?;
+  let _gl_format = reader.u32()?;
+  let internal = reader.u32()?;
+  let _base_internal = reader.u32()?;
+  let width = reader.u32()?;
+  let height = reader.u32()?;
+  let depth = reader.u32()?;
+  let layers = reader.u32()?;
+  let faces = reader.u32()?;
+  let level_count = reader.u32()?.max(1) as usize;
+  let key_value_length = reader.u32()?;
+  if gl_type != 0 {
+    return Err(CompressedError::Unsupported(String::from("uncompressed KTX data")));
+  }
+  let format = match CompressedFormat::from_gl_internal_format(internal) {
+    Some(f) => f,
+    None => return Err(CompressedError::Unsupported(format!("internal format {:#x}", internal))),
+  };
+  check_layout(width, height, depth, layers, faces, level_count)?;
+  reader.skip(key_value_length as usize)?;
+
+  let mut levels = Vec::with_capacity(level_count);
+  for level in 0..level_count {
+    let level_width = mip_dimension(width as i32, level);
+    let level_height = mip_dimension(height as i32, level);
+    // For non-array cube maps imageSize covers a single face
+    let image_size = reader.u32()? as usize;
+    let expected = format.level_size(level_width, level_height);
+    if image_size != expected * if faces == 6 { 1 } else { faces as usize } {
+      return Err(CompressedError::Corrupt(format!("level {} is {} bytes, expected {}", level, image_size, expected)));
+    }
+    let mut face_data = Vec::with_capacity(faces as usize);
+    for _ in 0..faces {
+      face_data.push(reader.bytes(expected)?.to_vec());
+      reader.skip((4 - expected % 4) % 4)?;
+    }
+    levels.push(MipLevel {
+      width: level_width,
+      height: level_height,
+      faces: face_data,
+    });
+  }
+
+  Ok(CompressedImage {
+    format,
+    width: width as i32,
+    height: height as i32,
+    face_count: faces as usize,
+    levels,
+  })
+}
+
+fn format_from_vk(vk_format: u32) -> Option<CompressedFormat> {
+  use super::CompressedFormat::*;
+  let srgb = vk_format & 1 == 0;
+  let format = match vk_format {
+    131..=134 => Bc1 { alpha: vk_format >= 133, srgb },
+    135 | 136 => Bc2 { srgb },
+    137 | 138 => Bc3 { srgb },
+    139 | 140 => Bc4 { signed: vk_format == 140 },
+    141 | 142 => Bc5 { signed: vk_format == 142 },
+    143 | 144 => Bc6h { signed: vk_format == 144 },
+    145 | 146 => Bc7 { srgb },
+    147 | 148 => Etc2Rgb8 { srgb },
+    149 | 150 => Etc2Rgb8A1 { srgb },
+    151 | 152 => Etc2Rgba8 { srgb },
+    153 | 154 => EacR11 { signed: vk_format == 154 },
+    155 | 156 => EacRg11 { signed: vk_format == 156 },
+    157..=184 => {
+      let index = (vk_format - 157) / 2;
+      let gl = if srgb { 0x93D0 } else { 0x93B0 } + index;
+      return CompressedFormat::from_gl_internal_format(gl);
+    },
+    _ => return None,
+  };
+  Some(format)
+}
+
+pub fn parse_ktx2(bytes: &[u8]) -> Result<CompressedImage, CompressedError> {
+  let mut reader = Reader::new(bytes);
+  reader.skip(KTX2_IDENTIFIER.len())?;
+  let vk_format = reader.u32()?;
+  let _type_size = reader.u32()?;
+  let width = reader.u32()?;
+  let height = reader.u32()?;
+  let depth = reader.u32()?;
+  let layers = reader.u32()?;
+  let faces = reader.u32()?;
+  let level_count = reader.u32()?.max(1) as usize;
+  let supercompression = reader.u32()?;
+  // Data format descriptor, key/value data and supercompression global data
+  reader.skip(4 * 4 + 8 * 2)?;
+  if supercompression != 0 {
+    return Err(CompressedError::Unsupported(format!("supercompression scheme {}", supercompression)));
+  }
+  let format = match format_from_vk(vk_format) {
+    Some(f) => f,
+    None => return Err(CompressedError::Unsupported(format!("vkFormat {}", vk_format))),
+  };
+  check_layout(width, height, depth, layers, faces, level_count)?;
+
+  let mut index = Vec::with_capacity(level_count);
+  for _ in 0..level_count {
+    let offset = reader.u64()? as usize;
+    let length = reader.u64()? as usize;
+    let _uncompressed_length = reader.u64()?;
+    index.push((offset, length));
+  }
+
+  let mut levels = Vec::with_capacity(level_count);
+  for (level, (offset, length)) in index.into_iter().enumerate() {
+    let level_width = mip_dimension(width as i32, level);
+    let level_height = mip_dimension(height as i32, level);
+    let face_size = format.level_size(level_width, level_height);
+    if length != face_size * faces as usize {
+      return Err(CompressedError::Corrupt(format!("level {} is {} bytes, expected {}", level, length, face_size * faces as usize)));
+    }
+    reader.seek(offset)?;
+    let mut face_data = Vec::with_capacity(faces as usize);
+    for _ in 0..faces {
+      face_data.push(reader.bytes(face_size)?.to_vec());
+    }
+    levels.push(MipLevel {
+      width: level_width,
+      height: level_height,
+      faces: face_data,
+    });
+  }
+
+  Ok(CompressedImage {
+    format,
+    width: width as i32,
+    height: height as i32,
+    face_count: faces as usize,
+    levels,
+  })
+}
@@ -0,0 +1,425 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use super::cube_texture::{CubeFace, CubeTexture};
+use super::gli;
+use super::texture::Texture;
+
+mod dds;
+mod ktx;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompressedFormat {
+  Bc1 { alpha: bool, srgb: bool },
+  Bc2 { srgb: bool },
+  Bc3 { srgb: bool },
+  Bc4 { signed: bool },
+  Bc5 { signed: bool },
+  Bc6h { signed: bool },
+  Bc7 { srgb: bool },
+  Etc2Rgb8 { srgb: bool },
+  Etc2Rgb8A1 { srgb: bool },
+  Etc2Rgba8 { srgb: bool },
+  EacR11 { signed: bool },
+  EacRg11 { signed: bool },
+  Astc { block_width: u8, block_height: u8, srgb: bool },
+}
+
+const ASTC_BLOCKS: [(u8, u8); 14] = [
+  (4, 4), (5, 4), (5, 5), (6, 5), (6, 6), (8, 5), (8, 6),
+  (8, 8), (10, 5), (10, 6), (10, 8), (10, 10), (12, 10), (12, 12),
+];
+
+impl CompressedFormat {
+  pub fn gl_internal_format(self) -> u32 {
+    use self::CompressedFormat::*;
+    match self {
+      Bc1 { alpha: false, srgb: false } => 0x83F0,
+      Bc1 { alpha: true, srgb: false } => 0x83F1,
+      Bc1 { alpha: false, srgb: true } => 0x8C4C,
+      Bc1 { alpha: true, srgb: true } => 0x8C4D,
+      Bc2 { srgb } => if srgb { 0x8C4E } else { 0x83F2 },
+      Bc3 { srgb } => if srgb { 0x8C4F } else { 0x83F3 },
+      Bc4 { signed } => if signed { 0x8DBC } else { 0x8DBB },
+      Bc5 { signed } => if signed { 0x8DBE } else { 0x8DBD },
+      Bc6h { signed } => if signed { 0x8E8E } else { 0x8E8F },
+      Bc7 { srgb } => if srgb { 0x8E8D } else { 0x8E8C },
+      EacR11 { signed } => if signed { 0x9271 } else { 0x9270 },
+      EacRg11 { signed } => if signed { 0x9273 } else { 0x9272 },
+      Etc2Rgb8 { srgb } => if srgb { 0x9275 } else { 0x9274 },
+      Etc2Rgb8A1 { srgb } => if srgb { 0x9277 } else { 0x9276 },
+      Etc2Rgba8 { srgb } => if srgb { 0x9279 } else { 0x9278 },
+      Astc { block_width, block_height, srgb } => {
+        let index = ASTC_BLOCKS.iter().position(|b| *b == (block_width, block_height)).unwrap_or(0) as u32;
+        if srgb { 0x93D0 + index } else { 0x93B0 + index }
+      },
+    }
+  }
+
+  pub fn from_gl_internal_format(internal: u32) -> Option<CompressedFormat> {
+    use self::CompressedFormat::*;
+    let format = match internal {
+      0x83F0 => Bc1 { alpha: false, srgb: false },
+      0x83F1 => Bc1 { alpha: true, srgb: false },
+      0x8C4C => Bc1 { alpha: false, srgb: true },
+      0x8C4D => Bc1 { alpha: true, srgb: true },
+      0x83F2 | 0x8C4E => Bc2 { srgb: internal == 0x8C4E },
+      0x83F3 | 0x8C4F => Bc3 { srgb: internal == 0x8C4F },
+      0x8DBB | 0x8DBC => Bc4 { signed: internal == 0x8DBC },
+      0x8DBD | 0x8DBE => Bc5 { signed: internal == 0x8DBE },
+      0x8E8E | 0x8E8F => Bc6h { signed: internal == 0x8E8E },
+      0x8E8C | 0x8E8D => Bc7 { srgb: internal == 0x8E8D },
+      0x9270 | 0x9271 => EacR11 { signed: internal == 0x9271 },
+      0x9272 | 0x9273 => EacRg11 { signed: internal == 0x9273 },
+      0x9274 | 0x9275 => Etc2Rgb8 { srgb: internal == 0x9275 },
+      0x9276 | 0x9277 => Etc2Rgb8A1 { srgb: internal == 0x9277 },
+      0x9278 | 0x9279 => Etc2Rgba8 { srgb: internal == 0x9279 },
+      0x93B0..=0x93BD | 0x93D0..=0x93DD => {
+        let srgb = internal >= 0x93D0;
+        let (block_width, block_height) = ASTC_BLOCKS[(internal & 0xF) as usize];
+        Astc { block_width, block_height, srgb }
+      },
+      _ => return None,
+    };
+    Some(format)
+  }
+
+  pub fn block_dimensions(self) -> (i32, i32) {
+    match self {
+      CompressedFormat::Astc { block_width, block_height, .. } => (block_width as i32, block_height as i32),
+      _ => (4, 4),
+    }
+  }
+
+  pub fn block_bytes(self) -> usize {
+    use self::CompressedFormat::*;
+    match self {
+      Bc1 { .. } | Bc4 { .. } | Etc2Rgb8 { .. } | Etc2Rgb8A1 { .. } | EacR11 { .. } => 8,
+      _ => 16,
+    }
+  }
+
+  pub fn level_size(self, width: i32, height: i32) -> usize {
+    let (bw, bh) = self.block_dimensions();
+    let blocks_x = ((width + bw - 1) / bw) as usize;
+    let blocks_y = ((height + bh - 1) / bh) as usize;
+    blocks_x * blocks_y * self.block_bytes()
+  }
+
+  pub fn is_supported(self) -> bool {
+    gli::supports_compressed_format(self.gl_internal_format())
+  }
+}
+
+pub struct MipLevel {
+  pub width: i32,
+  pub height: i32,
+  // One entry per face; 2D images have a single face
+  pub faces: Vec<Vec<u8>>,
+}
+
+pub struct CompressedImage {
+  pub format: CompressedFormat,
+  pub width: i32,
+  pub height: i32,
+  pub face_count: usize,
+  pub levels: Vec<MipLevel>,
+}
+
+#[derive(Debug)]
+pub enum CompressedError {
+  Io(io::Error),
+  UnknownContainer,
+  Unsupported(String),
+  Corrupt(String),
+  FormatNotSupported(CompressedFormat),
+}
+
+impl fmt::Display for CompressedError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CompressedError::Io(e) => write!(f, "Failed to read texture: {}", e),
+      CompressedError::UnknownContainer => write!(f, "Data is not a KTX, KTX2 or DDS container"),
+      CompressedError::Unsupported(msg) => write!(f, "Unsupported texture: {}", msg),
+      CompressedError::Corrupt(msg) => write!(f, "Corrupt texture: {}", msg),
+      CompressedError::FormatNotSupported(format) => write!(f, "{:?} is not supported by the current context", format),
+    }
+  }
+}
+
+impl Error for CompressedError {}
+
+impl From<io::Error> for CompressedError {
+  fn from(e: io::Error) -> CompressedError {
+    CompressedError::Io(e)
+  }
+}
+
+impl CompressedImage {
+  pub fn from_file<P: AsRef<Path>>(path: P) -> Result<CompressedImage, CompressedError> {
+    let bytes = fs::read(path)?;
+    CompressedImage::from_bytes(&bytes)
+  }
+
+  // Detects the container from its magic bytes
+  pub fn from_bytes(bytes: &[u8]) -> Result<CompressedImage, CompressedError> {
+    if bytes.starts_with(&ktx::KTX1_IDENTIFIER) {
+      ktx::parse_ktx1(bytes)
+    } else if bytes.starts_with(&ktx::KTX2_IDENTIFIER) {
+      ktx::parse_ktx2(bytes)
+    } else if bytes.starts_with(dds::DDS_MAGIC) {
+      dds::parse_dds(bytes)
+    } else {
+      Err(CompressedError::UnknownContainer)
+    }
+  }
+
+  pub fn is_cube_map(&self) -> bool {
+    self.face_count == 6
+  }
+
+  pub fn is_supported(&self) -> bool {
+    self.format.is_supported()
+  }
+
+  fn check_supported(&self) -> Result<(), CompressedError> {
+    if self.is_supported() {
+      Ok(())
+    } else {
+      Err(CompressedError::FormatNotSupported(self.format))
+    }
+  }
+}
+
+impl Texture {
+  // Uploads every mip level of a 2D compressed image. Returns
+  // FormatNotSupported, without touching GL state, when the context can't
+  // sample the format so callers can fall back to an uncompressed asset.
+  pub fn from_compressed(image: &CompressedImage) -> Result<Texture, CompressedError> {
+    if image.is_cube_map() {
+      return Err(CompressedError::Unsupported(String::from("cube map data cannot be loaded into a 2D texture")));
+    }
+    image.check_supported()?;
+    let mut texture = Texture::new();
+    let internal = image.format.gl_internal_format();
+    for (level, mip) in image.levels.iter().enumerate() {
+      texture.set_compressed_level(level as i32, internal, mip.width, mip.height, &mip.faces[0]);
+    }
+    Ok(texture)
+  }
+}
+
+impl CubeTexture {
+  pub fn from_compressed(image: &CompressedImage) -> Result<CubeTexture, CompressedError> {
+    if !image.is_cube_map() {
+      return Err(CompressedError::Unsupported(String::from("image does not contain six cube faces")));
+    }
+    if image.width != image.height {
+      return Err(CompressedError::Corrupt(format!("cube faces must be square, got {}x{}", image.width, image.height)));
+    }
+    image.check_supported()?;
+    let mut texture = CubeTexture::new();
+    let internal = image.format.gl_internal_format();
+    for (level, mip) in image.levels.iter().enumerate() {
+      for (face, data) in CubeFace::all().iter().zip(mip.faces.iter()) {
+        texture.set_compressed_face_level(*face, level as i32, internal, mip.width, data);
+      }
+    }
+    Ok(texture)
+  }
+}
+
+struct Reader<'a> {
+  data: &'a [u8],
+  pos: usize,
+  big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+  fn new(data: &'a [u8]) -> Reader<'a> {
+    Reader {
+      data,
+      pos: 0,
+      big_endian: false,
+    }
+  }
+
+  fn bytes(&mut self, length: usize) -> Result<&'a [u8], CompressedError> {
+    let end = self.pos.checked_add(length).filter(|end| *end <= self.data.len());
+    match end {
+      Some(end) => {
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+      },
+      None => Err(CompressedError::Corrupt(format!("unexpected end of data at byte {}", self.pos))),
+    }
+  }
+
+  fn skip(&mut self, length: usize) -> Result<(), CompressedError> {
+    self.bytes(length).map(|_| ())
+  }
+
+  fn seek(&mut self, pos: usize) -> Result<(), CompressedError> {
+    if pos > self.data.len() {
+      return Err(CompressedError::Corrupt(format!("offset {} is past the end of the data", pos)));
+    }
+    self.pos = pos;
+    Ok(())
+  }
+
+  fn u32(&mut self) -> Result<u32, CompressedError> {
+    let b = self.bytes(4)?;
+    let raw = [b[0], b[1], b[2], b[3]];
+    Ok(if self.big_endian { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) })
+  }
+
+  fn u64(&mut self) -> Result<u64, CompressedError> {
+    let low = self.u32()? as u64;
+    let high = self.u32()? as u64;
+    Ok(if self.big_endian { (low << 32) | high } else { (high << 32) | low })
+  }
+}
+
+// Rejects sizes that don't fit an i32 and level counts longer than the full
+// mip chain, which also keeps `mip_dimension` from shifting out of range
+fn check_mip_chain(width: u32, height: u32, level_count: usize) -> Result<(), CompressedError> {
+  if width > i32::MAX as u32 || height > i32::MAX as u32 {
+    return Err(CompressedError::Corrupt(format!("invalid size {}x{}", width, height)));
+  }
+  let max_levels = 32 - width.max(height).leading_zeros() as usize;
+  if level_count > max_levels {
+    return Err(CompressedError::Corrupt(format!("{} mip levels for a {}x{} image, at most {} fit", level_count, width, height, max_levels)));
+  }
+  Ok(())
+}
+
+fn mip_dimension(base: i32, level: usize) -> i32 {
+  (base >> level).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn push_u32s(bytes: &mut Vec<u8>, values: &[u32]) {
+    for value in values {
+      bytes.extend_from_slice(&value.to_le_bytes());
+    }
+  }
+
+  // Distinct bytes for each BC1 level of an 8x8 image: 2x2 blocks, then 1
+  fn bc1_levels(level_count: usize) -> Vec<Vec<u8>> {
+    (0..level_count).map(|level| {
+      let size = if level == 0 { 32 } else { 8 };
+      (0..size).map(|i| (level * 64 + i) as u8).collect()
+    }).collect()
+  }
+
+  fn dds(level_count: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = dds::DDS_MAGIC.to_vec();
+    push_u32s(&mut bytes, &[124, 0, 8, 8, 0, 0, level_count]);
+    push_u32s(&mut bytes, &[0; 11]);
+    push_u32s(&mut bytes, &[32, 0x4, u32::from_le_bytes(*b"DXT1"), 0, 0, 0, 0, 0]);
+    push_u32s(&mut bytes, &[0x1000, 0, 0, 0, 0]);
+    for level in levels {
+      bytes.extend_from_slice(level);
+    }
+    bytes
+  }
+
+  fn ktx1(level_count: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = ktx::KTX1_IDENTIFIER.to_vec();
+    push_u32s(&mut bytes, &[0x04030201, 0, 1, 0, 0x83F0, 0x1907, 8, 8, 0, 0, 1, level_count, 0]);
+    for level in levels {
+      push_u32s(&mut bytes, &[level.len() as u32]);
+      bytes.extend_from_slice(level);
+    }
+    bytes
+  }
+
+  fn ktx2(level_count: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = ktx::KTX2_IDENTIFIER.to_vec();
+    push_u32s(&mut bytes, &[131, 1, 8, 8, 0, 0, 1, level_count, 0]);
+    push_u32s(&mut bytes, &[0; 8]);
+    let mut offset = bytes.len() + levels.len() * 24;
+    for level in levels {
+      for value in &[offset as u64, level.len() as u64, level.len() as u64] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+      }
+      offset += level.len();
+    }
+    for level in levels {
+      bytes.extend_from_slice(level);
+    }
+    bytes
+  }
+
+  fn assert_round_trip(bytes: &[u8], format: CompressedFormat, levels: &[Vec<u8>]) {
+    let image = match CompressedImage::from_bytes(bytes) {
+      Ok(image) => image,
+      Err(e) => panic!("Failed to parse: {}", e),
+    };
+    assert_eq!(image.format, format);
+    assert_eq!((image.width, image.height, image.face_count), (8, 8, 1));
+    let sizes: Vec<(i32, i32)> = image.levels.iter().map(|l| (l.width, l.height)).collect();
+    assert_eq!(sizes, vec![(8, 8), (4, 4), (2, 2), (1, 1)]);
+    for (parsed, expected) in image.levels.iter().zip(levels) {
+      assert_eq!(&parsed.faces, &vec![expected.clone()]);
+    }
+  }
+
+  fn assert_corrupt(bytes: &[u8]) {
+    match CompressedImage::from_bytes(bytes) {
+      Err(CompressedError::Corrupt(_)) => {},
+      Err(e) => panic!("Expected a corrupt texture error, got: {}", e),
+      Ok(_) => panic!("Expected a corrupt texture error, got an image"),
+    }
+  }
+
+  #[test]
+  fn dds_round_trip() {
+    let levels = bc1_levels(4);
+    assert_round_trip(&dds(4, &levels), CompressedFormat::Bc1 { alpha: true, srgb: false }, &levels);
+  }
+
+  #[test]
+  fn ktx1_round_trip() {
+    let levels = bc1_levels(4);
+    assert_round_trip(&ktx1(4, &levels), CompressedFormat::Bc1 { alpha: false, srgb: false }, &levels);
+  }
+
+  #[test]
+  fn ktx2_round_trip() {
+    let levels = bc1_levels(4);
+    assert_round_trip(&ktx2(4, &levels), CompressedFormat::Bc1 { alpha: false, srgb: false }, &levels);
+  }
+
+  #[test]
+  fn truncated_headers_are_corrupt() {
+    let levels = bc1_levels(1);
+    for bytes in &[dds(1, &levels), ktx1(1, &levels), ktx2(1, &levels)] {
+      assert_corrupt(&bytes[..40]);
+    }
+  }
+
+  #[test]
+  fn truncated_level_data_is_corrupt() {
+    let levels = bc1_levels(4);
+    for bytes in &[dds(4, &levels), ktx1(4, &levels), ktx2(4, &levels)] {
+      assert_corrupt(&bytes[..bytes.len() - 1]);
+    }
+  }
+
+  #[test]
+  fn level_counts_past_the_mip_chain_are_corrupt() {
+    let levels = bc1_levels(4);
+    for level_count in &[5, 32, 33, u32::MAX] {
+      assert_corrupt(&dds(*level_count, &levels));
+      assert_corrupt(&ktx1(*level_count, &levels));
+      assert_corrupt(&ktx2(*level_count, &levels));
+    }
+  }
+}
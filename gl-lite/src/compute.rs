@@ -0,0 +1,59 @@
+use super::gli;
+
+pub struct StorageBuffer {
+  buffer: u32,
+  length: usize,
+}
+
+impl Default for StorageBuffer {
+  fn default() -> StorageBuffer {
+    StorageBuffer::new()
+  }
+}
+
+impl StorageBuffer {
+  pub fn new() -> StorageBuffer {
+    if !gli::supports_compute() {
+      panic!("Shader storage buffers are not supported by the current context");
+    }
+    StorageBuffer {
+      buffer: gli::create_buffer(),
+      length: 0,
+    }
+  }
+
+  pub fn buffer_data(&mut self, data: &[f32]) {
+    self.length = data.len();
+    gli::bind_shader_storage_buffer(self.buffer);
+    gli::shader_storage_buffer_data(data);
+  }
+
+  pub fn bind_to_index(&self, index: u32) {
+    gli::bind_shader_storage_buffer_base(index, self.buffer);
+  }
+
+  pub fn read_data(&self) -> Vec<f32> {
+    let mut data = vec![0.0; self.length];
+    gli::bind_shader_storage_buffer(self.buffer);
+    gli::get_shader_storage_buffer_data(&mut data);
+    data
+  }
+
+  pub fn len(&self) -> usize {
+    self.length
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.length == 0
+  }
+}
+
+impl Drop for StorageBuffer {
+  fn drop(&mut self) {
+    gli::delete_buffer(self.buffer);
+  }
+}
+
+pub fn memory_barrier(barriers: u32) {
+  gli::memory_barrier(barriers);
+}
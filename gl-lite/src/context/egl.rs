@@ -0,0 +1,273 @@
+use libloading::Library;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::Mutex;
+
+type EglDisplay = *mut c_void;
+type EglConfig = *mut c_void;
+type EglContextHandle = *mut c_void;
+type EglDevice = *mut c_void;
+
+type GetProcAddressFn = unsafe extern "system" fn(*const c_char) -> *const c_void;
+type GetPlatformDisplayFn = unsafe extern "system" fn(u32, *mut c_void, *const i32) -> EglDisplay;
+type QueryDevicesFn = unsafe extern "system" fn(i32, *mut EglDevice, *mut i32) -> u32;
+type QueryDeviceStringFn = unsafe extern "system" fn(EglDevice, i32) -> *const c_char;
+type InitializeFn = unsafe extern "system" fn(EglDisplay, *mut i32, *mut i32) -> u32;
+type BindApiFn = unsafe extern "system" fn(u32) -> u32;
+type ChooseConfigFn = unsafe extern "system" fn(EglDisplay, *const i32, *mut EglConfig, i32, *mut i32) -> u32;
+type CreateContextFn = unsafe extern "system" fn(EglDisplay, EglConfig, EglContextHandle, *const i32) -> EglContextHandle;
+type MakeCurrentFn = unsafe extern "system" fn(EglDisplay, *mut c_void, *mut c_void, EglContextHandle) -> u32;
+type DestroyContextFn = unsafe extern "system" fn(EglDisplay, EglContextHandle) -> u32;
+type TerminateFn = unsafe extern "system" fn(EglDisplay) -> u32;
+type QueryStringFn = unsafe extern "system" fn(EglDisplay, i32) -> *const c_char;
+type GetErrorFn = unsafe extern "system" fn() -> i32;
+
+const EGL_NONE: i32 = 0x3038;
+const EGL_EXTENSIONS: i32 = 0x3055;
+const EGL_RENDERABLE_TYPE: i32 = 0x3040;
+const EGL_OPENGL_BIT: i32 = 0x0008;
+const EGL_OPENGL_API: u32 = 0x30A2;
+const EGL_PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
+const EGL_PLATFORM_DEVICE_EXT: u32 = 0x313F;
+const EGL_CONTEXT_MAJOR_VERSION: i32 = 0x3098;
+const EGL_CONTEXT_MINOR_VERSION: i32 = 0x30FB;
+const EGL_CONTEXT_OPENGL_PROFILE_MASK: i32 = 0x30FD;
+const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: i32 = 0x1;
+
+struct EglFunctions {
+  get_proc_address: GetProcAddressFn,
+  initialize: InitializeFn,
+  bind_api: BindApiFn,
+  choose_config: ChooseConfigFn,
+  create_context: CreateContextFn,
+  make_current: MakeCurrentFn,
+  destroy_context: DestroyContextFn,
+  terminate: TerminateFn,
+  query_string: QueryStringFn,
+  get_error: GetErrorFn,
+}
+
+// An OpenGL context on Mesa's surfaceless EGL platform, or on its software
+// EGL device. It has no default framebuffer, so everything has to be drawn
+// into framebuffer objects.
+pub struct EglContext {
+  _library: Library,
+  functions: EglFunctions,
+  display: EglDisplay,
+  context: EglContextHandle,
+}
+
+// Platform displays are shared by everything in the process, so each one is
+// counted here and only terminated when its last context is dropped. The
+// lock is held while initializing and terminating so the two can't race.
+static DISPLAY_USERS: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+unsafe fn acquire_display(functions: &EglFunctions, display: EglDisplay) -> Result<(), String> {
+  let mut users = DISPLAY_USERS.lock().unwrap_or_else(|e| e.into_inner());
+  match users.iter_mut().find(|(d, _)| *d == display as usize) {
+    Some((_, count)) => *count += 1,
+    None => {
+      let (mut major, mut minor) = (0, 0);
+      if (functions.initialize)(display, &mut major, &mut minor) == 0 {
+        return Err(format!("eglInitialize failed with error {:#x}", (functions.get_error)()));
+      }
+      users.push((display as usize, 1));
+    },
+  }
+  Ok(())
+}
+
+unsafe fn release_display(functions: &EglFunctions, display: EglDisplay) {
+  let mut users = DISPLAY_USERS.lock().unwrap_or_else(|e| e.into_inner());
+  if let Some(index) = users.iter().position(|(d, _)| *d == display as usize) {
+    users[index].1 -= 1;
+    if users[index].1 == 0 {
+      users.remove(index);
+      (functions.terminate)(display);
+    }
+  }
+}
+
+fn load_library() -> Result<Library, String> {
+  Library::new("libEGL.so.1")
+    .or_else(|_| Library::new("libEGL.so"))
+    .map_err(|e| format!("could not load libEGL: {}", e))
+}
+
+unsafe fn load_functions(library: &Library) -> Result<EglFunctions, String> {
+  macro_rules! symbol {
+    ($name:expr) => {
+      *library.get($name).map_err(|e| format!("libEGL is missing a symbol: {}", e))?
+    };
+  }
+  Ok(EglFunctions {
+    get_proc_address: symbol!(b"eglGetProcAddress\0"),
+    initialize: symbol!(b"eglInitialize\0"),
+    bind_api: symbol!(b"eglBindAPI\0"),
+    choose_config: symbol!(b"eglChooseConfig\0"),
+    create_context: symbol!(b"eglCreateContext\0"),
+    make_current: symbol!(b"eglMakeCurrent\0"),
+    destroy_context: symbol!(b"eglDestroyContext\0"),
+    terminate: symbol!(b"eglTerminate\0"),
+    query_string: symbol!(b"eglQueryString\0"),
+    get_error: symbol!(b"eglGetError\0"),
+  })
+}
+
+unsafe fn extensions(functions: &EglFunctions, display: EglDisplay) -> String {
+  let raw = (functions.query_string)(display, EGL_EXTENSIONS);
+  if raw.is_null() {
+    String::new()
+  } else {
+    CStr::from_ptr(raw).to_string_lossy().into_owned()
+  }
+}
+
+unsafe fn extension_function(functions: &EglFunctions, name: &str) -> Result<*const c_void, String> {
+  let symbol = CString::new(name).unwrap();
+  let function = (functions.get_proc_address)(symbol.as_ptr());
+  if function.is_null() {
+    return Err(format!("{} is not available", name));
+  }
+  Ok(function)
+}
+
+unsafe fn surfaceless_display(functions: &EglFunctions) -> Result<EglDisplay, String> {
+  if !extensions(functions, ptr::null_mut()).contains("EGL_MESA_platform_surfaceless") {
+    return Err(String::from("EGL_MESA_platform_surfaceless is not available"));
+  }
+  let get_platform_display: GetPlatformDisplayFn = std::mem::transmute(extension_function(functions, "eglGetPlatformDisplayEXT")?);
+  let no_attributes = [EGL_NONE];
+  let display = get_platform_display(EGL_PLATFORM_SURFACELESS_MESA, ptr::null_mut(), no_attributes.as_ptr());
+  if display.is_null() {
+    return Err(String::from("could not open a surfaceless EGL display"));
+  }
+  Ok(display)
+}
+
+// Opens the display of the device Mesa marks with EGL_MESA_device_software,
+// which always renders with llvmpipe or softpipe whatever GPUs are present
+unsafe fn software_display(functions: &EglFunctions) -> Result<EglDisplay, String> {
+  let client_extensions = extensions(functions, ptr::null_mut());
+  for extension in &["EGL_EXT_device_enumeration", "EGL_EXT_platform_device"] {
+    if !client_extensions.contains(extension) {
+      return Err(format!("{} is not available", extension));
+    }
+  }
+  let query_devices: QueryDevicesFn = std::mem::transmute(extension_function(functions, "eglQueryDevicesEXT")?);
+  let query_device_string: QueryDeviceStringFn = std::mem::transmute(extension_function(functions, "eglQueryDeviceStringEXT")?);
+  let get_platform_display: GetPlatformDisplayFn = std::mem::transmute(extension_function(functions, "eglGetPlatformDisplayEXT")?);
+
+  let mut count = 0;
+  if query_devices(0, ptr::null_mut(), &mut count) == 0 || count < 1 {
+    return Err(String::from("no EGL devices were found"));
+  }
+  let mut devices = vec![ptr::null_mut(); count as usize];
+  query_devices(count, devices.as_mut_ptr(), &mut count);
+  devices.truncate(count.max(0) as usize);
+  let device = devices.into_iter().find(|device| {
+    let raw = query_device_string(*device, EGL_EXTENSIONS);
+    !raw.is_null() && CStr::from_ptr(raw).to_string_lossy().contains("EGL_MESA_device_software")
+  });
+  let device = match device {
+    Some(device) => device,
+    None => return Err(String::from("no EGL device supports EGL_MESA_device_software")),
+  };
+  let no_attributes = [EGL_NONE];
+  let display = get_platform_display(EGL_PLATFORM_DEVICE_EXT, device, no_attributes.as_ptr());
+  if display.is_null() {
+    return Err(String::from("could not open the software EGL device"));
+  }
+  Ok(display)
+}
+
+impl EglContext {
+  // Creates an OpenGL 3.3 core context, or the best context the driver offers
+  // if it refuses that request
+  pub fn new() -> Result<EglContext, String> {
+    EglContext::on_display(surfaceless_display)
+  }
+
+  // Like new, but always renders in software so results don't depend on the
+  // machine's GPU
+  pub fn software() -> Result<EglContext, String> {
+    EglContext::on_display(software_display)
+  }
+
+  fn on_display(open: unsafe fn(&EglFunctions) -> Result<EglDisplay, String>) -> Result<EglContext, String> {
+    let library = load_library()?;
+    unsafe {
+      let functions = load_functions(&library)?;
+      let display = open(&functions)?;
+      let no_attributes = [EGL_NONE];
+      acquire_display(&functions, display)?;
+      if (functions.bind_api)(EGL_OPENGL_API) == 0 {
+        release_display(&functions, display);
+        return Err(String::from("the EGL driver does not support desktop OpenGL"));
+      }
+
+      let config_attributes = [EGL_RENDERABLE_TYPE, EGL_OPENGL_BIT, EGL_NONE];
+      let mut config: EglConfig = ptr::null_mut();
+      let mut config_count = 0;
+      (functions.choose_config)(display, config_attributes.as_ptr(), &mut config, 1, &mut config_count);
+      if config_count == 0 && !extensions(&functions, display).contains("EGL_KHR_no_config_context") {
+        release_display(&functions, display);
+        return Err(String::from("no EGL config supports OpenGL"));
+      }
+
+      let core_attributes = [
+        EGL_CONTEXT_MAJOR_VERSION, 3,
+        EGL_CONTEXT_MINOR_VERSION, 3,
+        EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+        EGL_NONE,
+      ];
+      let mut context = (functions.create_context)(display, config, ptr::null_mut(), core_attributes.as_ptr());
+      if context.is_null() {
+        context = (functions.create_context)(display, config, ptr::null_mut(), no_attributes.as_ptr());
+      }
+      if context.is_null() {
+        let error = (functions.get_error)();
+        release_display(&functions, display);
+        return Err(format!("eglCreateContext failed with error {:#x}", error));
+      }
+      Ok(EglContext {
+        _library: library,
+        functions,
+        display,
+        context,
+      })
+    }
+  }
+
+  pub fn make_current(&self) -> Result<(), String> {
+    let result = unsafe {
+      (self.functions.make_current)(self.display, ptr::null_mut(), ptr::null_mut(), self.context)
+    };
+    if result == 0 {
+      let error = unsafe { (self.functions.get_error)() };
+      return Err(format!("eglMakeCurrent failed with error {:#x}", error));
+    }
+    Ok(())
+  }
+
+  pub fn get_proc_address(&self, symbol: &str) -> *const c_void {
+    let name = match CString::new(symbol) {
+      Ok(name) => name,
+      Err(_) => return ptr::null(),
+    };
+    unsafe {
+      (self.functions.get_proc_address)(name.as_ptr())
+    }
+  }
+}
+
+impl Drop for EglContext {
+  fn drop(&mut self) {
+    unsafe {
+      (self.functions.make_current)(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+      (self.functions.destroy_context)(self.display, self.context);
+      release_display(&self.functions, self.display);
+    }
+  }
+}
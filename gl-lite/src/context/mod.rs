@@ -0,0 +1,258 @@
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "app")]
+use glutin::{Api, ContextBuilder, ContextTrait, GlProfile, GlRequest};
+#[cfg(feature = "app")]
+use glutin::dpi::PhysicalSize;
+use super::framebuffer::Framebuffer;
+use super::readback::{PixelData, PixelRect};
+use super::gli;
+
+#[cfg(target_os = "linux")]
+mod egl;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeadlessBackend {
+  // Mesa's surfaceless EGL platform; uses the GPU when one is present and
+  // llvmpipe otherwise, and needs no display server
+  EglSurfaceless,
+  // Mesa's software EGL device, which renders with llvmpipe or softpipe even
+  // when a GPU is present
+  EglSoftware,
+  // Mesa's off-screen software renderer
+  OsMesa,
+  // The platform's windowing system, which needs a display connection
+  Native,
+}
+
+impl HeadlessBackend {
+  // Backends in the order HeadlessContext::new tries them
+  pub fn all() -> [HeadlessBackend; 3] {
+    [HeadlessBackend::EglSurfaceless, HeadlessBackend::OsMesa, HeadlessBackend::Native]
+  }
+
+  // Backends in the order HeadlessContext::software tries them
+  pub fn software() -> [HeadlessBackend; 2] {
+    [HeadlessBackend::EglSoftware, HeadlessBackend::OsMesa]
+  }
+}
+
+#[derive(Debug)]
+pub enum ContextError {
+  Unavailable(String),
+  Creation(String),
+}
+
+impl fmt::Display for ContextError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ContextError::Unavailable(msg) => write!(f, "No headless context available: {}", msg),
+      ContextError::Creation(msg) => write!(f, "Failed to create headless context: {}", msg),
+    }
+  }
+}
+
+impl Error for ContextError {}
+
+#[cfg(feature = "app")]
+struct GlutinContext {
+  context: glutin::Context,
+  // Kept alive for contexts that borrow the loop's display connection
+  _events_loop: Option<glutin::EventsLoop>,
+}
+
+enum Inner {
+  #[cfg(target_os = "linux")]
+  Egl(egl::EglContext),
+  #[cfg(feature = "app")]
+  Glutin(Box<GlutinContext>),
+}
+
+impl Inner {
+  fn make_current(&self) -> Result<(), ContextError> {
+    match self {
+      #[cfg(target_os = "linux")]
+      Inner::Egl(context) => context.make_current().map_err(ContextError::Creation),
+      #[cfg(feature = "app")]
+      Inner::Glutin(glutin) => unsafe {
+        glutin.context.make_current().map_err(|e| ContextError::Creation(format!("{:?}", e)))
+      },
+    }
+  }
+
+  fn load_gl(&self) {
+    match self {
+      #[cfg(target_os = "linux")]
+      Inner::Egl(context) => gl::load_with(|symbol| context.get_proc_address(symbol)),
+      #[cfg(feature = "app")]
+      Inner::Glutin(glutin) => gl::load_with(|symbol| glutin.context.get_proc_address(symbol) as *const _),
+    }
+  }
+}
+
+#[cfg(feature = "app")]
+fn context_builder<'a>() -> ContextBuilder<'a> {
+  ContextBuilder::new()
+    .with_gl(GlRequest::Specific(Api::OpenGl, (3, 3)))
+    .with_gl_profile(GlProfile::Core)
+}
+
+#[cfg(feature = "app")]
+fn physical_size(width: i32, height: i32) -> PhysicalSize {
+  PhysicalSize::new(width as f64, height as f64)
+}
+
+#[cfg(target_os = "linux")]
+fn create_egl(software: bool) -> Result<Inner, ContextError> {
+  let context = if software { egl::EglContext::software() } else { egl::EglContext::new() };
+  context.map(Inner::Egl).map_err(ContextError::Unavailable)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn create_egl(_software: bool) -> Result<Inner, ContextError> {
+  Err(ContextError::Unavailable(String::from("EGL headless contexts are only supported on Linux")))
+}
+
+#[cfg(all(unix, feature = "app"))]
+fn create_osmesa(width: i32, height: i32) -> Result<Inner, ContextError> {
+  use glutin::os::unix::OsMesaContextExt;
+  glutin::Context::new_osmesa(context_builder(), physical_size(width, height))
+    .map(|context| Inner::Glutin(Box::new(GlutinContext { context, _events_loop: None })))
+    .map_err(|e| ContextError::Unavailable(format!("OSMesa: {}", e)))
+}
+
+#[cfg(all(unix, not(feature = "app")))]
+fn create_osmesa(_width: i32, _height: i32) -> Result<Inner, ContextError> {
+  Err(ContextError::Unavailable(String::from("OSMesa contexts need the app feature")))
+}
+
+#[cfg(not(unix))]
+fn create_osmesa(_width: i32, _height: i32) -> Result<Inner, ContextError> {
+  Err(ContextError::Unavailable(String::from("OSMesa is only supported on Unix platforms")))
+}
+
+#[cfg(feature = "app")]
+fn create_native(width: i32, height: i32) -> Result<Inner, ContextError> {
+  // EventsLoop::new panics without a display server, so open X11 explicitly
+  #[cfg(all(unix, not(target_os = "macos")))]
+  let events_loop = {
+    use glutin::os::unix::EventsLoopExt;
+    glutin::EventsLoop::new_x11().map_err(|e| ContextError::Unavailable(format!("no X11 display: {:?}", e)))?
+  };
+  #[cfg(not(all(unix, not(target_os = "macos"))))]
+  let events_loop = glutin::EventsLoop::new();
+  context_builder()
+    .build_headless(&events_loop, physical_size(width, height))
+    .map(|context| Inner::Glutin(Box::new(GlutinContext { context, _events_loop: Some(events_loop) })))
+    .map_err(|e| ContextError::Creation(format!("{}", e)))
+}
+
+#[cfg(not(feature = "app"))]
+fn create_native(_width: i32, _height: i32) -> Result<Inner, ContextError> {
+  Err(ContextError::Unavailable(String::from("native contexts need the app feature")))
+}
+
+// A GL context with no window, rendering into an offscreen framebuffer of a
+// fixed size. The framebuffer is bound on creation, so draws land in it
+// without any further setup.
+pub struct HeadlessContext {
+  // Fields drop in order, so the framebuffer goes while the context is alive
+  framebuffer: Framebuffer,
+  inner: Inner,
+  backend: HeadlessBackend,
+}
+
+impl HeadlessContext {
+  // Tries each backend in turn, returning the first that works
+  pub fn new(width: i32, height: i32) -> Result<HeadlessContext, ContextError> {
+    HeadlessContext::first_of(&HeadlessBackend::all(), width, height)
+  }
+
+  // Like new, but only tries software rasterizers, so the output is the same
+  // on every machine with the same Mesa version
+  pub fn software(width: i32, height: i32) -> Result<HeadlessContext, ContextError> {
+    HeadlessContext::first_of(&HeadlessBackend::software(), width, height)
+  }
+
+  fn first_of(backends: &[HeadlessBackend], width: i32, height: i32) -> Result<HeadlessContext, ContextError> {
+    let mut failures = Vec::new();
+    for backend in backends.iter() {
+      match HeadlessContext::with_backend(*backend, width, height) {
+        Ok(context) => return Ok(context),
+        Err(e) => failures.push(format!("{:?}: {}", backend, e)),
+      }
+    }
+    Err(ContextError::Unavailable(failures.join("; ")))
+  }
+
+  pub fn with_backend(backend: HeadlessBackend, width: i32, height: i32) -> Result<HeadlessContext, ContextError> {
+    if width < 1 || height < 1 {
+      panic!("Cannot create a headless context of size {}x{}", width, height);
+    }
+    let inner = match backend {
+      HeadlessBackend::EglSurfaceless => create_egl(false)?,
+      HeadlessBackend::EglSoftware => create_egl(true)?,
+      HeadlessBackend::OsMesa => create_osmesa(width, height)?,
+      HeadlessBackend::Native => create_native(width, height)?,
+    };
+    inner.make_current()?;
+    inner.load_gl();
+    let framebuffer = Framebuffer::new(width, height);
+    if !framebuffer.is_complete() {
+      return Err(ContextError::Creation(String::from("offscreen framebuffer is incomplete")));
+    }
+    framebuffer.bind();
+    Ok(HeadlessContext {
+      framebuffer,
+      inner,
+      backend,
+    })
+  }
+
+  pub fn backend(&self) -> HeadlessBackend {
+    self.backend
+  }
+
+  pub fn width(&self) -> i32 {
+    self.framebuffer.width()
+  }
+
+  pub fn height(&self) -> i32 {
+    self.framebuffer.height()
+  }
+
+  // Makes this the current context on the calling thread and rebinds its
+  // framebuffer
+  pub fn make_current(&self) -> Result<(), ContextError> {
+    self.inner.make_current()?;
+    self.framebuffer.bind();
+    Ok(())
+  }
+
+  pub fn framebuffer(&self) -> &Framebuffer {
+    &self.framebuffer
+  }
+
+  pub fn resize(&mut self, width: i32, height: i32) {
+    self.framebuffer.resize(width, height);
+    self.framebuffer.bind();
+  }
+
+  pub fn clear(&self, color: [f32; 4]) {
+    self.framebuffer.clear(color);
+  }
+
+  // Reads the whole frame as RGBA8, bottom row first
+  pub fn read_pixels(&self) -> PixelData {
+    let rect = PixelRect::new(0, 0, self.width(), self.height());
+    self.framebuffer.read_pixels(0, rect, gli::RGBA)
+  }
+}
+
+impl Drop for HeadlessContext {
+  // The framebuffer is deleted in whichever context is current, which may
+  // belong to another HeadlessContext
+  fn drop(&mut self) {
+    let _ = self.inner.make_current();
+  }
+}
@@ -0,0 +1,155 @@
+use super::gli;
+use super::texture::{FilterMode, UnpackLayout, WrapMode, bytes_per_pixel, full_mip_count};
+use super::uniforms::UniformValue;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CubeFace {
+  PositiveX,
+  NegativeX,
+  PositiveY,
+  NegativeY,
+  PositiveZ,
+  NegativeZ,
+}
+
+impl CubeFace {
+  pub fn all() -> [CubeFace; 6] {
+    [
+      CubeFace::PositiveX,
+      CubeFace::NegativeX,
+      CubeFace::PositiveY,
+      CubeFace::NegativeY,
+      CubeFace::PositiveZ,
+      CubeFace::NegativeZ,
+    ]
+  }
+
+  pub fn gl_target(self) -> u32 {
+    match self {
+      CubeFace::PositiveX => gli::TEXTURE_CUBE_MAP_POSITIVE_X,
+      CubeFace::NegativeX => gli::TEXTURE_CUBE_MAP_NEGATIVE_X,
+      CubeFace::PositiveY => gli::TEXTURE_CUBE_MAP_POSITIVE_Y,
+      CubeFace::NegativeY => gli::TEXTURE_CUBE_MAP_NEGATIVE_Y,
+      CubeFace::PositiveZ => gli::TEXTURE_CUBE_MAP_POSITIVE_Z,
+      CubeFace::NegativeZ => gli::TEXTURE_CUBE_MAP_NEGATIVE_Z,
+    }
+  }
+}
+
+pub struct CubeTexture {
+  gl_texture: u32,
+  size: i32,
+  internal_format: u32,
+  format: u32,
+  mip_levels: i32,
+}
+
+impl Default for CubeTexture {
+  fn default() -> CubeTexture {
+    CubeTexture::new()
+  }
+}
+
+impl CubeTexture {
+  pub fn new() -> CubeTexture {
+    let tex = gli::create_texture();
+    gli::bind_texture(gli::TEXTURE_CUBE_MAP, tex);
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_WRAP_S, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_WRAP_T, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_WRAP_R, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_MIN_FILTER, gli::LINEAR);
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_MAG_FILTER, gli::LINEAR);
+    CubeTexture {
+      gl_texture: tex,
+      size: 0,
+      internal_format: gli::RGBA,
+      format: gli::RGBA,
+      mip_levels: 1,
+    }
+  }
+
+  pub fn size(&self) -> i32 {
+    self.size
+  }
+
+  pub fn internal_format(&self) -> u32 {
+    self.internal_format
+  }
+
+  pub fn format(&self) -> u32 {
+    self.format
+  }
+
+  pub fn mip_levels(&self) -> i32 {
+    self.mip_levels
+  }
+
+  pub fn set_wrap_mode(&self, s: WrapMode, t: WrapMode, r: WrapMode) {
+    gli::bind_texture(gli::TEXTURE_CUBE_MAP, self.gl_texture);
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_WRAP_S, s.gl_wrap());
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_WRAP_T, t.gl_wrap());
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_WRAP_R, r.gl_wrap());
+  }
+
+  pub fn set_filter_mode(&self, min: FilterMode, mag: FilterMode) {
+    if mag.uses_mipmaps() {
+      panic!("Magnification filter cannot use mipmaps, got {:?}", mag);
+    }
+    gli::bind_texture(gli::TEXTURE_CUBE_MAP, self.gl_texture);
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_MIN_FILTER, min.gl_filter());
+    gli::tex_parameter(gli::TEXTURE_CUBE_MAP, gli::TEXTURE_MAG_FILTER, mag.gl_filter());
+  }
+
+  // Every face must share the size and format of the first face uploaded
+  pub fn set_face_from_bytes(&mut self, face: CubeFace, internal: u32, size: i32, format: u32, data: &[u8]) {
+    if size < 1 {
+      panic!("Cannot create a cube map face of size {}", size);
+    }
+    if self.size != 0 && (self.size != size || self.internal_format != internal) {
+      panic!("Cube map faces must all be {}x{} with format {:#x}", self.size, self.size, self.internal_format);
+    }
+    let expected = size as usize * size as usize * bytes_per_pixel(format);
+    if data.len() != expected {
+      panic!("Expected {} bytes for a {}x{} cube map face, got {}", expected, size, size, data.len());
+    }
+    gli::bind_texture(gli::TEXTURE_CUBE_MAP, self.gl_texture);
+    UnpackLayout::tightly_packed().apply();
+    gli::tex_image_cube_face_from_bytes(face.gl_target(), internal, size, format, data);
+    UnpackLayout::default().apply();
+    self.size = size;
+    self.internal_format = internal;
+    self.format = format;
+  }
+
+  pub(crate) fn set_compressed_face_level(&mut self, face: CubeFace, level: i32, internal: u32, size: i32, data: &[u8]) {
+    gli::bind_texture(gli::TEXTURE_CUBE_MAP, self.gl_texture);
+    gli::compressed_tex_image_2d(face.gl_target(), level, internal, size, size, data);
+    if level == 0 {
+      self.size = size;
+      self.internal_format = internal;
+      self.format = internal;
+    }
+    self.mip_levels = self.mip_levels.max(level + 1);
+  }
+
+  pub fn generate_mipmaps(&mut self) {
+    gli::bind_texture(gli::TEXTURE_CUBE_MAP, self.gl_texture);
+    gli::generate_mipmap(gli::TEXTURE_CUBE_MAP);
+    self.mip_levels = full_mip_count(self.size, self.size);
+  }
+
+  pub fn as_uniform_value(&self) -> UniformValue {
+    UniformValue::TextureCube(self.gl_texture)
+  }
+
+  pub fn bind_to_slot(&self, slot: u32) {
+    gli::active_texture(slot);
+    gli::bind_texture(gli::TEXTURE_CUBE_MAP, self.gl_texture);
+  }
+}
+
+impl Drop for CubeTexture {
+  fn drop(&mut self) {
+    gli::delete_texture(self.gl_texture);
+  }
+}
@@ -0,0 +1,174 @@
+use super::gli;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channels {
+  R,
+  RG,
+  RGB,
+  RGBA,
+}
+
+impl Channels {
+  pub fn count(self) -> usize {
+    match self {
+      Channels::R => 1,
+      Channels::RG => 2,
+      Channels::RGB => 3,
+      Channels::RGBA => 4,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextureFormat {
+  Unorm8(Channels),
+  Float16(Channels),
+  Float32(Channels),
+  Uint8(Channels),
+  Uint16(Channels),
+  Uint32(Channels),
+  Int8(Channels),
+  Int16(Channels),
+  Int32(Channels),
+  Srgb8,
+  Srgb8Alpha8,
+  Depth16,
+  Depth24,
+  Depth32F,
+  Depth24Stencil8,
+  Depth32FStencil8,
+}
+
+impl TextureFormat {
+  pub fn internal_format(self) -> u32 {
+    use self::TextureFormat::*;
+    match self {
+      Unorm8(c) => pick(c, gli::R8, gli::RG8, gli::RGB8, gli::RGBA8),
+      Float16(c) => pick(c, gli::R16F, gli::RG16F, gli::RGB16F, gli::RGBA16F),
+      Float32(c) => pick(c, gli::R32F, gli::RG32F, gli::RGB32F, gli::RGBA32F),
+      Uint8(c) => pick(c, gli::R8UI, gli::RG8UI, gli::RGB8UI, gli::RGBA8UI),
+      Uint16(c) => pick(c, gli::R16UI, gli::RG16UI, gli::RGB16UI, gli::RGBA16UI),
+      Uint32(c) => pick(c, gli::R32UI, gli::RG32UI, gli::RGB32UI, gli::RGBA32UI),
+      Int8(c) => pick(c, gli::R8I, gli::RG8I, gli::RGB8I, gli::RGBA8I),
+      Int16(c) => pick(c, gli::R16I, gli::RG16I, gli::RGB16I, gli::RGBA16I),
+      Int32(c) => pick(c, gli::R32I, gli::RG32I, gli::RGB32I, gli::RGBA32I),
+      Srgb8 => gli::SRGB8,
+      Srgb8Alpha8 => gli::SRGB8_ALPHA8,
+      Depth16 => gli::DEPTH_COMPONENT16,
+      Depth24 => gli::DEPTH_COMPONENT24,
+      Depth32F => gli::DEPTH_COMPONENT32F,
+      Depth24Stencil8 => gli::DEPTH24_STENCIL8,
+      Depth32FStencil8 => gli::DEPTH32F_STENCIL8,
+    }
+  }
+
+  pub fn format(self) -> u32 {
+    use self::TextureFormat::*;
+    match self {
+      Unorm8(c) | Float16(c) | Float32(c) => pick(c, gli::RED, gli::RG, gli::RGB, gli::RGBA),
+      Uint8(c) | Uint16(c) | Uint32(c) | Int8(c) | Int16(c) | Int32(c) => {
+        pick(c, gli::RED_INTEGER, gli::RG_INTEGER, gli::RGB_INTEGER, gli::RGBA_INTEGER)
+      },
+      Srgb8 => gli::RGB,
+      Srgb8Alpha8 => gli::RGBA,
+      Depth16 | Depth24 | Depth32F => gli::DEPTH_COMPONENT,
+      Depth24Stencil8 | Depth32FStencil8 => gli::DEPTH_STENCIL,
+    }
+  }
+
+  pub fn pixel_type(self) -> u32 {
+    use self::TextureFormat::*;
+    match self {
+      Unorm8(_) | Uint8(_) | Srgb8 | Srgb8Alpha8 => gli::UNSIGNED_BYTE,
+      Float16(_) => gli::HALF_FLOAT,
+      Float32(_) | Depth32F => gli::FLOAT,
+      Uint16(_) | Depth16 => gli::UNSIGNED_SHORT,
+      Uint32(_) | Depth24 => gli::UNSIGNED_INT,
+      Int8(_) => gli::BYTE,
+      Int16(_) => gli::SHORT,
+      Int32(_) => gli::INT,
+      Depth24Stencil8 => gli::UNSIGNED_INT_24_8,
+      Depth32FStencil8 => gli::FLOAT_32_UNSIGNED_INT_24_8_REV,
+    }
+  }
+
+  // Number of values of the pixel type that make up one pixel
+  pub fn components(self) -> usize {
+    use self::TextureFormat::*;
+    match self {
+      Unorm8(c) | Float16(c) | Float32(c) | Uint8(c) | Uint16(c) | Uint32(c) | Int8(c) | Int16(c) | Int32(c) => c.count(),
+      Srgb8 => 3,
+      Srgb8Alpha8 => 4,
+      Depth16 | Depth24 | Depth32F | Depth24Stencil8 | Depth32FStencil8 => 1,
+    }
+  }
+
+  pub fn bytes_per_pixel(self) -> usize {
+    self.components() * gli::size_of_type(self.pixel_type()) as usize
+  }
+
+  pub fn is_depth(self) -> bool {
+    use self::TextureFormat::*;
+    matches!(self, Depth16 | Depth24 | Depth32F | Depth24Stencil8 | Depth32FStencil8)
+  }
+
+  // Resolves the GL type to upload client data of `data_type` with, if the
+  // format can accept it
+  pub fn upload_type(self, data_type: u32) -> Option<u32> {
+    let pixel_type = self.pixel_type();
+    if data_type == pixel_type {
+      return Some(pixel_type);
+    }
+    match (self, data_type) {
+      (TextureFormat::Float16(_), gli::FLOAT) => Some(gli::FLOAT),
+      (TextureFormat::Depth24Stencil8, gli::UNSIGNED_INT) => Some(gli::UNSIGNED_INT_24_8),
+      _ => None,
+    }
+  }
+}
+
+fn pick(channels: Channels, r: u32, rg: u32, rgb: u32, rgba: u32) -> u32 {
+  match channels {
+    Channels::R => r,
+    Channels::RG => rg,
+    Channels::RGB => rgb,
+    Channels::RGBA => rgba,
+  }
+}
+
+pub trait TexelData: Copy {
+  const GL_TYPE: u32;
+}
+
+impl TexelData for u8 {
+  const GL_TYPE: u32 = gli::UNSIGNED_BYTE;
+}
+
+impl TexelData for i8 {
+  const GL_TYPE: u32 = gli::BYTE;
+}
+
+impl TexelData for u16 {
+  const GL_TYPE: u32 = gli::UNSIGNED_SHORT;
+}
+
+impl TexelData for i16 {
+  const GL_TYPE: u32 = gli::SHORT;
+}
+
+impl TexelData for u32 {
+  const GL_TYPE: u32 = gli::UNSIGNED_INT;
+}
+
+impl TexelData for i32 {
+  const GL_TYPE: u32 = gli::INT;
+}
+
+impl TexelData for f32 {
+  const GL_TYPE: u32 = gli::FLOAT;
+}
+
+#[cfg(feature = "half")]
+impl TexelData for half::f16 {
+  const GL_TYPE: u32 = gli::HALF_FLOAT;
+}
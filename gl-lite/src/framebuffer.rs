@@ -0,0 +1,210 @@
+use super::format::{Channels, TextureFormat};
+use super::gli;
+use super::readback::{self, PixelData, PixelRect};
+use super::texture::Texture;
+
+enum DepthAttachment {
+  Renderbuffer(u32, TextureFormat),
+  Texture(Texture),
+}
+
+impl DepthAttachment {
+  fn format(&self) -> TextureFormat {
+    match self {
+      DepthAttachment::Renderbuffer(_, format) => *format,
+      DepthAttachment::Texture(texture) => texture.texture_format().unwrap_or(TextureFormat::Depth24Stencil8),
+    }
+  }
+}
+
+pub(crate) fn depth_attachment_point(format: TextureFormat) -> u32 {
+  match format {
+    TextureFormat::Depth24Stencil8 | TextureFormat::Depth32FStencil8 => gli::DEPTH_STENCIL_ATTACHMENT,
+    _ => gli::DEPTH_ATTACHMENT,
+  }
+}
+
+// An offscreen render target made of color textures and an optional depth
+// buffer. Color attachments are textures so the results can be sampled by
+// later draws.
+pub struct Framebuffer {
+  gl_framebuffer: u32,
+  width: i32,
+  height: i32,
+  color: Vec<Texture>,
+  depth: Option<DepthAttachment>,
+}
+
+impl Framebuffer {
+  // RGBA8 color with a 24-bit depth / 8-bit stencil renderbuffer
+  pub fn new(width: i32, height: i32) -> Framebuffer {
+    let mut framebuffer = Framebuffer::with_color_formats(width, height, &[TextureFormat::Unorm8(Channels::RGBA)]);
+    framebuffer.add_depth_renderbuffer(TextureFormat::Depth24Stencil8);
+    framebuffer
+  }
+
+  pub fn with_color_formats(width: i32, height: i32, formats: &[TextureFormat]) -> Framebuffer {
+    if width < 1 || height < 1 {
+      panic!("Cannot create a framebuffer of size {}x{}", width, height);
+    }
+    if let Some(format) = formats.iter().find(|f| f.is_depth()) {
+      panic!("{:?} cannot be used as a color attachment", format);
+    }
+    let mut framebuffer = Framebuffer {
+      gl_framebuffer: gli::create_framebuffer(),
+      width,
+      height,
+      color: Vec::with_capacity(formats.len()),
+      depth: None,
+    };
+    gli::bind_framebuffer(framebuffer.gl_framebuffer);
+    for format in formats {
+      let mut texture = Texture::new();
+      texture.allocate(*format, width, height);
+      gli::framebuffer_texture_2d(gli::COLOR_ATTACHMENT0 + framebuffer.color.len() as u32, texture.gl_texture());
+      framebuffer.color.push(texture);
+    }
+    gli::draw_buffers(framebuffer.color.len());
+    gli::bind_framebuffer(0);
+    framebuffer
+  }
+
+  // Depth that only needs testing against, never sampling
+  pub fn add_depth_renderbuffer(&mut self, format: TextureFormat) {
+    if !format.is_depth() {
+      panic!("{:?} is not a depth format", format);
+    }
+    self.remove_depth();
+    let renderbuffer = gli::create_renderbuffer();
+    gli::renderbuffer_storage(renderbuffer, format.internal_format(), self.width, self.height);
+    gli::bind_framebuffer(self.gl_framebuffer);
+    gli::framebuffer_renderbuffer(depth_attachment_point(format), renderbuffer);
+    gli::bind_framebuffer(0);
+    self.depth = Some(DepthAttachment::Renderbuffer(renderbuffer, format));
+  }
+
+  // Depth stored in a texture, e.g. for shadow maps
+  pub fn add_depth_texture(&mut self, format: TextureFormat) {
+    if !format.is_depth() {
+      panic!("{:?} is not a depth format", format);
+    }
+    self.remove_depth();
+    let mut texture = Texture::new();
+    texture.allocate(format, self.width, self.height);
+    gli::bind_framebuffer(self.gl_framebuffer);
+    gli::framebuffer_texture_2d(depth_attachment_point(format), texture.gl_texture());
+    gli::bind_framebuffer(0);
+    self.depth = Some(DepthAttachment::Texture(texture));
+  }
+
+  fn remove_depth(&mut self) {
+    if let Some(depth) = self.depth.take() {
+      gli::bind_framebuffer(self.gl_framebuffer);
+      gli::framebuffer_renderbuffer(depth_attachment_point(depth.format()), 0);
+      gli::bind_framebuffer(0);
+      if let DepthAttachment::Renderbuffer(renderbuffer, _) = depth {
+        gli::delete_renderbuffer(renderbuffer);
+      }
+    }
+  }
+
+  pub fn gl_framebuffer(&self) -> u32 {
+    self.gl_framebuffer
+  }
+
+  pub fn width(&self) -> i32 {
+    self.width
+  }
+
+  pub fn height(&self) -> i32 {
+    self.height
+  }
+
+  pub fn color_attachment_count(&self) -> usize {
+    self.color.len()
+  }
+
+  pub fn color_texture(&self, index: usize) -> &Texture {
+    &self.color[index]
+  }
+
+  pub fn depth_texture(&self) -> Option<&Texture> {
+    match &self.depth {
+      Some(DepthAttachment::Texture(texture)) => Some(texture),
+      _ => None,
+    }
+  }
+
+  pub fn is_complete(&self) -> bool {
+    gli::bind_framebuffer(self.gl_framebuffer);
+    let complete = gli::framebuffer_complete();
+    gli::bind_framebuffer(0);
+    complete
+  }
+
+  // Reallocates every attachment at the new size; previous contents are lost
+  pub fn resize(&mut self, width: i32, height: i32) {
+    if width < 1 || height < 1 {
+      panic!("Cannot resize a framebuffer to {}x{}", width, height);
+    }
+    if width == self.width && height == self.height {
+      return;
+    }
+    self.width = width;
+    self.height = height;
+    for texture in self.color.iter_mut() {
+      if let Some(format) = texture.texture_format() {
+        texture.allocate(format, width, height);
+      }
+    }
+    let depth = self.depth.as_ref().map(|d| (d.format(), matches!(d, DepthAttachment::Texture(_))));
+    match depth {
+      Some((format, true)) => self.add_depth_texture(format),
+      Some((format, false)) => self.add_depth_renderbuffer(format),
+      None => (),
+    }
+  }
+
+  // Directs subsequent draws into this framebuffer and covers it with the
+  // viewport
+  pub fn bind(&self) {
+    gli::bind_framebuffer(self.gl_framebuffer);
+    gli::viewport(0, 0, self.width, self.height);
+  }
+
+  pub fn unbind(&self) {
+    gli::bind_framebuffer(0);
+  }
+
+  pub fn clear(&self, color: [f32; 4]) {
+    self.bind();
+    gli::clear_color(color[0], color[1], color[2], color[3]);
+    let mut mask = gli::COLOR_BUFFER_BIT;
+    if self.depth.is_some() {
+      mask |= gli::DEPTH_BUFFER_BIT | gli::STENCIL_BUFFER_BIT;
+    }
+    gli::clear(mask);
+  }
+
+  // Reads from a color attachment; rows are bottom to top
+  pub fn read_pixels(&self, attachment: usize, rect: PixelRect, format: u32) -> PixelData {
+    if attachment >= self.color.len() {
+      panic!("Framebuffer has no color attachment {}", attachment);
+    }
+    gli::bind_read_framebuffer(self.gl_framebuffer);
+    gli::read_buffer(gli::COLOR_ATTACHMENT0 + attachment as u32);
+    let pixels = readback::read_pixels(rect, format);
+    gli::bind_read_framebuffer(0);
+    pixels
+  }
+}
+
+// Color and depth textures delete themselves as they are dropped
+impl Drop for Framebuffer {
+  fn drop(&mut self) {
+    if let Some(DepthAttachment::Renderbuffer(renderbuffer, _)) = self.depth {
+      gli::delete_renderbuffer(renderbuffer);
+    }
+    gli::delete_framebuffer(self.gl_framebuffer);
+  }
+}
@@ -1,117 +1,501 @@
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::rc::Rc;
 use super::gli;
 use super::program::Attribute;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BufferUsage {
+  Static,
+  Dynamic,
+  Stream,
+}
+
+impl BufferUsage {
+  pub fn gl_usage(self) -> u32 {
+    match self {
+      BufferUsage::Static => gli::STATIC_DRAW,
+      BufferUsage::Dynamic => gli::DYNAMIC_DRAW,
+      BufferUsage::Stream => gli::STREAM_DRAW,
+    }
+  }
+}
+
 pub struct AttributeInfo {
   normalize: bool,
+  integer: bool,
   location: u32,
   offset: i32,
   size: i32,
-  stride: i32,
   gl_type: u32,
 }
 
-pub struct Geometry {
+struct VertexStream {
+  buffer: Rc<VertexBuffer>,
   attributes: Vec<AttributeInfo>,
-  buffer: VertexBuffer,
+  stride: i32,
+  base_offset: i32,
+}
+
+pub struct Geometry {
+  streams: Vec<VertexStream>,
   data_length: i32,
+  first_vertex: i32,
+  base_vertex: i32,
+  vertex_count: Option<i32>,
   index: Option<u32>,
   index_count: i32,
-  total_byte_length: i32,
+  index_type: u32,
   vao: u32,
-  bound: bool,
+  bound_buffers: Vec<u32>,
+}
+
+impl Default for Geometry {
+  fn default() -> Geometry {
+    Geometry::new()
+  }
 }
 
 impl Geometry {
   pub fn new() -> Geometry {
-    Geometry {
-      attributes: Vec::new(),
-      buffer: VertexBuffer::new(),
+    Geometry::with_usage(BufferUsage::Static)
+  }
+
+  pub fn with_usage(usage: BufferUsage) -> Geometry {
+    Geometry::for_buffer(Rc::new(VertexBuffer::with_usage(usage)), 0)
+  }
+
+  pub fn for_buffer(buffer: Rc<VertexBuffer>, base_offset: i32) -> Geometry {
+    let mut geometry = Geometry {
+      streams: Vec::new(),
       data_length: 0,
+      first_vertex: 0,
+      base_vertex: 0,
+      vertex_count: None,
       index: None,
       index_count: 0,
-      total_byte_length: 0,
+      index_type: gli::UNSIGNED_SHORT,
       vao: gli::create_vertex_array(),
-      bound: false,
-    }
+      bound_buffers: Vec::new(),
+    };
+    geometry.add_stream(buffer, base_offset);
+    geometry
+  }
+
+  pub fn add_stream(&mut self, buffer: Rc<VertexBuffer>, base_offset: i32) -> usize {
+    self.streams.push(VertexStream {
+      buffer,
+      attributes: Vec::new(),
+      stride: 0,
+      base_offset,
+    });
+    self.streams.len() - 1
+  }
+
+  pub fn stream_buffer(&self, stream: usize) -> &Rc<VertexBuffer> {
+    &self.streams[stream].buffer
+  }
+
+  pub fn vertex_array(&self) -> u32 {
+    self.vao
   }
 
-  fn add_attr(&mut self, attr: &Attribute, normalize: bool) {
+  fn add_attr(&mut self, stream: usize, attr: &Attribute, normalize: bool) {
     let (size, gl_type) = gli::get_attribute_size_and_type(attr.gl_type, normalize);
-    let length = gli::size_of_type(gl_type);
+    let length = gli::size_of_type(gl_type) * size;
+    self.push_attr(stream, attr, size, gl_type, normalize, length);
+  }
+
+  fn push_attr(&mut self, stream: usize, attr: &Attribute, size: i32, gl_type: u32, normalize: bool, length: i32) {
+    let stream = &mut self.streams[stream];
     let info = AttributeInfo {
-      normalize: normalize,
+      normalize,
+      integer: gli::is_integer_attribute(attr.gl_type),
       location: attr.location,
-      size: size,
-      gl_type: gl_type,
-      offset: self.total_byte_length,
-      stride: length * size,
+      size,
+      gl_type,
+      offset: stream.stride,
     };
-    self.attributes.push(info);
-    self.total_byte_length += length * size;
+    stream.attributes.push(info);
+    stream.stride += length;
+    self.bound_buffers.clear();
   }
 
   pub fn add_attribute(&mut self, attr: &Attribute) {
-    self.add_attr(attr, false);
+    self.add_attr(0, attr, false);
   }
 
   pub fn add_normalized_attribute(&mut self, attr: &Attribute) {
-    self.add_attr(attr, true);
+    self.add_attr(0, attr, true);
+  }
+
+  pub fn add_stream_attribute(&mut self, stream: usize, attr: &Attribute) {
+    self.add_attr(stream, attr, false);
+  }
+
+  pub fn add_normalized_stream_attribute(&mut self, stream: usize, attr: &Attribute) {
+    self.add_attr(stream, attr, true);
+  }
+
+  // Reads the attribute from components stored as `gl_type`, such as
+  // UNSIGNED_SHORT joint indices. Integer program attributes get the values
+  // as they are; float ones get them converted, and normalized if asked.
+  // Each typed attribute takes a multiple of 4 bytes in the vertex.
+  pub fn add_typed_attribute(&mut self, attr: &Attribute, gl_type: u32, normalize: bool) {
+    self.add_typed_stream_attribute(0, attr, gl_type, normalize);
+  }
+
+  pub fn add_typed_stream_attribute(&mut self, stream: usize, attr: &Attribute, gl_type: u32, normalize: bool) {
+    let size = gli::get_attribute_size_and_type(attr.gl_type, false).0;
+    let length = (gli::size_of_type(gl_type) * size + 3) & !3;
+    self.push_attr(stream, attr, size, gl_type, normalize, length);
+  }
+
+  pub fn set_usage(&self, usage: BufferUsage) {
+    self.streams[0].buffer.set_usage(usage);
   }
 
   pub fn buffer_data(&mut self, data: &[f32]) {
-    self.data_length = data.len() as i32 * 4;
-    self.buffer.buffer_data(data);
+    self.upload(data);
+  }
+
+  // Vertex data laid out byte by byte, for attributes of mixed types
+  pub fn buffer_bytes(&mut self, data: &[u8]) {
+    self.upload(data);
+  }
+
+  fn upload<T: Copy>(&mut self, data: &[T]) {
+    self.data_length = mem::size_of_val(data) as i32;
+    let offset = self.streams[0].buffer.upload(data);
+    let stride = self.streams[0].stride;
+    self.first_vertex = if stride > 0 {
+      offset / stride
+    } else {
+      0
+    };
+  }
+
+  pub fn buffer_sub_data(&self, offset: i32, data: &[f32]) {
+    self.streams[0].buffer.buffer_sub_data(offset, data);
+  }
+
+  pub fn orphan(&self) {
+    self.streams[0].buffer.orphan();
+  }
+
+  // Streams each buffer_data call into the next of `segments` slices of a
+  // persistently mapped buffer, each large enough for `segment_vertices`.
+  // Returns false, leaving the buffer untouched, when the context can't map
+  // buffers persistently.
+  pub fn enable_persistent_ring(&mut self, segment_vertices: i32, segments: usize) -> bool {
+    let stride = self.streams[0].stride;
+    if stride == 0 {
+      panic!("Cannot create a ring buffer before attributes have been added");
+    }
+    let enabled = self.streams[0].buffer.enable_persistent_ring(segment_vertices * stride, segments);
+    if enabled {
+      self.data_length = 0;
+    }
+    enabled
+  }
+
+  // Draws triangles from these indices into the vertex data. They are
+  // stored as 16-bit values when every index fits.
+  pub fn set_indices(&mut self, indices: &[u32]) {
+    let buffer = match self.index {
+      Some(buffer) => buffer,
+      None => gli::create_buffer(),
+    };
+    let usage = self.streams[0].buffer.usage().gl_usage();
+    // The element buffer binding is part of the vertex array's state
+    gli::bind_vertex_array(self.vao);
+    gli::bind_element_array_buffer(buffer);
+    if indices.iter().all(|i| *i <= u16::MAX as u32) {
+      let short: Vec<u16> = indices.iter().map(|i| *i as u16).collect();
+      gli::buffer_element_data_u16(&short, usage);
+      self.index_type = gli::UNSIGNED_SHORT;
+    } else {
+      gli::buffer_element_data_u32(indices, usage);
+      self.index_type = gli::UNSIGNED_INT;
+    }
+    gli::bind_vertex_array(0);
+    self.index = Some(buffer);
+    self.index_count = indices.len() as i32;
+  }
+
+  pub fn is_indexed(&self) -> bool {
+    self.index.is_some()
+  }
+
+  pub fn index_count(&self) -> i32 {
+    self.index_count
+  }
+
+  // Draws `count` vertices starting at `base_vertex` instead of inferring the
+  // range from the last buffer_data call. Indexed draws add `base_vertex` to
+  // each index.
+  pub fn set_vertex_range(&mut self, base_vertex: i32, count: i32) {
+    self.base_vertex = base_vertex;
+    self.vertex_count = Some(count);
+  }
+
+  fn vertex_count(&self) -> i32 {
+    match self.vertex_count {
+      Some(count) => count,
+      None => {
+        let stride = self.streams[0].stride;
+        if stride == 0 {
+          panic!("Cannot infer the vertex count without attributes in the first stream, use set_vertex_range");
+        }
+        self.data_length / stride
+      },
+    }
   }
 
   fn bind_to_attributes(&self) {
-    for attr in self.attributes.iter() {
-      self.buffer.bind_to_attribute(
-        attr.location,
-        attr.size,
-        attr.gl_type,
-        attr.normalize,
-        self.total_byte_length,
-        attr.offset,
-      );
+    for stream in self.streams.iter() {
+      for attr in stream.attributes.iter() {
+        let offset = stream.base_offset + attr.offset;
+        if attr.integer {
+          stream.buffer.bind_to_integer_attribute(attr.location, attr.size, attr.gl_type, stream.stride, offset);
+        } else {
+          stream.buffer.bind_to_attribute(attr.location, attr.size, attr.gl_type, attr.normalize, stream.stride, offset);
+        }
+      }
     }
   }
 
-  pub fn draw(&mut self) {
+  fn bind(&mut self) {
     gli::bind_vertex_array(self.vao);
-    if !self.bound {
+    let buffers: Vec<u32> = self.streams.iter().map(|s| s.buffer.gl_buffer()).collect();
+    if buffers != self.bound_buffers {
       self.bind_to_attributes();
-      self.bound = true;
+      self.bound_buffers = buffers;
     }
-    if let Some(index) = self.index {
-      gli::draw_elements_triangles(self.index_count);
+  }
+
+  fn fence(&self) {
+    for stream in self.streams.iter() {
+      stream.buffer.fence();
+    }
+  }
+
+  pub fn draw(&mut self) {
+    self.bind();
+    if self.index.is_some() {
+      gli::draw_elements_triangles(self.index_count, self.index_type, self.first_vertex + self.base_vertex);
+    } else {
+      gli::draw_arrays_triangles(self.first_vertex + self.base_vertex, self.vertex_count());
+    }
+    gli::bind_vertex_array(0);
+    self.fence();
+  }
+
+  pub fn draw_patches(&mut self, vertices_per_patch: i32) {
+    gli::patch_vertices(vertices_per_patch);
+    self.bind();
+    if self.index.is_some() {
+      gli::draw_elements_patches(self.index_count, self.index_type, self.first_vertex + self.base_vertex);
     } else {
-      let count = self.data_length / self.total_byte_length;
-      gli::draw_arrays_triangles(count);
+      gli::draw_arrays_patches(self.first_vertex + self.base_vertex, self.vertex_count());
     }
     gli::bind_vertex_array(0);
+    self.fence();
+  }
+}
+
+// Vertex buffers are shared through Rc and free themselves; the vertex
+// array and index buffer belong to this geometry alone
+impl Drop for Geometry {
+  fn drop(&mut self) {
+    gli::delete_vertex_array(self.vao);
+    if let Some(index) = self.index {
+      gli::delete_buffer(index);
+    }
+  }
+}
+
+struct PersistentRing {
+  mapping: gli::MappedBuffer,
+  segment_size: i32,
+  fences: Vec<Option<gli::Fence>>,
+  current: usize,
+}
+
+impl PersistentRing {
+  fn write<T: Copy>(&mut self, data: &[T]) -> i32 {
+    let length = mem::size_of_val(data) as i32;
+    if length > self.segment_size {
+      panic!("Cannot write {} bytes into a ring segment of {} bytes", length, self.segment_size);
+    }
+    self.current = (self.current + 1) % self.fences.len();
+    if let Some(fence) = self.fences[self.current].take() {
+      gli::wait_sync(&fence);
+      gli::delete_sync(fence);
+    }
+    let offset = self.current as i32 * self.segment_size;
+    self.mapping.write(offset as usize, data);
+    offset
+  }
+
+  fn fence(&mut self) {
+    if let Some(old) = self.fences[self.current].replace(gli::fence_sync()) {
+      gli::delete_sync(old);
+    }
+  }
+
+  // Unmaps the ring, which must be the bound array buffer
+  fn release(self) {
+    for fence in self.fences.into_iter().flatten() {
+      gli::delete_sync(fence);
+    }
+    gli::unmap_array_buffer();
   }
 }
 
 pub struct VertexBuffer {
-  buffer: u32,
+  buffer: Cell<u32>,
+  usage: Cell<BufferUsage>,
+  byte_length: Cell<i32>,
+  ring: RefCell<Option<PersistentRing>>,
+}
+
+impl Default for VertexBuffer {
+  fn default() -> VertexBuffer {
+    VertexBuffer::new()
+  }
 }
 
 impl VertexBuffer {
   pub fn new() -> VertexBuffer {
+    VertexBuffer::with_usage(BufferUsage::Static)
+  }
+
+  pub fn with_usage(usage: BufferUsage) -> VertexBuffer {
     let vbo = gli::create_buffer();
     VertexBuffer {
-      buffer: vbo,
+      buffer: Cell::new(vbo),
+      usage: Cell::new(usage),
+      byte_length: Cell::new(0),
+      ring: RefCell::new(None),
+    }
+  }
+
+  pub fn gl_buffer(&self) -> u32 {
+    self.buffer.get()
+  }
+
+  pub fn usage(&self) -> BufferUsage {
+    self.usage.get()
+  }
+
+  pub fn byte_length(&self) -> i32 {
+    self.byte_length.get()
+  }
+
+  pub fn is_persistent(&self) -> bool {
+    self.ring.borrow().is_some()
+  }
+
+  // The new usage hint is applied the next time the store is allocated
+  pub fn set_usage(&self, usage: BufferUsage) {
+    self.usage.set(usage);
+  }
+
+  // Returns the byte offset the data was written to, which is only non-zero
+  // when writing into a persistent ring
+  pub fn buffer_data(&self, data: &[f32]) -> i32 {
+    self.upload(data)
+  }
+
+  pub fn buffer_bytes(&self, data: &[u8]) -> i32 {
+    self.upload(data)
+  }
+
+  fn upload<T: Copy>(&self, data: &[T]) -> i32 {
+    if let Some(ring) = self.ring.borrow_mut().as_mut() {
+      return ring.write(data);
+    }
+    gli::bind_array_buffer(self.buffer.get());
+    gli::buffer_array_data(data, self.usage.get().gl_usage());
+    self.byte_length.set(mem::size_of_val(data) as i32);
+    0
+  }
+
+  pub fn buffer_sub_data(&self, offset: i32, data: &[f32]) {
+    if self.is_persistent() {
+      panic!("Cannot update part of a persistent ring, use buffer_data instead");
+    }
+    let length = mem::size_of_val(data) as i32;
+    let byte_length = self.byte_length.get();
+    if offset < 0 || offset + length > byte_length {
+      panic!("Cannot write {} bytes at offset {} into a buffer of {} bytes", length, offset, byte_length);
+    }
+    gli::bind_array_buffer(self.buffer.get());
+    gli::buffer_array_sub_data(offset, data);
+  }
+
+  pub fn orphan(&self) {
+    if self.is_persistent() {
+      return;
+    }
+    gli::bind_array_buffer(self.buffer.get());
+    gli::orphan_array_buffer(self.byte_length.get(), self.usage.get().gl_usage());
+  }
+
+  pub fn enable_persistent_ring(&self, segment_size: i32, segments: usize) -> bool {
+    if segments == 0 {
+      panic!("Cannot create a ring buffer with no segments");
+    }
+    if !gli::supports_buffer_storage() {
+      return false;
+    }
+    // Immutable storage can't be re-specified, so the ring gets a fresh buffer
+    self.release();
+    let buffer = gli::create_buffer();
+    let byte_length = segment_size * segments as i32;
+    gli::bind_array_buffer(buffer);
+    let mapping = gli::map_persistent_array_buffer(byte_length);
+    self.buffer.set(buffer);
+    self.byte_length.set(byte_length);
+    self.ring.replace(Some(PersistentRing {
+      mapping,
+      segment_size,
+      fences: (0..segments).map(|_| None).collect(),
+      current: segments - 1,
+    }));
+    true
+  }
+
+  pub fn fence(&self) {
+    if let Some(ring) = self.ring.borrow_mut().as_mut() {
+      ring.fence();
     }
   }
 
-  pub fn buffer_data(&self, data: &[f32]) {
-    gli::bind_array_buffer(self.buffer);
-    gli::buffer_array_data(data);
+  fn release(&self) {
+    if let Some(ring) = self.ring.borrow_mut().take() {
+      gli::bind_array_buffer(self.buffer.get());
+      ring.release();
+    }
+    gli::delete_buffer(self.buffer.get());
   }
 
   pub fn bind_to_attribute(&self, location: u32, size: i32, gl_type: u32, normalized: bool, stride: i32, offset: i32) {
-    gli::bind_array_buffer(self.buffer);
+    gli::bind_array_buffer(self.buffer.get());
     gli::vertex_attrib_pointer(location, size, gl_type, normalized, stride, offset);
     gli::enable_vertex_attrib_array(location);
   }
+
+  pub fn bind_to_integer_attribute(&self, location: u32, size: i32, gl_type: u32, stride: i32, offset: i32) {
+    gli::bind_array_buffer(self.buffer.get());
+    gli::vertex_attrib_i_pointer(location, size, gl_type, stride, offset);
+    gli::enable_vertex_attrib_array(location);
+  }
+}
+
+impl Drop for VertexBuffer {
+  fn drop(&mut self) {
+    self.release();
+  }
 }
@@ -0,0 +1,1058 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use ::gltf::accessor::{DataType, Dimensions};
+use ::gltf::animation::{Interpolation as GltfInterpolation, Property as GltfProperty};
+use ::gltf::accessor::sparse::IndexType;
+use ::gltf::mesh::{Mode, Semantic};
+use ::gltf::{self, Document};
+#[cfg(feature = "image")]
+use super::image_loader::{ImageError, ImageOptions};
+use super::animation::{AnimationClip, Channel, Interpolation, Property, Skeleton};
+use super::gli;
+use super::material::Material;
+use super::math::{Mat4, Quat, Vec3};
+use super::mesh::{AttributeNames, MeshData, VertexAttribute};
+use super::node::Node;
+use super::program::Program;
+use super::render_state::{CullMode, RenderState};
+use super::scene::{NodeId, Scene, Transform};
+#[cfg(feature = "image")]
+use super::texture::{FilterMode, WrapMode};
+use super::texture::Texture;
+use super::uniforms::UniformValue;
+
+#[derive(Debug)]
+pub enum GltfError {
+  Io(PathBuf, io::Error),
+  Parse(gltf::Error),
+  Invalid(String),
+  Unsupported(String),
+  #[cfg(feature = "image")]
+  Image(ImageError),
+}
+
+impl fmt::Display for GltfError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      GltfError::Io(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+      GltfError::Parse(e) => write!(f, "Failed to parse glTF: {}", e),
+      GltfError::Invalid(msg) => write!(f, "Invalid glTF: {}", msg),
+      GltfError::Unsupported(msg) => write!(f, "Unsupported glTF: {}", msg),
+      #[cfg(feature = "image")]
+      GltfError::Image(e) => write!(f, "Failed to load glTF image: {}", e),
+    }
+  }
+}
+
+impl Error for GltfError {}
+
+impl From<gltf::Error> for GltfError {
+  fn from(e: gltf::Error) -> GltfError {
+    GltfError::Parse(e)
+  }
+}
+
+#[cfg(feature = "image")]
+impl From<ImageError> for GltfError {
+  fn from(e: ImageError) -> GltfError {
+    GltfError::Image(e)
+  }
+}
+
+fn invalid<T>(message: String) -> Result<T, GltfError> {
+  Err(GltfError::Invalid(message))
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, GltfError> {
+  fs::read(path).map_err(|e| GltfError::Io(path.to_path_buf(), e))
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+  let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+  let mut bits: u32 = 0;
+  let mut bit_count = 0;
+  for c in encoded.bytes() {
+    let value = match c {
+      b'A'..=b'Z' => c - b'A',
+      b'a'..=b'z' => c - b'a' + 26,
+      b'0'..=b'9' => c - b'0' + 52,
+      b'+' | b'-' => 62,
+      b'/' | b'_' => 63,
+      b'=' => break,
+      b' ' | b'\n' | b'\r' | b'\t' => continue,
+      _ => return None,
+    };
+    bits = (bits << 6) | value as u32;
+    bit_count += 6;
+    if bit_count >= 8 {
+      bit_count -= 8;
+      bytes.push((bits >> bit_count) as u8);
+    }
+  }
+  Some(bytes)
+}
+
+// Decodes %XX escapes in relative URIs
+fn decode_uri(uri: &str) -> String {
+  let bytes = uri.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let escaped = if bytes[i] == b'%' && i + 2 < bytes.len() {
+      std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+    } else {
+      None
+    };
+    match escaped {
+      Some(byte) => {
+        decoded.push(byte);
+        i += 3;
+      },
+      None => {
+        decoded.push(bytes[i]);
+        i += 1;
+      },
+    }
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// The values of an accessor in their stored component type, with sparse
+// substitutions applied. Matrices are stored column by column.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccessorValues {
+  I8(Vec<i8>),
+  U8(Vec<u8>),
+  I16(Vec<i16>),
+  U16(Vec<u16>),
+  U32(Vec<u32>),
+  F32(Vec<f32>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessorData {
+  // Values per element, 16 for a mat4
+  pub components: usize,
+  pub normalized: bool,
+  pub values: AccessorValues,
+}
+
+impl AccessorData {
+  pub fn len(&self) -> usize {
+    let values = match &self.values {
+      AccessorValues::I8(v) => v.len(),
+      AccessorValues::U8(v) => v.len(),
+      AccessorValues::I16(v) => v.len(),
+      AccessorValues::U16(v) => v.len(),
+      AccessorValues::U32(v) => v.len(),
+      AccessorValues::F32(v) => v.len(),
+    };
+    values / self.components
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  // The GL type of the stored components, for binding them as a vertex
+  // attribute as they are
+  pub fn gl_type(&self) -> u32 {
+    match self.values {
+      AccessorValues::I8(_) => gli::BYTE,
+      AccessorValues::U8(_) => gli::UNSIGNED_BYTE,
+      AccessorValues::I16(_) => gli::SHORT,
+      AccessorValues::U16(_) => gli::UNSIGNED_SHORT,
+      AccessorValues::U32(_) => gli::UNSIGNED_INT,
+      AccessorValues::F32(_) => gli::FLOAT,
+    }
+  }
+
+  // Converts to floats, mapping normalized integers to 0..1 or -1..1
+  pub fn to_f32(&self) -> Vec<f32> {
+    let n = self.normalized;
+    match &self.values {
+      AccessorValues::I8(v) => v.iter().map(|c| if n { (*c as f32 / 127.0).max(-1.0) } else { *c as f32 }).collect(),
+      AccessorValues::U8(v) => v.iter().map(|c| if n { *c as f32 / 255.0 } else { *c as f32 }).collect(),
+      AccessorValues::I16(v) => v.iter().map(|c| if n { (*c as f32 / 32767.0).max(-1.0) } else { *c as f32 }).collect(),
+      AccessorValues::U16(v) => v.iter().map(|c| if n { *c as f32 / 65535.0 } else { *c as f32 }).collect(),
+      AccessorValues::U32(v) => v.iter().map(|c| if n { *c as f32 / 4294967295.0 } else { *c as f32 }).collect(),
+      AccessorValues::F32(v) => v.clone(),
+    }
+  }
+
+  // Indices and joint numbers; floats are truncated
+  pub fn to_u32(&self) -> Vec<u32> {
+    match &self.values {
+      AccessorValues::I8(v) => v.iter().map(|c| *c as u32).collect(),
+      AccessorValues::U8(v) => v.iter().map(|c| *c as u32).collect(),
+      AccessorValues::I16(v) => v.iter().map(|c| *c as u32).collect(),
+      AccessorValues::U16(v) => v.iter().map(|c| *c as u32).collect(),
+      AccessorValues::U32(v) => v.clone(),
+      AccessorValues::F32(v) => v.iter().map(|c| *c as u32).collect(),
+    }
+  }
+}
+
+// Byte offsets of each component within an element. Matrix columns start
+// on 4-byte boundaries.
+fn component_offsets(dimensions: Dimensions, component_size: usize) -> (Vec<usize>, usize) {
+  let (columns, rows) = match dimensions {
+    Dimensions::Mat2 => (2, 2),
+    Dimensions::Mat3 => (3, 3),
+    Dimensions::Mat4 => (4, 4),
+    other => (1, other.multiplicity()),
+  };
+  let mut column_size = rows * component_size;
+  if columns > 1 {
+    column_size = (column_size + 3) & !3;
+  }
+  let offsets = (0..columns).flat_map(|c| (0..rows).map(move |r| c * column_size + r * component_size)).collect();
+  (offsets, columns * column_size)
+}
+
+fn decode_components(data_type: DataType, bytes: &[u8], offsets: &[usize]) -> AccessorValues {
+  macro_rules! decode {
+    ($variant:ident, $ty:ty) => {
+      AccessorValues::$variant(offsets.iter().map(|o| {
+        const SIZE: usize = std::mem::size_of::<$ty>();
+        let mut raw = [0; SIZE];
+        raw.copy_from_slice(&bytes[*o..*o + SIZE]);
+        <$ty>::from_le_bytes(raw)
+      }).collect())
+    };
+  }
+  match data_type {
+    DataType::I8 => decode!(I8, i8),
+    DataType::U8 => decode!(U8, u8),
+    DataType::I16 => decode!(I16, i16),
+    DataType::U16 => decode!(U16, u16),
+    DataType::U32 => decode!(U32, u32),
+    DataType::F32 => decode!(F32, f32),
+  }
+}
+
+fn index_size(index_type: IndexType) -> usize {
+  match index_type {
+    IndexType::U8 => 1,
+    IndexType::U16 => 2,
+    IndexType::U32 => 4,
+  }
+}
+
+// Surface parameters of a glTF metallic-roughness material. Maps are glTF
+// texture indices; base color and emissive maps hold sRGB values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PbrMaterial {
+  pub name: Option<String>,
+  pub base_color: [f32; 4],
+  pub metallic: f32,
+  pub roughness: f32,
+  pub emissive: [f32; 3],
+  pub normal_scale: f32,
+  pub occlusion_strength: f32,
+  // Fragments with less alpha are discarded when set; None unless the
+  // material is alpha masked
+  pub alpha_cutoff: Option<f32>,
+  pub blend: bool,
+  pub double_sided: bool,
+  pub base_color_map: Option<usize>,
+  pub metallic_roughness_map: Option<usize>,
+  pub normal_map: Option<usize>,
+  pub occlusion_map: Option<usize>,
+  pub emissive_map: Option<usize>,
+}
+
+impl Default for PbrMaterial {
+  fn default() -> PbrMaterial {
+    PbrMaterial::new()
+  }
+}
+
+impl PbrMaterial {
+  // The glTF default material
+  pub fn new() -> PbrMaterial {
+    PbrMaterial {
+      name: None,
+      base_color: [1.0; 4],
+      metallic: 1.0,
+      roughness: 1.0,
+      emissive: [0.0; 3],
+      normal_scale: 1.0,
+      occlusion_strength: 1.0,
+      alpha_cutoff: None,
+      blend: false,
+      double_sided: false,
+      base_color_map: None,
+      metallic_roughness_map: None,
+      normal_map: None,
+      occlusion_map: None,
+      emissive_map: None,
+    }
+  }
+
+  pub fn from_gltf(material: &gltf::Material) -> PbrMaterial {
+    let pbr = material.pbr_metallic_roughness();
+    let alpha_cutoff = match material.alpha_mode() {
+      gltf::material::AlphaMode::Mask => Some(material.alpha_cutoff().unwrap_or(0.5)),
+      _ => None,
+    };
+    PbrMaterial {
+      name: material.name().map(String::from),
+      base_color: pbr.base_color_factor(),
+      metallic: pbr.metallic_factor(),
+      roughness: pbr.roughness_factor(),
+      emissive: material.emissive_factor(),
+      normal_scale: material.normal_texture().map_or(1.0, |t| t.scale()),
+      occlusion_strength: material.occlusion_texture().map_or(1.0, |t| t.strength()),
+      alpha_cutoff,
+      blend: material.alpha_mode() == gltf::material::AlphaMode::Blend,
+      double_sided: material.double_sided(),
+      base_color_map: pbr.base_color_texture().map(|t| t.texture().index()),
+      metallic_roughness_map: pbr.metallic_roughness_texture().map(|t| t.texture().index()),
+      normal_map: material.normal_texture().map(|t| t.texture().index()),
+      occlusion_map: material.occlusion_texture().map(|t| t.texture().index()),
+      emissive_map: material.emissive_texture().map(|t| t.texture().index()),
+    }
+  }
+
+  // Values set as u_base_color, u_metallic, u_roughness, u_emissive,
+  // u_normal_scale, u_occlusion_strength and, when masked, u_alpha_cutoff
+  pub fn uniforms(&self) -> Vec<(&'static str, UniformValue)> {
+    let [r, g, b, a] = self.base_color;
+    let [er, eg, eb] = self.emissive;
+    let mut uniforms = vec![
+      ("u_base_color", UniformValue::FloatVec4(r, g, b, a)),
+      ("u_metallic", UniformValue::Float(self.metallic)),
+      ("u_roughness", UniformValue::Float(self.roughness)),
+      ("u_emissive", UniformValue::FloatVec3(er, eg, eb)),
+      ("u_normal_scale", UniformValue::Float(self.normal_scale)),
+      ("u_occlusion_strength", UniformValue::Float(self.occlusion_strength)),
+    ];
+    if let Some(cutoff) = self.alpha_cutoff {
+      uniforms.push(("u_alpha_cutoff", UniformValue::Float(cutoff)));
+    }
+    uniforms
+  }
+
+  // glTF texture indices by the sampler uniform they are bound to
+  pub fn texture_maps(&self) -> Vec<(&'static str, usize)> {
+    let maps = [
+      ("u_base_color_map", self.base_color_map),
+      ("u_metallic_roughness_map", self.metallic_roughness_map),
+      ("u_normal_map", self.normal_map),
+      ("u_occlusion_map", self.occlusion_map),
+      ("u_emissive_map", self.emissive_map),
+    ];
+    maps.iter().filter_map(|(name, map)| map.map(|m| (*name, m))).collect()
+  }
+
+  pub fn render_state(&self) -> RenderState {
+    let mut state = if self.blend { RenderState::transparent() } else { RenderState::opaque() };
+    state.cull = if self.double_sided { CullMode::None } else { CullMode::Back };
+    state
+  }
+
+  // Maps missing from `textures` are left unbound
+  pub fn to_material(&self, program: Rc<Program>, textures: &HashMap<usize, Texture>) -> Material {
+    let material = Material::new(program);
+    for (name, value) in self.uniforms() {
+      material.set_uniform(String::from(name), value);
+    }
+    for (name, index) in self.texture_maps() {
+      if let Some(texture) = textures.get(&index) {
+        material.set_uniform(String::from(name), texture.as_uniform_value());
+      }
+    }
+    material.set_render_state(self.render_state());
+    material
+  }
+}
+
+// What create_scene built. glTF indices map onto the entries here.
+pub struct GltfScene {
+  pub scene: Scene,
+  // The scene node of each glTF node, None for nodes outside the scene
+  pub nodes: Vec<Option<NodeId>>,
+  pub materials: Vec<Rc<Material>>,
+  // Used by primitives without a material
+  pub default_material: Rc<Material>,
+  // The scene nodes drawing each glTF node's mesh, the node itself when it
+  // has a single primitive, otherwise its extra children
+  pub drawables: Vec<Vec<NodeId>>,
+  pub skins: Vec<Skeleton>,
+  // (glTF node, skin) for every skinned mesh in the scene
+  skinned: Vec<(usize, usize)>,
+}
+
+impl GltfScene {
+  // Updates the scene's transforms, then the joint matrices of every
+  // skinned mesh. Call it each frame after animating, before drawing.
+  pub fn update_skins(&mut self) {
+    self.scene.update();
+    for (node, skin) in self.skinned.iter() {
+      let skeleton = &mut self.skins[*skin];
+      skeleton.update(&self.scene, self.nodes[*node]);
+      for drawable in self.drawables[*node].iter() {
+        if let Some(target) = self.scene.node_mut(*drawable) {
+          skeleton.apply(target);
+        }
+      }
+    }
+  }
+}
+
+// A parsed .gltf or .glb file with its buffers loaded
+pub struct GltfData {
+  pub document: Document,
+  pub buffers: Vec<Vec<u8>>,
+  base_dir: Option<PathBuf>,
+}
+
+impl GltfData {
+  // Loads a .gltf or .glb file. External buffers and images are looked up
+  // next to it.
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<GltfData, GltfError> {
+    let path = path.as_ref();
+    let bytes = read_file(path)?;
+    GltfData::from_slice(&bytes, path.parent())
+  }
+
+  // Without a `base_dir`, only embedded and data URI resources can be used
+  pub fn from_slice(bytes: &[u8], base_dir: Option<&Path>) -> Result<GltfData, GltfError> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice_without_validation(bytes)?;
+    // Required extensions change how the data must be read, for example
+    // compressed geometry, and none are supported. Checked before
+    // validation, which would report them as a parse error.
+    let required: Vec<&str> = document.extensions_required().collect();
+    if !required.is_empty() {
+      return Err(GltfError::Unsupported(format!("required extensions {}", required.join(", "))));
+    }
+    let document = Document::from_json(document.into_json())?;
+    let base_dir = base_dir.map(Path::to_path_buf);
+    let mut buffers = Vec::new();
+    for buffer in document.buffers() {
+      let mut data = match buffer.source() {
+        gltf::buffer::Source::Bin => match &blob {
+          Some(blob) => blob.clone(),
+          None => return invalid(String::from("buffer refers to a missing GLB binary chunk")),
+        },
+        gltf::buffer::Source::Uri(uri) => resolve_uri(base_dir.as_deref(), uri)?,
+      };
+      if data.len() < buffer.length() {
+        return invalid(format!("buffer {} holds {} bytes, expected {}", buffer.index(), data.len(), buffer.length()));
+      }
+      // GLB chunks may be padded past the declared length
+      data.truncate(buffer.length());
+      buffers.push(data);
+    }
+    Ok(GltfData { document, buffers, base_dir })
+  }
+
+  fn view_bytes(&self, view: &gltf::buffer::View) -> Result<&[u8], GltfError> {
+    let buffer = &self.buffers[view.buffer().index()];
+    match buffer.get(view.offset()..view.offset() + view.length()) {
+      Some(bytes) => Ok(bytes),
+      None => invalid(format!("buffer view {} runs past the end of its buffer", view.index())),
+    }
+  }
+
+  // Reads every element of an accessor into memory
+  pub fn accessor(&self, accessor: &gltf::Accessor) -> Result<AccessorData, GltfError> {
+    let data_type = accessor.data_type();
+    let (offsets, element_size) = component_offsets(accessor.dimensions(), data_type.size());
+    let count = accessor.count();
+    let components = offsets.len();
+    let read_elements = |bytes: &[u8], stride: usize, count: usize, what: &str| -> Result<AccessorValues, GltfError> {
+      if count > 0 && (count - 1) * stride + element_size > bytes.len() {
+        return invalid(format!("{} of accessor {} runs past the end of its buffer view", what, accessor.index()));
+      }
+      let all: Vec<usize> = (0..count).flat_map(|i| offsets.iter().map(move |o| i * stride + o)).collect();
+      Ok(decode_components(data_type, bytes, &all))
+    };
+
+    let mut values = match accessor.view() {
+      Some(view) => {
+        let bytes = self.view_bytes(&view)?;
+        let bytes = bytes.get(accessor.offset()..).unwrap_or(&[]);
+        read_elements(bytes, view.stride().unwrap_or(element_size), count, "data")?
+      },
+      // Sparse accessors without a view start out zeroed
+      None => decode_components(data_type, &vec![0; element_size], &offsets.repeat(count)),
+    };
+
+    if let Some(sparse) = accessor.sparse() {
+      let indices = sparse.indices();
+      let size = index_size(indices.index_type());
+      let index_bytes = self.view_bytes(&indices.view())?.get(indices.offset()..).unwrap_or(&[]);
+      let index_type = match indices.index_type() {
+        IndexType::U8 => DataType::U8,
+        IndexType::U16 => DataType::U16,
+        IndexType::U32 => DataType::U32,
+      };
+      if sparse.count() * size > index_bytes.len() {
+        return invalid(format!("sparse indices of accessor {} run past the end of their buffer view", accessor.index()));
+      }
+      let targets = AccessorData {
+        components: 1,
+        normalized: false,
+        values: decode_components(index_type, index_bytes, &(0..sparse.count()).map(|i| i * size).collect::<Vec<_>>()),
+      }.to_u32();
+      let value_bytes = self.view_bytes(&sparse.values().view())?.get(sparse.values().offset()..).unwrap_or(&[]);
+      let replacements = read_elements(value_bytes, element_size, sparse.count(), "sparse values")?;
+      for (i, target) in targets.iter().enumerate() {
+        let target = *target as usize;
+        if target >= count {
+          return invalid(format!("sparse index {} of accessor {} is out of range", target, accessor.index()));
+        }
+        let (to, from) = (target * components..(target + 1) * components, i * components..(i + 1) * components);
+        match (&mut values, &replacements) {
+          (AccessorValues::I8(v), AccessorValues::I8(r)) => v[to].copy_from_slice(&r[from]),
+          (AccessorValues::U8(v), AccessorValues::U8(r)) => v[to].copy_from_slice(&r[from]),
+          (AccessorValues::I16(v), AccessorValues::I16(r)) => v[to].copy_from_slice(&r[from]),
+          (AccessorValues::U16(v), AccessorValues::U16(r)) => v[to].copy_from_slice(&r[from]),
+          (AccessorValues::U32(v), AccessorValues::U32(r)) => v[to].copy_from_slice(&r[from]),
+          (AccessorValues::F32(v), AccessorValues::F32(r)) => v[to].copy_from_slice(&r[from]),
+          _ => unreachable!(),
+        }
+      }
+    }
+
+    Ok(AccessorData {
+      components,
+      normalized: accessor.normalized(),
+      values,
+    })
+  }
+
+  // The encoded bytes of an image, such as a PNG file
+  pub fn image_bytes(&self, image: &gltf::Image) -> Result<Vec<u8>, GltfError> {
+    match image.source() {
+      gltf::image::Source::View { view, .. } => Ok(self.view_bytes(&view)?.to_vec()),
+      gltf::image::Source::Uri { uri, .. } => resolve_uri(self.base_dir.as_deref(), uri),
+    }
+  }
+
+  // Decodes the images and applies the samplers of every texture
+  #[cfg(feature = "image")]
+  pub fn load_textures(&self) -> Result<HashMap<usize, Texture>, GltfError> {
+    // glTF texture coordinates start at the top of the image
+    let options = ImageOptions {
+      flip_vertically: false,
+      ..ImageOptions::default()
+    };
+    let mut textures = HashMap::new();
+    for gltf_texture in self.document.textures() {
+      let bytes = self.image_bytes(&gltf_texture.source())?;
+      let mut texture = Texture::from_image_bytes(&bytes, &options)?;
+      let sampler = gltf_texture.sampler();
+      let min = match sampler.min_filter() {
+        Some(gltf::texture::MinFilter::Nearest) => FilterMode::Nearest,
+        Some(gltf::texture::MinFilter::Linear) => FilterMode::Linear,
+        Some(gltf::texture::MinFilter::NearestMipmapNearest) => FilterMode::NearestMipmapNearest,
+        Some(gltf::texture::MinFilter::NearestMipmapLinear) => FilterMode::NearestMipmapLinear,
+        Some(gltf::texture::MinFilter::LinearMipmapNearest) => FilterMode::LinearMipmapNearest,
+        Some(gltf::texture::MinFilter::LinearMipmapLinear) | None => FilterMode::LinearMipmapLinear,
+      };
+      let mag = match sampler.mag_filter() {
+        Some(gltf::texture::MagFilter::Nearest) => FilterMode::Nearest,
+        _ => FilterMode::Linear,
+      };
+      let wrap = |mode| match mode {
+        gltf::texture::WrappingMode::ClampToEdge => WrapMode::ClampToEdge,
+        gltf::texture::WrappingMode::MirroredRepeat => WrapMode::MirroredRepeat,
+        gltf::texture::WrappingMode::Repeat => WrapMode::Repeat,
+      };
+      if min.uses_mipmaps() {
+        texture.generate_mipmaps();
+      }
+      texture.set_filter_mode(min, mag);
+      texture.set_wrap_mode(wrap(sampler.wrap_s()), wrap(sampler.wrap_t()));
+      textures.insert(gltf_texture.index(), texture);
+    }
+    Ok(textures)
+  }
+
+  // Vertex data of a triangle primitive, with each semantic bound to the
+  // attribute `names` gives it. Strips and fans become triangle lists, and
+  // meshes without normals get flat ones.
+  pub fn mesh_data(&self, primitive: &gltf::Primitive, names: &AttributeNames) -> Result<MeshData, GltfError> {
+    let mut data = MeshData::new();
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for (semantic, accessor) in primitive.attributes() {
+      let values = self.accessor(&accessor)?;
+      let components = values.components;
+      let floats = values.to_f32();
+      let count = values.len();
+      let name = match semantic {
+        Semantic::Positions => {
+          data.positions = floats.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect();
+          continue;
+        },
+        Semantic::Normals => {
+          data.normals = floats.chunks_exact(3).map(|n| [n[0], n[1], n[2]]).collect();
+          continue;
+        },
+        Semantic::TexCoords(0) => {
+          data.uvs = floats.chunks_exact(2).map(|t| [t[0], t[1]]).collect();
+          continue;
+        },
+        Semantic::TexCoords(1) => &names.uv1,
+        Semantic::Tangents => &names.tangent,
+        Semantic::Colors(0) => &names.color,
+        Semantic::Joints(0) => &names.joints,
+        Semantic::Weights(0) => &names.weights,
+        // Further sets have no attribute to go to
+        _ => continue,
+      };
+      counts.push((semantic.to_string(), count));
+      data.attributes.push(VertexAttribute {
+        name: name.clone(),
+        components,
+        values: floats,
+        component_type: values.gl_type(),
+        normalized: values.normalized,
+      });
+    }
+    if data.positions.is_empty() {
+      return invalid(String::from("primitive has no POSITION attribute"));
+    }
+    for (i, target) in primitive.morph_targets().enumerate() {
+      let deltas = [
+        (target.positions(), &names.morph_position),
+        (target.normals(), &names.morph_normal),
+        (target.tangents(), &names.morph_tangent),
+      ];
+      for (accessor, prefix) in deltas.iter() {
+        if let Some(accessor) = accessor {
+          let values = self.accessor(accessor)?;
+          let name = format!("{}{}", prefix, i);
+          counts.push((name.clone(), values.len()));
+          data.attributes.push(VertexAttribute {
+            name,
+            components: values.components,
+            values: values.to_f32(),
+            component_type: values.gl_type(),
+            normalized: values.normalized,
+          });
+        }
+      }
+    }
+
+    counts.push((String::from("NORMAL"), data.normals.len()));
+    counts.push((String::from("TEXCOORD_0"), data.uvs.len()));
+    let count = data.positions.len() as u32;
+    // Every attribute needs a value per position. Missing normals and UVs
+    // are empty instead.
+    if let Some((name, n)) = counts.iter().find(|(_, n)| *n != 0 && *n != count as usize) {
+      return invalid(format!("attribute {} has {} values for {} positions", name, n, count));
+    }
+    let order = match primitive.indices() {
+      Some(indices) => self.accessor(&indices)?.to_u32(),
+      None => (0..count).collect(),
+    };
+    if let Some(index) = order.iter().find(|i| **i >= count) {
+      return invalid(format!("index {} is out of range for {} vertices", index, count));
+    }
+    data.indices = match primitive.mode() {
+      Mode::Triangles => order,
+      Mode::TriangleStrip => (0..order.len().saturating_sub(2)).flat_map(|i| {
+        let odd = i & 1;
+        vec![order[i], order[i + 1 + odd], order[i + 2 - odd]]
+      }).collect(),
+      Mode::TriangleFan => (1..order.len().saturating_sub(1)).flat_map(|i| vec![order[i], order[i + 1], order[0]]).collect(),
+      mode => return Err(GltfError::Unsupported(format!("{:?} primitives cannot be drawn", mode))),
+    };
+    if data.normals.is_empty() {
+      data.unweld();
+      data.compute_normals();
+    }
+    Ok(data)
+  }
+
+  // Builds the default scene, or the first one, with every material using
+  // `program`. Point and line primitives are skipped. Initial morph target
+  // weights go to `morph_uniform`, which should be the uniform the mixer
+  // animating the scene writes, usually MORPH_WEIGHTS_UNIFORM.
+  pub fn create_scene(&self, program: &Rc<Program>, textures: &HashMap<usize, Texture>, names: &AttributeNames, morph_uniform: &str) -> Result<GltfScene, GltfError> {
+    let materials: Vec<Rc<Material>> = self.document.materials()
+      .map(|m| Rc::new(PbrMaterial::from_gltf(&m).to_material(program.clone(), textures)))
+      .collect();
+    let default_material = Rc::new(PbrMaterial::new().to_material(program.clone(), textures));
+
+    let mut result = GltfScene {
+      scene: Scene::new(),
+      nodes: vec![None; self.document.nodes().len()],
+      materials,
+      default_material,
+      drawables: vec![Vec::new(); self.document.nodes().len()],
+      skins: Vec::new(),
+      skinned: Vec::new(),
+    };
+    let roots: Vec<gltf::Node> = match self.document.default_scene().or_else(|| self.document.scenes().next()) {
+      Some(scene) => scene.nodes().collect(),
+      None => Vec::new(),
+    };
+    for root in roots {
+      self.add_node(&mut result, &root, None, names, morph_uniform)?;
+    }
+    for skin in self.document.skins() {
+      let mut joints = Vec::new();
+      for joint in skin.joints() {
+        match result.nodes[joint.index()] {
+          Some(id) => joints.push(id),
+          None => return invalid(format!("joint {} of skin {} is not in the scene", joint.index(), skin.index())),
+        }
+      }
+      let inverse_bind_matrices: Vec<Mat4> = match skin.inverse_bind_matrices() {
+        Some(accessor) => self.accessor(&accessor)?.to_f32().chunks_exact(16).map(|m| {
+          let mut columns = [0.0; 16];
+          columns.copy_from_slice(m);
+          Mat4::from_cols_array(&columns)
+        }).collect(),
+        None => Vec::new(),
+      };
+      if !inverse_bind_matrices.is_empty() && inverse_bind_matrices.len() != joints.len() {
+        return invalid(format!("skin {} has {} joints but {} inverse bind matrices", skin.index(), joints.len(), inverse_bind_matrices.len()));
+      }
+      result.skins.push(Skeleton::new(joints, inverse_bind_matrices));
+    }
+    result.update_skins();
+    Ok(result)
+  }
+
+  // Converts the animations to clips driving the nodes of `scene`, which
+  // create_scene built from this file. Morph weight channels drive every
+  // primitive of the targeted mesh.
+  pub fn animations(&self, scene: &GltfScene) -> Result<Vec<AnimationClip>, GltfError> {
+    let mut clips = Vec::new();
+    for animation in self.document.animations() {
+      let name = match animation.name() {
+        Some(name) => String::from(name),
+        None => format!("animation {}", animation.index()),
+      };
+      let mut clip = AnimationClip::new(name);
+      for channel in animation.channels() {
+        let node = channel.target().node().index();
+        let id = match scene.nodes[node] {
+          Some(id) => id,
+          None => continue,
+        };
+        let sampler = channel.sampler();
+        let times = self.accessor(&sampler.input())?.to_f32();
+        let values = self.accessor(&sampler.output())?.to_f32();
+        let interpolation = match sampler.interpolation() {
+          GltfInterpolation::Step => Interpolation::Step,
+          GltfInterpolation::Linear => Interpolation::Linear,
+          GltfInterpolation::CubicSpline => Interpolation::CubicSpline,
+        };
+        let elements = if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+        if times.is_empty() || times.windows(2).any(|w| w[1] < w[0]) {
+          return invalid(format!("animation {} has a sampler without increasing keyframe times", animation.index()));
+        }
+        let (property, targets) = match channel.target().property() {
+          GltfProperty::Translation => (Property::Translation, vec![id]),
+          GltfProperty::Rotation => (Property::Rotation, vec![id]),
+          GltfProperty::Scale => (Property::Scale, vec![id]),
+          GltfProperty::MorphTargetWeights => {
+            (Property::MorphWeights(values.len() / (times.len() * elements)), scene.drawables[node].clone())
+          },
+        };
+        if values.len() != times.len() * elements * property.width() {
+          return invalid(format!("animation {} has {} output values for {} keyframes of {:?}", animation.index(), values.len(), times.len(), property));
+        }
+        for target in targets {
+          clip.add_channel(Channel::new(target, property, interpolation, times.clone(), values.clone()));
+        }
+      }
+      clips.push(clip);
+    }
+    Ok(clips)
+  }
+
+  fn add_node(&self, result: &mut GltfScene, node: &gltf::Node, parent: Option<NodeId>, names: &AttributeNames, morph_uniform: &str) -> Result<(), GltfError> {
+    let mut primitives = Vec::new();
+    if let Some(mesh) = node.mesh() {
+      let weights = node.weights().or_else(|| mesh.weights());
+      for primitive in mesh.primitives() {
+        if matches!(primitive.mode(), Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan) {
+          let material = match primitive.material().index() {
+            Some(index) => result.materials[index].clone(),
+            None => result.default_material.clone(),
+          };
+          let geometry = self.mesh_data(&primitive, names)?.to_geometry(material.program(), names);
+          let mut drawable = Node::with_material(material, geometry);
+          let targets = primitive.morph_targets().len();
+          if targets > 0 {
+            let mut initial = weights.map(|w| w.to_vec()).unwrap_or_default();
+            initial.resize(targets, 0.0);
+            drawable.set_uniform(String::from(morph_uniform), UniformValue::FloatArray(initial));
+          }
+          primitives.push(drawable);
+        }
+      }
+    }
+
+    // A single primitive is drawn by the node itself, more become children
+    let scene = &mut result.scene;
+    let single = if primitives.len() == 1 { primitives.pop() } else { None };
+    let single_drawn = single.is_some();
+    let id = match (parent, single) {
+      (Some(parent), Some(drawable)) => scene.add_child(parent, drawable),
+      (Some(parent), None) => scene.add_empty_child(parent),
+      (None, Some(drawable)) => scene.add(drawable),
+      (None, None) => scene.add_empty(),
+    };
+    let (t, r, s) = node.transform().decomposed();
+    scene.set_transform(id, Transform {
+      translation: Vec3::new(t[0], t[1], t[2]),
+      rotation: Quat::new(r[0], r[1], r[2], r[3]),
+      scale: Vec3::new(s[0], s[1], s[2]),
+    });
+    let drawables = &mut result.drawables[node.index()];
+    if single_drawn {
+      drawables.push(id);
+    }
+    for drawable in primitives {
+      drawables.push(scene.add_child(id, drawable));
+    }
+    result.nodes[node.index()] = Some(id);
+    if let (Some(skin), false) = (node.skin(), result.drawables[node.index()].is_empty()) {
+      result.skinned.push((node.index(), skin.index()));
+    }
+    for child in node.children() {
+      self.add_node(result, &child, Some(id), names, morph_uniform)?;
+    }
+    Ok(())
+  }
+}
+
+fn resolve_uri(base_dir: Option<&Path>, uri: &str) -> Result<Vec<u8>, GltfError> {
+  if let Some(data) = uri.strip_prefix("data:") {
+    return match data.find(";base64,") {
+      Some(start) => match decode_base64(&data[start + 8..]) {
+        Some(bytes) => Ok(bytes),
+        None => invalid(String::from("data URI is not valid base64")),
+      },
+      None => Err(GltfError::Unsupported(String::from("data URIs must be base64 encoded"))),
+    };
+  }
+  if uri.contains("://") {
+    return Err(GltfError::Unsupported(format!("cannot fetch {}", uri)));
+  }
+  match base_dir {
+    Some(dir) => read_file(&dir.join(decode_uri(uri))),
+    None => Err(GltfError::Unsupported(format!("cannot resolve {} without a base directory", uri))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // A triangle whose COLOR_0 accessor has `colors` elements, in a zeroed
+  // 48 byte buffer
+  fn triangle(colors: usize, extensions: &str) -> String {
+    format!(r#"{{
+      "asset": {{ "version": "2.0" }},
+      {}
+      "buffers": [{{ "byteLength": 48, "uri": "data:application/octet-stream;base64,{}" }}],
+      "bufferViews": [{{ "buffer": 0, "byteLength": 48 }}],
+      "accessors": [
+        {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [0, 0, 0] }},
+        {{ "bufferView": 0, "byteOffset": 36, "componentType": 5121, "normalized": true, "count": {}, "type": "VEC4" }}
+      ],
+      "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0, "COLOR_0": 1 }} }}] }}]
+    }}"#, extensions, "A".repeat(64), colors)
+  }
+
+  fn primitive_data(source: &str) -> Result<MeshData, GltfError> {
+    let data = GltfData::from_slice(source.as_bytes(), None)?;
+    let mesh = data.document.meshes().next().unwrap();
+    let primitive = mesh.primitives().next().unwrap();
+    data.mesh_data(&primitive, &AttributeNames::default())
+  }
+
+  #[test]
+  fn attributes_keep_their_component_type() {
+    let data = primitive_data(&triangle(3, "")).unwrap();
+    let color = data.attribute("a_color").unwrap();
+    assert_eq!((color.component_type, color.normalized, color.values.len()), (gli::UNSIGNED_BYTE, true, 12));
+  }
+
+  #[test]
+  fn short_attributes_are_invalid() {
+    match primitive_data(&triangle(2, "")) {
+      Err(GltfError::Invalid(message)) => assert_eq!(message, "attribute COLOR_0 has 2 values for 3 positions"),
+      other => panic!("Loaded as {:?}", other.map(|_| ())),
+    }
+  }
+
+  #[test]
+  fn required_extensions_are_unsupported() {
+    let extensions = r#""extensionsUsed": ["KHR_draco_mesh_compression"], "extensionsRequired": ["KHR_draco_mesh_compression"],"#;
+    match GltfData::from_slice(triangle(3, extensions).as_bytes(), None) {
+      Err(GltfError::Unsupported(message)) => assert!(message.contains("KHR_draco_mesh_compression")),
+      other => panic!("Loaded as {:?}", other.map(|_| ())),
+    }
+  }
+
+  fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+      let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, b)| bits | (*b as u32) << (16 - 8 * i));
+      for i in 0..4 {
+        if i <= chunk.len() {
+          encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+        } else {
+          encoded.push('=');
+        }
+      }
+    }
+    encoded
+  }
+
+  fn floats(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes().to_vec()).collect()
+  }
+
+  // A file holding `buffer` as a data URI, with the given views, accessors
+  // and meshes
+  fn document(buffer: &[u8], views: &str, accessors: &str, meshes: &str) -> GltfData {
+    let source = format!(r#"{{
+      "asset": {{ "version": "2.0" }},
+      "buffers": [{{ "byteLength": {}, "uri": "data:application/octet-stream;base64,{}" }}],
+      "bufferViews": [{}],
+      "accessors": [{}],
+      "meshes": [{}]
+    }}"#, buffer.len(), encode_base64(buffer), views, accessors, meshes);
+    GltfData::from_slice(source.as_bytes(), None).unwrap()
+  }
+
+  fn accessor_values(data: &GltfData, index: usize) -> Result<AccessorData, GltfError> {
+    data.accessor(&data.document.accessors().nth(index).unwrap())
+  }
+
+  #[test]
+  fn base64_decodes_both_alphabets() {
+    assert_eq!(decode_base64("SGVsbG8="), Some(b"Hello".to_vec()));
+    assert_eq!(decode_base64("SGVs\nbG8"), Some(b"Hello".to_vec()));
+    assert_eq!(decode_base64("+/8="), Some(vec![0xfb, 0xff]));
+    assert_eq!(decode_base64("-_8="), Some(vec![0xfb, 0xff]));
+    assert_eq!(decode_base64(""), Some(Vec::new()));
+    assert_eq!(decode_base64("SGV*"), None);
+    assert_eq!(decode_base64(&encode_base64(&[0, 1, 2, 253, 254, 255, 7])), Some(vec![0, 1, 2, 253, 254, 255, 7]));
+  }
+
+  #[test]
+  fn data_uris_must_be_base64() {
+    assert_eq!(resolve_uri(None, "data:application/octet-stream;base64,AAEC").unwrap(), vec![0, 1, 2]);
+    match resolve_uri(None, "data:text/plain,hello") {
+      Err(GltfError::Unsupported(_)) => (),
+      other => panic!("Resolved as {:?}", other),
+    }
+    match resolve_uri(None, "data:application/octet-stream;base64,AA!C") {
+      Err(GltfError::Invalid(message)) => assert_eq!(message, "data URI is not valid base64"),
+      other => panic!("Resolved as {:?}", other),
+    }
+  }
+
+  #[test]
+  fn matrix_columns_are_padded_to_four_bytes() {
+    assert_eq!(component_offsets(Dimensions::Mat2, 1), (vec![0, 1, 4, 5], 8));
+    assert_eq!(component_offsets(Dimensions::Mat3, 1), (vec![0, 1, 2, 4, 5, 6, 8, 9, 10], 12));
+    assert_eq!(component_offsets(Dimensions::Mat3, 2), (vec![0, 2, 4, 8, 10, 12, 16, 18, 20], 24));
+    assert_eq!(component_offsets(Dimensions::Mat2, 4), (vec![0, 4, 8, 12], 16));
+    // Vectors are never padded
+    assert_eq!(component_offsets(Dimensions::Vec3, 1), (vec![0, 1, 2], 3));
+  }
+
+  #[test]
+  fn padded_matrices_read_column_by_column() {
+    let buffer = [1, 2, 99, 99, 3, 4, 99, 99, 5, 6, 99, 99, 7, 8, 99, 99];
+    let data = document(
+      &buffer,
+      r#"{ "buffer": 0, "byteLength": 16 }"#,
+      r#"{ "bufferView": 0, "componentType": 5121, "count": 2, "type": "MAT2" }"#,
+      "",
+    );
+    let values = accessor_values(&data, 0).unwrap();
+    assert_eq!((values.components, values.len()), (4, 2));
+    assert_eq!(values.values, AccessorValues::U8(vec![1, 2, 3, 4, 5, 6, 7, 8]));
+  }
+
+  // Four floats, then sparse indices [1, 3] as bytes and the floats 20, 40
+  fn sparse_buffer() -> Vec<u8> {
+    let mut buffer = floats(&[1.0, 2.0, 3.0, 4.0]);
+    buffer.extend_from_slice(&[1, 3, 0, 0]);
+    buffer.extend(floats(&[20.0, 40.0]));
+    buffer
+  }
+
+  const SPARSE_VIEWS: &str = r#"
+    { "buffer": 0, "byteLength": 16 },
+    { "buffer": 0, "byteOffset": 16, "byteLength": 2 },
+    { "buffer": 0, "byteOffset": 20, "byteLength": 8 }"#;
+
+  fn sparse_accessor(view: &str) -> String {
+    format!(r#"{{
+      {} "componentType": 5126, "count": 4, "type": "SCALAR",
+      "sparse": {{
+        "count": 2,
+        "indices": {{ "bufferView": 1, "componentType": 5121 }},
+        "values": {{ "bufferView": 2 }}
+      }}
+    }}"#, view)
+  }
+
+  #[test]
+  fn sparse_values_replace_the_base_data() {
+    let accessors = format!("{}, {}", sparse_accessor(r#""bufferView": 0,"#), sparse_accessor(""));
+    let data = document(&sparse_buffer(), SPARSE_VIEWS, &accessors, "");
+    assert_eq!(accessor_values(&data, 0).unwrap().values, AccessorValues::F32(vec![1.0, 20.0, 3.0, 40.0]));
+    // Without a view the base data is all zeros
+    assert_eq!(accessor_values(&data, 1).unwrap().values, AccessorValues::F32(vec![0.0, 20.0, 0.0, 40.0]));
+  }
+
+  #[test]
+  fn sparse_indices_past_the_end_are_invalid() {
+    let mut buffer = sparse_buffer();
+    buffer[17] = 4;
+    let data = document(&buffer, SPARSE_VIEWS, &sparse_accessor(r#""bufferView": 0,"#), "");
+    match accessor_values(&data, 0) {
+      Err(GltfError::Invalid(message)) => assert_eq!(message, "sparse index 4 of accessor 0 is out of range"),
+      other => panic!("Read as {:?}", other),
+    }
+  }
+
+  // Five positions and normals drawn with `mode` and no indices
+  fn primitive_indices(mode: u32) -> Result<Vec<u32>, GltfError> {
+    let data = document(
+      &[0; 120],
+      r#"{ "buffer": 0, "byteLength": 60 }, { "buffer": 0, "byteOffset": 60, "byteLength": 60 }"#,
+      r#"{ "bufferView": 0, "componentType": 5126, "count": 5, "type": "VEC3", "min": [0, 0, 0], "max": [0, 0, 0] },
+        { "bufferView": 1, "componentType": 5126, "count": 5, "type": "VEC3" }"#,
+      &format!(r#"{{ "primitives": [{{ "attributes": {{ "POSITION": 0, "NORMAL": 1 }}, "mode": {} }}] }}"#, mode),
+    );
+    let mesh = data.document.meshes().next().unwrap();
+    let primitive = mesh.primitives().next().unwrap();
+    data.mesh_data(&primitive, &AttributeNames::default()).map(|mesh| mesh.indices)
+  }
+
+  #[test]
+  fn strips_and_fans_become_triangle_lists() {
+    assert_eq!(primitive_indices(4).unwrap(), vec![0, 1, 2, 3, 4]);
+    // Every other strip triangle is flipped to keep the winding
+    assert_eq!(primitive_indices(5).unwrap(), vec![0, 1, 2, 1, 3, 2, 2, 3, 4]);
+    assert_eq!(primitive_indices(6).unwrap(), vec![1, 2, 0, 2, 3, 0, 3, 4, 0]);
+    match primitive_indices(1) {
+      Err(GltfError::Unsupported(message)) => assert_eq!(message, "Lines primitives cannot be drawn"),
+      other => panic!("Triangulated as {:?}", other),
+    }
+  }
+}
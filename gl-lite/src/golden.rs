@@ -0,0 +1,273 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::context::{ContextError, HeadlessContext};
+use super::gli;
+use super::image_loader::ImageError;
+use super::node::Node;
+use super::readback::PixelData;
+
+// Set to regenerate reference images instead of comparing against them
+pub const UPDATE_ENV: &str = "GLLITE_UPDATE_GOLDEN";
+// Set to let golden tests pass without running where no context can be made
+pub const SKIP_ENV: &str = "GLLITE_SKIP_GOLDEN";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+  // Largest allowed difference in any single channel
+  pub channel: u8,
+  // When set, pixels over the channel tolerance still pass if their color
+  // difference (CIE76 delta E) is at most this value and their alpha is
+  // within the channel tolerance
+  pub delta_e: Option<f32>,
+  // Number of failing pixels allowed before the comparison fails
+  pub max_failing_pixels: usize,
+}
+
+impl Default for Tolerance {
+  fn default() -> Tolerance {
+    Tolerance {
+      channel: 2,
+      delta_e: Some(2.3),
+      max_failing_pixels: 0,
+    }
+  }
+}
+
+impl Tolerance {
+  pub fn exact() -> Tolerance {
+    Tolerance {
+      channel: 0,
+      delta_e: None,
+      max_failing_pixels: 0,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Comparison {
+  pub failing_pixels: usize,
+  pub max_channel_difference: u8,
+  pub max_delta_e: f32,
+}
+
+impl Comparison {
+  pub fn passes(&self, tolerance: &Tolerance) -> bool {
+    self.failing_pixels <= tolerance.max_failing_pixels
+  }
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+  Context(ContextError),
+  Image(ImageError),
+  MissingReference(PathBuf),
+  SizeMismatch { expected: (u32, u32), actual: (u32, u32) },
+  Mismatch { comparison: Comparison, diff: PathBuf, renderer: String },
+}
+
+impl fmt::Display for GoldenError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      GoldenError::Context(e) => write!(f, "{}", e),
+      GoldenError::Image(e) => write!(f, "{}", e),
+      GoldenError::MissingReference(path) => {
+        write!(f, "Reference image {} does not exist; run with {}=1 to create it", path.display(), UPDATE_ENV)
+      },
+      GoldenError::SizeMismatch { expected, actual } => {
+        write!(f, "Reference is {}x{} but the render is {}x{}", expected.0, expected.1, actual.0, actual.1)
+      },
+      GoldenError::Mismatch { comparison, diff, renderer } => write!(
+        f,
+        "{} pixels differ (max channel difference {}, max delta E {:.2}) on renderer {}; diff written to {}",
+        comparison.failing_pixels, comparison.max_channel_difference, comparison.max_delta_e, renderer, diff.display()
+      ),
+    }
+  }
+}
+
+impl Error for GoldenError {}
+
+impl From<ContextError> for GoldenError {
+  fn from(e: ContextError) -> GoldenError {
+    GoldenError::Context(e)
+  }
+}
+
+impl From<ImageError> for GoldenError {
+  fn from(e: ImageError) -> GoldenError {
+    GoldenError::Image(e)
+  }
+}
+
+impl From<image::ImageError> for GoldenError {
+  fn from(e: image::ImageError) -> GoldenError {
+    GoldenError::Image(ImageError::from(e))
+  }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+  if c <= 0.04045 {
+    c / 12.92
+  } else {
+    ((c + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+fn lab(rgb: &[u8]) -> [f32; 3] {
+  let r = srgb_to_linear(rgb[0] as f32 / 255.0);
+  let g = srgb_to_linear(rgb[1] as f32 / 255.0);
+  let b = srgb_to_linear(rgb[2] as f32 / 255.0);
+  // D65 white point
+  let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+  let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+  let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+  let f = |t: f32| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+  let (fx, fy, fz) = (f(x), f(y), f(z));
+  [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn delta_e(a: &[u8], b: &[u8]) -> f32 {
+  let (la, lb) = (lab(a), lab(b));
+  ((la[0] - lb[0]).powi(2) + (la[1] - lb[1]).powi(2) + (la[2] - lb[2]).powi(2)).sqrt()
+}
+
+// Compares two top-down RGBA8 images of the same size. Returns the
+// statistics and a diff image showing the expected image dimmed, with
+// failing pixels in red scaled by how far off they are.
+pub fn compare(expected: &[u8], actual: &[u8], tolerance: &Tolerance) -> (Comparison, Vec<u8>) {
+  if expected.len() != actual.len() || !expected.len().is_multiple_of(4) {
+    panic!("Cannot compare RGBA images of {} and {} bytes", expected.len(), actual.len());
+  }
+  let mut comparison = Comparison {
+    failing_pixels: 0,
+    max_channel_difference: 0,
+    max_delta_e: 0.0,
+  };
+  let mut diff = Vec::with_capacity(expected.len());
+  for (e, a) in expected.chunks(4).zip(actual.chunks(4)) {
+    let channel = e.iter().zip(a.iter()).map(|(x, y)| (*x as i32 - *y as i32).unsigned_abs() as u8).max().unwrap_or(0);
+    let distance = delta_e(e, a);
+    comparison.max_channel_difference = comparison.max_channel_difference.max(channel);
+    comparison.max_delta_e = comparison.max_delta_e.max(distance);
+    let alpha_ok = (e[3] as i32 - a[3] as i32).unsigned_abs() as u8 <= tolerance.channel;
+    let fails = channel > tolerance.channel && match tolerance.delta_e {
+      Some(limit) => distance > limit || !alpha_ok,
+      None => true,
+    };
+    if fails {
+      comparison.failing_pixels += 1;
+      diff.extend_from_slice(&[128u8.saturating_add(channel / 2), 0, 0, 255]);
+    } else {
+      let luma = (e[0] as u32 * 54 + e[1] as u32 * 183 + e[2] as u32 * 19) >> 8;
+      let dimmed = (luma / 4) as u8;
+      diff.extend_from_slice(&[dimmed, dimmed, dimmed, 255]);
+    }
+  }
+  (comparison, diff)
+}
+
+fn save_rgba(path: &Path, width: u32, height: u32, data: &[u8]) -> Result<(), GoldenError> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(ImageError::from)?;
+  }
+  image::save_buffer_with_format(path, data, width, height, image::ColorType::Rgba8, image::ImageFormat::Png)?;
+  Ok(())
+}
+
+// Renders into a software headless context and checks the results against
+// reference PNGs. GPU drivers rasterize and blend slightly differently, so
+// references are only comparable across machines when made in software.
+// Failed checks write `<name>.actual.png` and `<name>.diff.png` to the
+// output directory.
+pub struct GoldenHarness {
+  context: HeadlessContext,
+  reference_dir: PathBuf,
+  output_dir: PathBuf,
+  tolerance: Tolerance,
+  clear_color: [f32; 4],
+}
+
+impl GoldenHarness {
+  pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(width: i32, height: i32, reference_dir: P, output_dir: Q) -> Result<GoldenHarness, GoldenError> {
+    Ok(GoldenHarness {
+      context: HeadlessContext::software(width, height)?,
+      reference_dir: reference_dir.as_ref().to_path_buf(),
+      output_dir: output_dir.as_ref().to_path_buf(),
+      tolerance: Tolerance::default(),
+      clear_color: [0.0, 0.0, 0.0, 1.0],
+    })
+  }
+
+  pub fn context(&self) -> &HeadlessContext {
+    &self.context
+  }
+
+  pub fn set_tolerance(&mut self, tolerance: Tolerance) {
+    self.tolerance = tolerance;
+  }
+
+  pub fn set_clear_color(&mut self, color: [f32; 4]) {
+    self.clear_color = color;
+  }
+
+  // Clears the target, runs `draw` and reads the result back top-down
+  pub fn render<F: FnOnce()>(&self, draw: F) -> PixelData {
+    self.context.clear(self.clear_color);
+    draw();
+    let mut pixels = self.context.read_pixels();
+    pixels.flip_rows();
+    pixels
+  }
+
+  pub fn render_node(&self, node: &mut Node) -> PixelData {
+    self.render(|| {
+      node.program().make_current();
+      node.draw();
+    })
+  }
+
+  pub fn check<F: FnOnce()>(&self, name: &str, draw: F) -> Result<Comparison, GoldenError> {
+    let pixels = self.render(draw);
+    self.check_pixels(name, &pixels)
+  }
+
+  pub fn check_node(&self, name: &str, node: &mut Node) -> Result<Comparison, GoldenError> {
+    let pixels = self.render_node(node);
+    self.check_pixels(name, &pixels)
+  }
+
+  // Compares top-down RGBA pixels against `<reference_dir>/<name>.png`
+  pub fn check_pixels(&self, name: &str, pixels: &PixelData) -> Result<Comparison, GoldenError> {
+    if pixels.format != gli::RGBA {
+      panic!("Golden comparisons need RGBA pixels, got format {:#x}", pixels.format);
+    }
+    let (width, height) = (pixels.width as u32, pixels.height as u32);
+    let reference_path = self.reference_dir.join(format!("{}.png", name));
+    if env::var_os(UPDATE_ENV).is_some() {
+      save_rgba(&reference_path, width, height, &pixels.data)?;
+      return Ok(Comparison {
+        failing_pixels: 0,
+        max_channel_difference: 0,
+        max_delta_e: 0.0,
+      });
+    }
+    if !reference_path.exists() {
+      return Err(GoldenError::MissingReference(reference_path));
+    }
+    let reference = image::open(&reference_path)?.to_rgba8();
+    if reference.dimensions() != (width, height) {
+      return Err(GoldenError::SizeMismatch { expected: reference.dimensions(), actual: (width, height) });
+    }
+    let (comparison, diff) = compare(reference.as_raw(), &pixels.data, &self.tolerance);
+    if !comparison.passes(&self.tolerance) {
+      let diff_path = self.output_dir.join(format!("{}.diff.png", name));
+      save_rgba(&self.output_dir.join(format!("{}.actual.png", name)), width, height, &pixels.data)?;
+      save_rgba(&diff_path, width, height, &diff)?;
+      return Err(GoldenError::Mismatch { comparison, diff: diff_path, renderer: gli::renderer() });
+    }
+    Ok(comparison)
+  }
+}
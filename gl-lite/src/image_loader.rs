@@ -0,0 +1,272 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use image::{self, DynamicImage};
+use super::format::{Channels, TextureFormat};
+use super::texture::Texture;
+
+pub struct ImageOptions {
+  // GL's texture origin is the bottom-left corner, while image files start
+  // at the top-left
+  pub flip_vertically: bool,
+  pub premultiply_alpha: bool,
+  // Converts the image to this channel layout, or keeps the source layout
+  // when None
+  pub channels: Option<Channels>,
+  // Uploads 16-bit sources as 32-bit float textures instead of reducing
+  // them to 8 bits per channel
+  pub keep_16_bit: bool,
+}
+
+impl Default for ImageOptions {
+  fn default() -> ImageOptions {
+    ImageOptions {
+      flip_vertically: true,
+      premultiply_alpha: false,
+      channels: None,
+      keep_16_bit: false,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+  Io(io::Error),
+  Unsupported(String),
+  Corrupt(String),
+}
+
+impl fmt::Display for ImageError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ImageError::Io(e) => write!(f, "Failed to read image: {}", e),
+      ImageError::Unsupported(msg) => write!(f, "Unsupported image: {}", msg),
+      ImageError::Corrupt(msg) => write!(f, "Corrupt image: {}", msg),
+    }
+  }
+}
+
+impl Error for ImageError {}
+
+impl From<io::Error> for ImageError {
+  fn from(e: io::Error) -> ImageError {
+    ImageError::Io(e)
+  }
+}
+
+impl From<image::ImageError> for ImageError {
+  fn from(e: image::ImageError) -> ImageError {
+    match e {
+      image::ImageError::IoError(io) => ImageError::Io(io),
+      image::ImageError::Unsupported(u) => ImageError::Unsupported(u.to_string()),
+      other => ImageError::Corrupt(other.to_string()),
+    }
+  }
+}
+
+impl Texture {
+  pub fn from_file<P: AsRef<Path>>(path: P, options: &ImageOptions) -> Result<Texture, ImageError> {
+    let bytes = fs::read(path)?;
+    Texture::from_image_bytes(&bytes, options)
+  }
+
+  pub fn from_image_bytes(bytes: &[u8], options: &ImageOptions) -> Result<Texture, ImageError> {
+    let image = DecodedImage::from_bytes(bytes, options)?;
+    let mut texture = Texture::new();
+    image.upload(&mut texture);
+    Ok(texture)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImagePixels {
+  Bytes(Vec<u8>),
+  Floats(Vec<f32>),
+}
+
+// An image converted as ImageOptions asks, ready to upload
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedImage {
+  pub format: TextureFormat,
+  pub width: i32,
+  pub height: i32,
+  // Tightly packed rows, in upload order
+  pub pixels: ImagePixels,
+}
+
+impl DecodedImage {
+  pub fn from_bytes(bytes: &[u8], options: &ImageOptions) -> Result<DecodedImage, ImageError> {
+    Ok(DecodedImage::new(image::load_from_memory(bytes)?, options))
+  }
+
+  pub fn new(img: DynamicImage, options: &ImageOptions) -> DecodedImage {
+    let channels = options.channels.unwrap_or_else(|| source_channels(&img));
+    let img = if options.flip_vertically { img.flipv() } else { img };
+    let width = img.width() as i32;
+    let height = img.height() as i32;
+    if options.keep_16_bit && is_high_precision(&img) {
+      let mut data = to_float_channels(&img, channels);
+      if options.premultiply_alpha {
+        premultiply_f32(&mut data, channels);
+      }
+      DecodedImage {
+        format: TextureFormat::Float32(channels),
+        width,
+        height,
+        pixels: ImagePixels::Floats(data),
+      }
+    } else {
+      let mut data = to_byte_channels(&img, channels);
+      if options.premultiply_alpha {
+        premultiply_u8(&mut data, channels);
+      }
+      DecodedImage {
+        format: TextureFormat::Unorm8(channels),
+        width,
+        height,
+        pixels: ImagePixels::Bytes(data),
+      }
+    }
+  }
+
+  pub fn upload(&self, texture: &mut Texture) {
+    match &self.pixels {
+      ImagePixels::Bytes(data) => texture.set_data(self.format, self.width, self.height, data),
+      ImagePixels::Floats(data) => texture.set_data(self.format, self.width, self.height, data),
+    }
+  }
+}
+
+fn source_channels(img: &DynamicImage) -> Channels {
+  match img.color().channel_count() {
+    1 => Channels::R,
+    2 => Channels::RG,
+    3 => Channels::RGB,
+    _ => Channels::RGBA,
+  }
+}
+
+fn is_high_precision(img: &DynamicImage) -> bool {
+  img.color().bytes_per_pixel() / img.color().channel_count() > 1
+}
+
+fn to_byte_channels(img: &DynamicImage, channels: Channels) -> Vec<u8> {
+  match channels {
+    Channels::R => img.to_luma8().into_raw(),
+    Channels::RG => img.to_luma_alpha8().into_raw(),
+    Channels::RGB => img.to_rgb8().into_raw(),
+    Channels::RGBA => img.to_rgba8().into_raw(),
+  }
+}
+
+fn to_float_channels(img: &DynamicImage, channels: Channels) -> Vec<f32> {
+  let raw = match channels {
+    Channels::R => img.to_luma16().into_raw(),
+    Channels::RG => img.to_luma_alpha16().into_raw(),
+    Channels::RGB => img.to_rgb16().into_raw(),
+    Channels::RGBA => img.to_rgba16().into_raw(),
+  };
+  raw.iter().map(|v| *v as f32 / 65535.0).collect()
+}
+
+fn alpha_index(channels: Channels) -> Option<usize> {
+  match channels {
+    Channels::RG => Some(1),
+    Channels::RGBA => Some(3),
+    _ => None,
+  }
+}
+
+fn premultiply_u8(data: &mut [u8], channels: Channels) {
+  if let Some(alpha) = alpha_index(channels) {
+    for pixel in data.chunks_mut(channels.count()) {
+      let a = pixel[alpha] as u32;
+      for c in pixel[..alpha].iter_mut() {
+        *c = ((*c as u32 * a + 127) / 255) as u8;
+      }
+    }
+  }
+}
+
+fn premultiply_f32(data: &mut [f32], channels: Channels) {
+  if let Some(alpha) = alpha_index(channels) {
+    for pixel in data.chunks_mut(channels.count()) {
+      let a = pixel[alpha];
+      for c in pixel[..alpha].iter_mut() {
+        *c *= a;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use image::{ImageBuffer, Luma, LumaA, Rgba};
+
+  fn options() -> ImageOptions {
+    ImageOptions {
+      flip_vertically: false,
+      ..ImageOptions::default()
+    }
+  }
+
+  // A 1x2 image, red over half transparent green
+  fn two_rows() -> DynamicImage {
+    DynamicImage::ImageRgba8(ImageBuffer::from_fn(1, 2, |_, y| {
+      if y == 0 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 200, 0, 128]) }
+    }))
+  }
+
+  #[test]
+  fn source_layout_is_kept_by_default() {
+    let image = DecodedImage::new(two_rows(), &options());
+    assert_eq!((image.format, image.width, image.height), (TextureFormat::Unorm8(Channels::RGBA), 1, 2));
+    assert_eq!(image.pixels, ImagePixels::Bytes(vec![255, 0, 0, 255, 0, 200, 0, 128]));
+  }
+
+  #[test]
+  fn flipping_reverses_the_rows() {
+    let image = DecodedImage::new(two_rows(), &ImageOptions::default());
+    assert_eq!(image.pixels, ImagePixels::Bytes(vec![0, 200, 0, 128, 255, 0, 0, 255]));
+  }
+
+  #[test]
+  fn premultiplying_scales_color_by_alpha() {
+    let options = ImageOptions { premultiply_alpha: true, ..options() };
+    let image = DecodedImage::new(two_rows(), &options);
+    assert_eq!(image.pixels, ImagePixels::Bytes(vec![255, 0, 0, 255, 0, 100, 0, 128]));
+
+    let gray = DynamicImage::ImageLumaA8(ImageBuffer::from_pixel(1, 1, LumaA([200, 64])));
+    let image = DecodedImage::new(gray, &options);
+    assert_eq!(image.pixels, ImagePixels::Bytes(vec![50, 64]));
+  }
+
+  #[test]
+  fn channels_convert_the_layout() {
+    let options = ImageOptions { channels: Some(Channels::RGB), ..options() };
+    let image = DecodedImage::new(two_rows(), &options);
+    assert_eq!(image.format, TextureFormat::Unorm8(Channels::RGB));
+    assert_eq!(image.pixels, ImagePixels::Bytes(vec![255, 0, 0, 0, 200, 0]));
+
+    let gray = DynamicImage::ImageLuma8(ImageBuffer::from_pixel(1, 1, Luma([90])));
+    let options = ImageOptions { channels: Some(Channels::RGBA), ..ImageOptions::default() };
+    assert_eq!(DecodedImage::new(gray, &options).pixels, ImagePixels::Bytes(vec![90, 90, 90, 255]));
+  }
+
+  #[test]
+  fn sixteen_bit_sources_become_floats_when_kept() {
+    let deep = || DynamicImage::ImageLuma16(ImageBuffer::from_pixel(1, 1, Luma([65535 / 5])));
+    let options = ImageOptions { keep_16_bit: true, ..options() };
+    let image = DecodedImage::new(deep(), &options);
+    assert_eq!(image.format, TextureFormat::Float32(Channels::R));
+    assert_eq!(image.pixels, ImagePixels::Floats(vec![0.2]));
+
+    // Reduced to 8 bits unless asked, and 8-bit sources never become floats
+    assert_eq!(DecodedImage::new(deep(), &ImageOptions::default()).pixels, ImagePixels::Bytes(vec![51]));
+    let shallow = DynamicImage::ImageLuma8(ImageBuffer::from_pixel(1, 1, Luma([51])));
+    assert_eq!(DecodedImage::new(shallow, &options).format, TextureFormat::Unorm8(Channels::R));
+  }
+}
@@ -1,7 +1,40 @@
+#[cfg(all(feature = "app", not(target_arch = "wasm32")))]
+pub mod app;
+pub mod animation;
+pub mod atlas;
+pub mod camera;
+pub mod compressed;
+pub mod compute;
+// Without the app feature only the Linux EGL backend is available
+#[cfg(all(not(target_arch = "wasm32"), any(target_os = "linux", feature = "app")))]
+pub mod context;
+pub mod cube_texture;
+pub mod format;
+pub mod framebuffer;
 pub mod geometry;
+#[cfg(feature = "gltf")]
+pub mod gltf_loader;
+#[cfg(all(feature = "image", not(target_arch = "wasm32"), any(target_os = "linux", feature = "app")))]
+pub mod golden;
+#[cfg(feature = "image")]
+pub mod image_loader;
+pub mod material;
+pub mod math;
+pub mod mesh;
 pub mod node;
+pub mod obj;
+pub mod pixel_buffer;
+pub mod postprocess;
 pub mod program;
+pub mod readback;
+pub mod render_graph;
+pub mod render_queue;
+pub mod render_state;
+pub mod sampler;
+pub mod scene;
 pub mod texture;
+pub mod texture_3d;
+pub mod texture_array;
 pub mod uniforms;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,119 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::program::Program;
+use super::render_state::RenderState;
+use super::uniforms::{UniformValue, set_value_for_uniform};
+
+static NEXT_MATERIAL_ID: AtomicUsize = AtomicUsize::new(1);
+
+// Whose values a program's uniforms currently hold. `stale` lists the
+// uniforms written by someone else since the material was uploaded.
+pub(crate) struct MaterialBinding {
+  pub material: usize,
+  pub revision: u64,
+  pub stale: HashSet<String>,
+}
+
+// A program together with the uniform values, textures and render state
+// that describe a surface. Materials are meant to be shared between nodes
+// through an Rc; values set on a Node override the material's.
+pub struct Material {
+  id: usize,
+  program: Rc<Program>,
+  uniforms: RefCell<HashMap<String, UniformValue>>,
+  state: Cell<RenderState>,
+  revision: Cell<u64>,
+}
+
+impl Material {
+  pub fn new(program: Rc<Program>) -> Material {
+    Material {
+      id: NEXT_MATERIAL_ID.fetch_add(1, Ordering::Relaxed),
+      program,
+      uniforms: RefCell::new(HashMap::new()),
+      state: Cell::new(RenderState::default()),
+      revision: Cell::new(0),
+    }
+  }
+
+  // Unique for the lifetime of the process
+  pub fn id(&self) -> usize {
+    self.id
+  }
+
+  pub fn program(&self) -> &Rc<Program> {
+    &self.program
+  }
+
+  // Takes effect for every node sharing the material on their next draw
+  pub fn set_uniform(&self, name: String, value: UniformValue) {
+    self.uniforms.borrow_mut().insert(name, value);
+    self.revision.set(self.revision.get() + 1);
+  }
+
+  pub fn remove_uniform(&self, name: &str) -> Option<UniformValue> {
+    let removed = self.uniforms.borrow_mut().remove(name);
+    if removed.is_some() {
+      self.revision.set(self.revision.get() + 1);
+    }
+    removed
+  }
+
+  pub fn uniform(&self, name: &str) -> Option<UniformValue> {
+    self.uniforms.borrow().get(name).cloned()
+  }
+
+  pub fn render_state(&self) -> RenderState {
+    self.state.get()
+  }
+
+  pub fn set_render_state(&self, state: RenderState) {
+    self.state.set(state);
+  }
+
+  // GL names of the textures bound by this material's uniforms, sorted
+  pub fn textures(&self) -> Vec<u32> {
+    let mut textures: Vec<u32> = self.uniforms.borrow().values().filter_map(|u| u.texture_binding()).map(|(_, t, _)| t).collect();
+    textures.sort_unstable();
+    textures
+  }
+
+  pub(crate) fn with_uniforms<R, F: FnOnce(&HashMap<String, UniformValue>) -> R>(&self, f: F) -> R {
+    f(&self.uniforms.borrow())
+  }
+
+  // Uploads the non-texture uniforms to the current program, skipping those
+  // it still holds from the last time this material was bound. Returns the
+  // number of uniforms uploaded.
+  pub(crate) fn bind_values(&self, program: &Program) -> usize {
+    let mut binding = program.material_binding.borrow_mut();
+    let revision = self.revision.get();
+    let current = match binding.as_ref() {
+      Some(b) if b.material == self.id && b.revision == revision => Some(&b.stale),
+      _ => None,
+    };
+    let mut uploads = 0;
+    for (name, value) in self.uniforms.borrow().iter() {
+      if value.texture_binding().is_some() {
+        continue;
+      }
+      if let Some(stale) = current {
+        if !stale.contains(name) {
+          continue;
+        }
+      }
+      if let Some(uniform) = program.uniforms.get(name) {
+        set_value_for_uniform(uniform.location, value);
+        uploads += 1;
+      }
+    }
+    *binding = Some(MaterialBinding {
+      material: self.id,
+      revision,
+      stale: HashSet::new(),
+    });
+    uploads
+  }
+}
@@ -0,0 +1,241 @@
+// Conversions to and from other math crates, each behind a feature of the
+// same name. All of them share the column-major layout, so matrices convert
+// without transposing.
+
+#[cfg(feature = "glam")]
+mod glam_interop {
+  use super::super::{Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
+
+  impl From<glam::Vec2> for Vec2 {
+    fn from(v: glam::Vec2) -> Vec2 {
+      Vec2::new(v.x, v.y)
+    }
+  }
+
+  impl From<Vec2> for glam::Vec2 {
+    fn from(v: Vec2) -> glam::Vec2 {
+      glam::Vec2::new(v.x, v.y)
+    }
+  }
+
+  impl From<glam::Vec3> for Vec3 {
+    fn from(v: glam::Vec3) -> Vec3 {
+      Vec3::new(v.x, v.y, v.z)
+    }
+  }
+
+  impl From<Vec3> for glam::Vec3 {
+    fn from(v: Vec3) -> glam::Vec3 {
+      glam::Vec3::new(v.x, v.y, v.z)
+    }
+  }
+
+  impl From<glam::Vec4> for Vec4 {
+    fn from(v: glam::Vec4) -> Vec4 {
+      let [x, y, z, w] = v.to_array();
+      Vec4::new(x, y, z, w)
+    }
+  }
+
+  impl From<Vec4> for glam::Vec4 {
+    fn from(v: Vec4) -> glam::Vec4 {
+      glam::Vec4::new(v.x, v.y, v.z, v.w)
+    }
+  }
+
+  impl From<glam::Quat> for Quat {
+    fn from(q: glam::Quat) -> Quat {
+      let [x, y, z, w] = q.to_array();
+      Quat::new(x, y, z, w)
+    }
+  }
+
+  impl From<Quat> for glam::Quat {
+    fn from(q: Quat) -> glam::Quat {
+      glam::Quat::from_xyzw(q.x, q.y, q.z, q.w)
+    }
+  }
+
+  impl From<glam::Mat3> for Mat3 {
+    fn from(m: glam::Mat3) -> Mat3 {
+      Mat3::from_cols_array(&m.to_cols_array())
+    }
+  }
+
+  impl From<Mat3> for glam::Mat3 {
+    fn from(m: Mat3) -> glam::Mat3 {
+      glam::Mat3::from_cols_array(&m.to_array())
+    }
+  }
+
+  impl From<glam::Mat4> for Mat4 {
+    fn from(m: glam::Mat4) -> Mat4 {
+      Mat4::from_cols_array(&m.to_cols_array())
+    }
+  }
+
+  impl From<Mat4> for glam::Mat4 {
+    fn from(m: Mat4) -> glam::Mat4 {
+      glam::Mat4::from_cols_array(&m.to_array())
+    }
+  }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop {
+  use super::super::{Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
+
+  impl From<nalgebra::Vector2<f32>> for Vec2 {
+    fn from(v: nalgebra::Vector2<f32>) -> Vec2 {
+      Vec2::new(v.x, v.y)
+    }
+  }
+
+  impl From<Vec2> for nalgebra::Vector2<f32> {
+    fn from(v: Vec2) -> nalgebra::Vector2<f32> {
+      nalgebra::Vector2::new(v.x, v.y)
+    }
+  }
+
+  impl From<nalgebra::Vector3<f32>> for Vec3 {
+    fn from(v: nalgebra::Vector3<f32>) -> Vec3 {
+      Vec3::new(v.x, v.y, v.z)
+    }
+  }
+
+  impl From<Vec3> for nalgebra::Vector3<f32> {
+    fn from(v: Vec3) -> nalgebra::Vector3<f32> {
+      nalgebra::Vector3::new(v.x, v.y, v.z)
+    }
+  }
+
+  impl From<nalgebra::Vector4<f32>> for Vec4 {
+    fn from(v: nalgebra::Vector4<f32>) -> Vec4 {
+      Vec4::new(v.x, v.y, v.z, v.w)
+    }
+  }
+
+  impl From<Vec4> for nalgebra::Vector4<f32> {
+    fn from(v: Vec4) -> nalgebra::Vector4<f32> {
+      nalgebra::Vector4::new(v.x, v.y, v.z, v.w)
+    }
+  }
+
+  impl From<nalgebra::UnitQuaternion<f32>> for Quat {
+    fn from(q: nalgebra::UnitQuaternion<f32>) -> Quat {
+      Quat::new(q.i, q.j, q.k, q.w)
+    }
+  }
+
+  // Renormalizes, since nalgebra's unit quaternions must stay unit length
+  impl From<Quat> for nalgebra::UnitQuaternion<f32> {
+    fn from(q: Quat) -> nalgebra::UnitQuaternion<f32> {
+      nalgebra::UnitQuaternion::new_normalize(nalgebra::Quaternion::new(q.w, q.x, q.y, q.z))
+    }
+  }
+
+  impl From<nalgebra::Matrix3<f32>> for Mat3 {
+    fn from(m: nalgebra::Matrix3<f32>) -> Mat3 {
+      let mut values = [0.0; 9];
+      values.copy_from_slice(m.as_slice());
+      Mat3::from_cols_array(&values)
+    }
+  }
+
+  impl From<Mat3> for nalgebra::Matrix3<f32> {
+    fn from(m: Mat3) -> nalgebra::Matrix3<f32> {
+      nalgebra::Matrix3::from_column_slice(&m.to_array())
+    }
+  }
+
+  impl From<nalgebra::Matrix4<f32>> for Mat4 {
+    fn from(m: nalgebra::Matrix4<f32>) -> Mat4 {
+      let mut values = [0.0; 16];
+      values.copy_from_slice(m.as_slice());
+      Mat4::from_cols_array(&values)
+    }
+  }
+
+  impl From<Mat4> for nalgebra::Matrix4<f32> {
+    fn from(m: Mat4) -> nalgebra::Matrix4<f32> {
+      nalgebra::Matrix4::from_column_slice(&m.to_array())
+    }
+  }
+}
+
+#[cfg(feature = "cgmath")]
+mod cgmath_interop {
+  use super::super::{Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
+
+  impl From<cgmath::Vector2<f32>> for Vec2 {
+    fn from(v: cgmath::Vector2<f32>) -> Vec2 {
+      Vec2::new(v.x, v.y)
+    }
+  }
+
+  impl From<Vec2> for cgmath::Vector2<f32> {
+    fn from(v: Vec2) -> cgmath::Vector2<f32> {
+      cgmath::Vector2::new(v.x, v.y)
+    }
+  }
+
+  impl From<cgmath::Vector3<f32>> for Vec3 {
+    fn from(v: cgmath::Vector3<f32>) -> Vec3 {
+      Vec3::new(v.x, v.y, v.z)
+    }
+  }
+
+  impl From<Vec3> for cgmath::Vector3<f32> {
+    fn from(v: Vec3) -> cgmath::Vector3<f32> {
+      cgmath::Vector3::new(v.x, v.y, v.z)
+    }
+  }
+
+  impl From<cgmath::Vector4<f32>> for Vec4 {
+    fn from(v: cgmath::Vector4<f32>) -> Vec4 {
+      Vec4::new(v.x, v.y, v.z, v.w)
+    }
+  }
+
+  impl From<Vec4> for cgmath::Vector4<f32> {
+    fn from(v: Vec4) -> cgmath::Vector4<f32> {
+      cgmath::Vector4::new(v.x, v.y, v.z, v.w)
+    }
+  }
+
+  impl From<cgmath::Quaternion<f32>> for Quat {
+    fn from(q: cgmath::Quaternion<f32>) -> Quat {
+      Quat::new(q.v.x, q.v.y, q.v.z, q.s)
+    }
+  }
+
+  impl From<Quat> for cgmath::Quaternion<f32> {
+    fn from(q: Quat) -> cgmath::Quaternion<f32> {
+      cgmath::Quaternion::new(q.w, q.x, q.y, q.z)
+    }
+  }
+
+  impl From<cgmath::Matrix3<f32>> for Mat3 {
+    fn from(m: cgmath::Matrix3<f32>) -> Mat3 {
+      Mat3 { cols: m.into() }
+    }
+  }
+
+  impl From<Mat3> for cgmath::Matrix3<f32> {
+    fn from(m: Mat3) -> cgmath::Matrix3<f32> {
+      m.cols.into()
+    }
+  }
+
+  impl From<cgmath::Matrix4<f32>> for Mat4 {
+    fn from(m: cgmath::Matrix4<f32>) -> Mat4 {
+      Mat4 { cols: m.into() }
+    }
+  }
+
+  impl From<Mat4> for cgmath::Matrix4<f32> {
+    fn from(m: Mat4) -> cgmath::Matrix4<f32> {
+      m.cols.into()
+    }
+  }
+}
@@ -0,0 +1,720 @@
+use std::ops::{Add, Mul, Neg, Sub};
+use super::uniforms::UniformValue;
+
+mod interop;
+
+// Component-wise arithmetic shared by the vector types
+macro_rules! vector_ops {
+  ($name:ident, $($field:ident),+) => {
+    impl $name {
+      pub fn dot(self, other: $name) -> f32 {
+        0.0 $(+ self.$field * other.$field)+
+      }
+
+      pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+      }
+
+      // Returns the zero vector unchanged rather than dividing by zero
+      pub fn normalize(self) -> $name {
+        let length = self.length();
+        if length > 0.0 {
+          self * (1.0 / length)
+        } else {
+          self
+        }
+      }
+
+      pub fn lerp(self, other: $name, t: f32) -> $name {
+        self + (other - self) * t
+      }
+    }
+
+    impl Add for $name {
+      type Output = $name;
+
+      fn add(self, other: $name) -> $name {
+        $name { $($field: self.$field + other.$field),+ }
+      }
+    }
+
+    impl Sub for $name {
+      type Output = $name;
+
+      fn sub(self, other: $name) -> $name {
+        $name { $($field: self.$field - other.$field),+ }
+      }
+    }
+
+    impl Mul<f32> for $name {
+      type Output = $name;
+
+      fn mul(self, s: f32) -> $name {
+        $name { $($field: self.$field * s),+ }
+      }
+    }
+
+    impl Neg for $name {
+      type Output = $name;
+
+      fn neg(self) -> $name {
+        $name { $($field: -self.$field),+ }
+      }
+    }
+  };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vec2 {
+  pub x: f32,
+  pub y: f32,
+}
+
+impl Vec2 {
+  pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+  pub const ONE: Vec2 = Vec2 { x: 1.0, y: 1.0 };
+
+  pub fn new(x: f32, y: f32) -> Vec2 {
+    Vec2 { x, y }
+  }
+
+  pub fn extend(self, z: f32) -> Vec3 {
+    Vec3::new(self.x, self.y, z)
+  }
+}
+
+vector_ops!(Vec2, x, y);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vec3 {
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+}
+
+impl Vec3 {
+  pub const ZERO: Vec3 = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
+  pub const ONE: Vec3 = Vec3 { x: 1.0, y: 1.0, z: 1.0 };
+  pub const X: Vec3 = Vec3 { x: 1.0, y: 0.0, z: 0.0 };
+  pub const Y: Vec3 = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+  pub const Z: Vec3 = Vec3 { x: 0.0, y: 0.0, z: 1.0 };
+
+  pub fn new(x: f32, y: f32, z: f32) -> Vec3 {
+    Vec3 { x, y, z }
+  }
+
+  pub fn cross(self, other: Vec3) -> Vec3 {
+    Vec3::new(
+      self.y * other.z - self.z * other.y,
+      self.z * other.x - self.x * other.z,
+      self.x * other.y - self.y * other.x,
+    )
+  }
+
+  pub fn extend(self, w: f32) -> Vec4 {
+    Vec4::new(self.x, self.y, self.z, w)
+  }
+
+  pub fn truncate(self) -> Vec2 {
+    Vec2::new(self.x, self.y)
+  }
+}
+
+vector_ops!(Vec3, x, y, z);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vec4 {
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+  pub w: f32,
+}
+
+impl Vec4 {
+  pub const ZERO: Vec4 = Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 };
+  pub const ONE: Vec4 = Vec4 { x: 1.0, y: 1.0, z: 1.0, w: 1.0 };
+
+  pub fn new(x: f32, y: f32, z: f32, w: f32) -> Vec4 {
+    Vec4 { x, y, z, w }
+  }
+
+  pub fn truncate(self) -> Vec3 {
+    Vec3::new(self.x, self.y, self.z)
+  }
+}
+
+vector_ops!(Vec4, x, y, z, w);
+
+// Unit quaternion representing a rotation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+  pub w: f32,
+}
+
+impl Quat {
+  pub const IDENTITY: Quat = Quat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 };
+
+  pub fn new(x: f32, y: f32, z: f32, w: f32) -> Quat {
+    Quat { x, y, z, w }
+  }
+
+  // `angle` is in radians, counter-clockwise around `axis`
+  pub fn from_axis_angle(axis: Vec3, angle: f32) -> Quat {
+    let axis = axis.normalize();
+    let (s, c) = (angle * 0.5).sin_cos();
+    Quat::new(axis.x * s, axis.y * s, axis.z * s, c)
+  }
+
+  pub fn dot(self, other: Quat) -> f32 {
+    self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+  }
+
+  pub fn normalize(self) -> Quat {
+    let length = self.dot(self).sqrt();
+    if length > 0.0 {
+      Quat::new(self.x / length, self.y / length, self.z / length, self.w / length)
+    } else {
+      Quat::IDENTITY
+    }
+  }
+
+  // The inverse rotation, for unit quaternions
+  pub fn conjugate(self) -> Quat {
+    Quat::new(-self.x, -self.y, -self.z, self.w)
+  }
+
+  pub fn rotate(self, v: Vec3) -> Vec3 {
+    let u = Vec3::new(self.x, self.y, self.z);
+    let t = u.cross(v) * 2.0;
+    v + t * self.w + u.cross(t)
+  }
+
+  // Spherical interpolation along the shorter arc
+  pub fn slerp(self, other: Quat, t: f32) -> Quat {
+    let mut cos = self.dot(other);
+    let mut end = other;
+    if cos < 0.0 {
+      cos = -cos;
+      end = Quat::new(-other.x, -other.y, -other.z, -other.w);
+    }
+    let (a, b) = if cos > 0.9995 {
+      // Nearly parallel; a normalized lerp avoids dividing by sin(~0)
+      (1.0 - t, t)
+    } else {
+      let angle = cos.acos();
+      let sin = angle.sin();
+      (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+    };
+    Quat::new(
+      self.x * a + end.x * b,
+      self.y * a + end.y * b,
+      self.z * a + end.z * b,
+      self.w * a + end.w * b,
+    ).normalize()
+  }
+}
+
+// Applies `other` first, then `self`
+impl Mul for Quat {
+  type Output = Quat;
+
+  fn mul(self, other: Quat) -> Quat {
+    Quat::new(
+      self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+      self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+      self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+      self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+    )
+  }
+}
+
+// Column-major 3x3 matrix, matching GL's uniform layout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat3 {
+  pub cols: [[f32; 3]; 3],
+}
+
+impl Mat3 {
+  pub const IDENTITY: Mat3 = Mat3 {
+    cols: [
+      [1.0, 0.0, 0.0],
+      [0.0, 1.0, 0.0],
+      [0.0, 0.0, 1.0],
+    ],
+  };
+
+  pub fn from_cols_array(values: &[f32; 9]) -> Mat3 {
+    let mut m = Mat3::IDENTITY;
+    for (i, col) in m.cols.iter_mut().enumerate() {
+      col.copy_from_slice(&values[i * 3..i * 3 + 3]);
+    }
+    m
+  }
+
+  // The upper-left 3x3 block, dropping translation
+  pub fn from_mat4(m: &Mat4) -> Mat3 {
+    let mut out = Mat3::IDENTITY;
+    for (dst, src) in out.cols.iter_mut().zip(m.cols.iter()) {
+      dst.copy_from_slice(&src[..3]);
+    }
+    out
+  }
+
+  pub fn from_quat(q: Quat) -> Mat3 {
+    Mat3::from_mat4(&Mat4::from_quat(q))
+  }
+
+  pub fn transpose(&self) -> Mat3 {
+    let mut out = Mat3::IDENTITY;
+    for (c, col) in out.cols.iter_mut().enumerate() {
+      for (r, value) in col.iter_mut().enumerate() {
+        *value = self.cols[r][c];
+      }
+    }
+    out
+  }
+
+  pub fn determinant(&self) -> f32 {
+    let [a, b, c] = self.cols;
+    a[0] * (b[1] * c[2] - b[2] * c[1]) - b[0] * (a[1] * c[2] - a[2] * c[1]) + c[0] * (a[1] * b[2] - a[2] * b[1])
+  }
+
+  // Returns None for singular matrices
+  pub fn inverse(&self) -> Option<Mat3> {
+    let [[a00, a01, a02], [a10, a11, a12], [a20, a21, a22]] = self.cols;
+    let b01 = a22 * a11 - a12 * a21;
+    let b11 = -a22 * a10 + a12 * a20;
+    let b21 = a21 * a10 - a11 * a20;
+    let det = a00 * b01 + a01 * b11 + a02 * b21;
+    if det == 0.0 {
+      return None;
+    }
+    let inv = 1.0 / det;
+    Some(Mat3 {
+      cols: [
+        [b01 * inv, (-a22 * a01 + a02 * a21) * inv, (a12 * a01 - a02 * a11) * inv],
+        [b11 * inv, (a22 * a00 - a02 * a20) * inv, (-a12 * a00 + a02 * a10) * inv],
+        [b21 * inv, (-a21 * a00 + a01 * a20) * inv, (a11 * a00 - a01 * a10) * inv],
+      ],
+    })
+  }
+
+  pub fn to_array(&self) -> [f32; 9] {
+    let mut out = [0.0; 9];
+    for (i, col) in self.cols.iter().enumerate() {
+      out[i * 3..i * 3 + 3].copy_from_slice(col);
+    }
+    out
+  }
+}
+
+impl Mul for Mat3 {
+  type Output = Mat3;
+
+  fn mul(self, other: Mat3) -> Mat3 {
+    let mut out = [[0.0; 3]; 3];
+    for (c, col) in out.iter_mut().enumerate() {
+      for (r, value) in col.iter_mut().enumerate() {
+        *value = (0..3).map(|k| self.cols[k][r] * other.cols[c][k]).sum();
+      }
+    }
+    Mat3 { cols: out }
+  }
+}
+
+impl Mul<Vec3> for Mat3 {
+  type Output = Vec3;
+
+  fn mul(self, v: Vec3) -> Vec3 {
+    let c = &self.cols;
+    Vec3::new(
+      c[0][0] * v.x + c[1][0] * v.y + c[2][0] * v.z,
+      c[0][1] * v.x + c[1][1] * v.y + c[2][1] * v.z,
+      c[0][2] * v.x + c[1][2] * v.y + c[2][2] * v.z,
+    )
+  }
+}
+
+// Column-major 4x4 matrix, matching GL's uniform layout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+  pub cols: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+  pub const IDENTITY: Mat4 = Mat4 {
+    cols: [
+      [1.0, 0.0, 0.0, 0.0],
+      [0.0, 1.0, 0.0, 0.0],
+      [0.0, 0.0, 1.0, 0.0],
+      [0.0, 0.0, 0.0, 1.0],
+    ],
+  };
+
+  pub fn from_cols_array(values: &[f32; 16]) -> Mat4 {
+    let mut m = Mat4::IDENTITY;
+    for (i, col) in m.cols.iter_mut().enumerate() {
+      col.copy_from_slice(&values[i * 4..i * 4 + 4]);
+    }
+    m
+  }
+
+  pub fn from_translation(t: Vec3) -> Mat4 {
+    let mut m = Mat4::IDENTITY;
+    m.cols[3] = [t.x, t.y, t.z, 1.0];
+    m
+  }
+
+  pub fn from_scale(s: Vec3) -> Mat4 {
+    let mut m = Mat4::IDENTITY;
+    m.cols[0][0] = s.x;
+    m.cols[1][1] = s.y;
+    m.cols[2][2] = s.z;
+    m
+  }
+
+  pub fn from_quat(q: Quat) -> Mat4 {
+    let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+    let (x2, y2, z2) = (x + x, y + y, z + z);
+    let (xx, yy, zz) = (x * x2, y * y2, z * z2);
+    let (xy, xz, yz) = (x * y2, x * z2, y * z2);
+    let (wx, wy, wz) = (w * x2, w * y2, w * z2);
+    Mat4 {
+      cols: [
+        [1.0 - (yy + zz), xy + wz, xz - wy, 0.0],
+        [xy - wz, 1.0 - (xx + zz), yz + wx, 0.0],
+        [xz + wy, yz - wx, 1.0 - (xx + yy), 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+      ],
+    }
+  }
+
+  // Scale, then rotate, then translate
+  pub fn from_trs(translation: Vec3, rotation: Quat, scale: Vec3) -> Mat4 {
+    let mut m = Mat4::from_quat(rotation);
+    for (col, s) in m.cols.iter_mut().zip([scale.x, scale.y, scale.z].iter()) {
+      for value in col.iter_mut().take(3) {
+        *value *= s;
+      }
+    }
+    m.cols[3] = [translation.x, translation.y, translation.z, 1.0];
+    m
+  }
+
+  // Right-handed projection mapping depth to GL's -1..1 clip range.
+  // `fov_y` is the vertical field of view in radians.
+  pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    let f = 1.0 / (fov_y * 0.5).tan();
+    let nf = 1.0 / (near - far);
+    Mat4 {
+      cols: [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, (far + near) * nf, -1.0],
+        [0.0, 0.0, 2.0 * far * near * nf, 0.0],
+      ],
+    }
+  }
+
+  pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Mat4 {
+    let lr = 1.0 / (left - right);
+    let bt = 1.0 / (bottom - top);
+    let nf = 1.0 / (near - far);
+    Mat4 {
+      cols: [
+        [-2.0 * lr, 0.0, 0.0, 0.0],
+        [0.0, -2.0 * bt, 0.0, 0.0],
+        [0.0, 0.0, 2.0 * nf, 0.0],
+        [(left + right) * lr, (top + bottom) * bt, (far + near) * nf, 1.0],
+      ],
+    }
+  }
+
+  // View matrix for a camera at `eye` looking towards `target`
+  pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+    let f = (target - eye).normalize();
+    let s = f.cross(up).normalize();
+    let u = s.cross(f);
+    Mat4 {
+      cols: [
+        [s.x, u.x, -f.x, 0.0],
+        [s.y, u.y, -f.y, 0.0],
+        [s.z, u.z, -f.z, 0.0],
+        [-s.dot(eye), -u.dot(eye), f.dot(eye), 1.0],
+      ],
+    }
+  }
+
+  pub fn transpose(&self) -> Mat4 {
+    let mut out = Mat4::IDENTITY;
+    for (c, col) in out.cols.iter_mut().enumerate() {
+      for (r, value) in col.iter_mut().enumerate() {
+        *value = self.cols[r][c];
+      }
+    }
+    out
+  }
+
+  // Returns None for singular matrices
+  pub fn inverse(&self) -> Option<Mat4> {
+    let [[a00, a01, a02, a03], [a10, a11, a12, a13], [a20, a21, a22, a23], [a30, a31, a32, a33]] = self.cols;
+    let b00 = a00 * a11 - a01 * a10;
+    let b01 = a00 * a12 - a02 * a10;
+    let b02 = a00 * a13 - a03 * a10;
+    let b03 = a01 * a12 - a02 * a11;
+    let b04 = a01 * a13 - a03 * a11;
+    let b05 = a02 * a13 - a03 * a12;
+    let b06 = a20 * a31 - a21 * a30;
+    let b07 = a20 * a32 - a22 * a30;
+    let b08 = a20 * a33 - a23 * a30;
+    let b09 = a21 * a32 - a22 * a31;
+    let b10 = a21 * a33 - a23 * a31;
+    let b11 = a22 * a33 - a23 * a32;
+    let det = b00 * b11 - b01 * b10 + b02 * b09 + b03 * b08 - b04 * b07 + b05 * b06;
+    if det == 0.0 {
+      return None;
+    }
+    let inv = 1.0 / det;
+    Some(Mat4 {
+      cols: [
+        [
+          (a11 * b11 - a12 * b10 + a13 * b09) * inv,
+          (a02 * b10 - a01 * b11 - a03 * b09) * inv,
+          (a31 * b05 - a32 * b04 + a33 * b03) * inv,
+          (a22 * b04 - a21 * b05 - a23 * b03) * inv,
+        ],
+        [
+          (a12 * b08 - a10 * b11 - a13 * b07) * inv,
+          (a00 * b11 - a02 * b08 + a03 * b07) * inv,
+          (a32 * b02 - a30 * b05 - a33 * b01) * inv,
+          (a20 * b05 - a22 * b02 + a23 * b01) * inv,
+        ],
+        [
+          (a10 * b10 - a11 * b08 + a13 * b06) * inv,
+          (a01 * b08 - a00 * b10 - a03 * b06) * inv,
+          (a30 * b04 - a31 * b02 + a33 * b00) * inv,
+          (a21 * b02 - a20 * b04 - a23 * b00) * inv,
+        ],
+        [
+          (a11 * b07 - a10 * b09 - a12 * b06) * inv,
+          (a00 * b09 - a01 * b07 + a02 * b06) * inv,
+          (a31 * b01 - a30 * b03 - a32 * b00) * inv,
+          (a20 * b03 - a21 * b01 + a22 * b00) * inv,
+        ],
+      ],
+    })
+  }
+
+  // Inverse-transpose of the upper 3x3 block, for transforming normals under
+  // non-uniform scale. Falls back to the block itself when it is singular.
+  pub fn normal_matrix(&self) -> Mat3 {
+    let m = Mat3::from_mat4(self);
+    match m.inverse() {
+      Some(inverse) => inverse.transpose(),
+      None => m,
+    }
+  }
+
+  pub fn transform_point(&self, p: Vec3) -> Vec3 {
+    let c = &self.cols;
+    Vec3::new(
+      c[0][0] * p.x + c[1][0] * p.y + c[2][0] * p.z + c[3][0],
+      c[0][1] * p.x + c[1][1] * p.y + c[2][1] * p.z + c[3][1],
+      c[0][2] * p.x + c[1][2] * p.y + c[2][2] * p.z + c[3][2],
+    )
+  }
+
+  // Like transform_point, followed by the perspective divide
+  pub fn project_point(&self, p: Vec3) -> Vec3 {
+    let v = *self * p.extend(1.0);
+    if v.w != 0.0 {
+      v.truncate() * (1.0 / v.w)
+    } else {
+      v.truncate()
+    }
+  }
+
+  // Ignores translation
+  pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+    Mat3::from_mat4(self) * v
+  }
+
+  pub fn to_array(&self) -> [f32; 16] {
+    let mut out = [0.0; 16];
+    for (i, col) in self.cols.iter().enumerate() {
+      out[i * 4..i * 4 + 4].copy_from_slice(col);
+    }
+    out
+  }
+}
+
+impl Mul for Mat4 {
+  type Output = Mat4;
+
+  fn mul(self, other: Mat4) -> Mat4 {
+    let mut out = [[0.0; 4]; 4];
+    for (c, col) in out.iter_mut().enumerate() {
+      for (r, value) in col.iter_mut().enumerate() {
+        *value = (0..4).map(|k| self.cols[k][r] * other.cols[c][k]).sum();
+      }
+    }
+    Mat4 { cols: out }
+  }
+}
+
+impl Mul<Vec4> for Mat4 {
+  type Output = Vec4;
+
+  fn mul(self, v: Vec4) -> Vec4 {
+    let c = &self.cols;
+    let row = |r: usize| c[0][r] * v.x + c[1][r] * v.y + c[2][r] * v.z + c[3][r] * v.w;
+    Vec4::new(row(0), row(1), row(2), row(3))
+  }
+}
+
+impl From<Vec2> for UniformValue {
+  fn from(v: Vec2) -> UniformValue {
+    UniformValue::FloatVec2(v.x, v.y)
+  }
+}
+
+impl From<Vec3> for UniformValue {
+  fn from(v: Vec3) -> UniformValue {
+    UniformValue::FloatVec3(v.x, v.y, v.z)
+  }
+}
+
+impl From<Vec4> for UniformValue {
+  fn from(v: Vec4) -> UniformValue {
+    UniformValue::FloatVec4(v.x, v.y, v.z, v.w)
+  }
+}
+
+impl From<Mat3> for UniformValue {
+  fn from(m: Mat3) -> UniformValue {
+    UniformValue::FloatMat3(m.to_array())
+  }
+}
+
+impl From<Mat4> for UniformValue {
+  fn from(m: Mat4) -> UniformValue {
+    UniformValue::FloatMat4(m.to_array())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::FRAC_PI_2;
+
+  const EPSILON: f32 = 1e-4;
+
+  fn assert_vec3_eq(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < EPSILON, "{:?} != {:?}", a, b);
+  }
+
+  fn assert_mat4_eq(a: &Mat4, b: &Mat4) {
+    let close = a.to_array().iter().zip(b.to_array().iter()).all(|(x, y)| (x - y).abs() < EPSILON);
+    assert!(close, "{:?} != {:?}", a, b);
+  }
+
+  fn assert_mat3_eq(a: &Mat3, b: &Mat3) {
+    let close = a.to_array().iter().zip(b.to_array().iter()).all(|(x, y)| (x - y).abs() < EPSILON);
+    assert!(close, "{:?} != {:?}", a, b);
+  }
+
+  // The same rotation, allowing for q and -q
+  fn assert_quat_eq(a: Quat, b: Quat) {
+    assert!(a.dot(b).abs() > 1.0 - EPSILON, "{:?} != {:?}", a, b);
+  }
+
+  fn sample_transform() -> Mat4 {
+    let rotation = Quat::from_axis_angle(Vec3::new(1.0, 2.0, 3.0), 0.7);
+    Mat4::from_trs(Vec3::new(3.0, -2.0, 5.0), rotation, Vec3::new(2.0, 0.5, 3.0))
+  }
+
+  #[test]
+  fn mat4_inverse_round_trips() {
+    let m = sample_transform();
+    let inverse = m.inverse().unwrap();
+    assert_mat4_eq(&(m * inverse), &Mat4::IDENTITY);
+    assert_mat4_eq(&(inverse * m), &Mat4::IDENTITY);
+    assert_mat4_eq(&Mat4::IDENTITY.inverse().unwrap(), &Mat4::IDENTITY);
+
+    let p = Vec3::new(0.5, -1.0, 4.0);
+    assert_vec3_eq(inverse.transform_point(m.transform_point(p)), p);
+  }
+
+  #[test]
+  fn mat4_inverse_of_singular_matrix_is_none() {
+    assert!(Mat4::from_scale(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+  }
+
+  #[test]
+  fn perspective_maps_frustum_to_clip_cube() {
+    let (fov_y, aspect, near, far) = (FRAC_PI_2, 2.0, 0.5, 10.0);
+    let projection = Mat4::perspective(fov_y, aspect, near, far);
+    assert_vec3_eq(projection.project_point(Vec3::new(0.0, 0.0, -near)), Vec3::new(0.0, 0.0, -1.0));
+    assert_vec3_eq(projection.project_point(Vec3::new(0.0, 0.0, -far)), Vec3::new(0.0, 0.0, 1.0));
+    // tan(fov_y / 2) is 1, so the top right corner of the near plane
+    let corner = Vec3::new(near * aspect, near, -near);
+    assert_vec3_eq(projection.project_point(corner), Vec3::new(1.0, 1.0, -1.0));
+
+    let inverse = projection.inverse().unwrap();
+    assert_mat4_eq(&(projection * inverse), &Mat4::IDENTITY);
+  }
+
+  #[test]
+  fn look_at_moves_the_eye_to_the_origin_facing_down_negative_z() {
+    let eye = Vec3::new(1.0, 2.0, 3.0);
+    let view = Mat4::look_at(eye, Vec3::new(1.0, 2.0, 0.0), Vec3::Y);
+    assert_vec3_eq(view.transform_point(eye), Vec3::ZERO);
+    assert_vec3_eq(view.transform_point(Vec3::new(1.0, 2.0, 0.0)), Vec3::new(0.0, 0.0, -3.0));
+    assert_vec3_eq(view.transform_point(Vec3::new(2.0, 3.0, 0.0)), Vec3::new(1.0, 1.0, -3.0));
+    assert_vec3_eq(view.inverse().unwrap().transform_point(Vec3::ZERO), eye);
+  }
+
+  #[test]
+  fn quat_rotate_matches_axis_angle_and_matrix() {
+    let quarter = Quat::from_axis_angle(Vec3::Z, FRAC_PI_2);
+    assert_vec3_eq(quarter.rotate(Vec3::X), Vec3::Y);
+    assert_vec3_eq(quarter.conjugate().rotate(Vec3::Y), Vec3::X);
+
+    let q = Quat::from_axis_angle(Vec3::new(-1.0, 0.5, 2.0), 1.3);
+    let v = Vec3::new(0.3, -2.0, 1.5);
+    assert_vec3_eq(q.rotate(v), Mat4::from_quat(q).transform_vector(v));
+    assert_vec3_eq((q * quarter).rotate(v), q.rotate(quarter.rotate(v)));
+  }
+
+  #[test]
+  fn quat_slerp_interpolates_along_the_shorter_arc() {
+    let a = Quat::from_axis_angle(Vec3::Y, 0.2);
+    let b = Quat::from_axis_angle(Vec3::Y, 1.4);
+    assert_quat_eq(a.slerp(b, 0.0), a);
+    assert_quat_eq(a.slerp(b, 1.0), b);
+    assert_quat_eq(a.slerp(b, 0.25), Quat::from_axis_angle(Vec3::Y, 0.5));
+
+    let negated = Quat::new(-b.x, -b.y, -b.z, -b.w);
+    assert_quat_eq(a.slerp(negated, 0.5), Quat::from_axis_angle(Vec3::Y, 0.8));
+    // Nearly parallel rotations take the normalized lerp path
+    let c = Quat::from_axis_angle(Vec3::Y, 0.201);
+    assert_quat_eq(a.slerp(c, 0.5), Quat::from_axis_angle(Vec3::Y, 0.2005));
+  }
+
+  #[test]
+  fn mat3_inverse_round_trips() {
+    let m = Mat3::from_cols_array(&[2.0, 1.0, 0.0, -1.0, 3.0, 2.0, 0.5, 0.0, 1.0]);
+    let inverse = m.inverse().unwrap();
+    assert_mat3_eq(&(m * inverse), &Mat3::IDENTITY);
+    assert_mat3_eq(&(inverse * m), &Mat3::IDENTITY);
+    assert_mat3_eq(&Mat3::IDENTITY.inverse().unwrap(), &Mat3::IDENTITY);
+    assert!((m.determinant() * inverse.determinant() - 1.0).abs() < EPSILON);
+
+    let rotation = Mat3::from_quat(Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 0.9));
+    assert_mat3_eq(&rotation.inverse().unwrap(), &rotation.transpose());
+    assert!(Mat3::from_cols_array(&[1.0, 2.0, 3.0, 2.0, 4.0, 6.0, 0.0, 1.0, 0.0]).inverse().is_none());
+  }
+}
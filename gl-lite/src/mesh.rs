@@ -0,0 +1,252 @@
+use super::geometry::Geometry;
+use super::gli;
+use super::math::Vec3;
+use super::program::Program;
+
+// Names of the program attributes mesh data is bound to
+#[derive(Clone, Debug)]
+pub struct AttributeNames {
+  pub position: String,
+  pub normal: String,
+  pub uv: String,
+  pub uv1: String,
+  pub tangent: String,
+  pub color: String,
+  pub joints: String,
+  pub weights: String,
+  // Prefixes for morph target deltas, target i going to e.g. a_morph_position0
+  pub morph_position: String,
+  pub morph_normal: String,
+  pub morph_tangent: String,
+}
+
+impl Default for AttributeNames {
+  fn default() -> AttributeNames {
+    AttributeNames {
+      position: String::from("a_position"),
+      normal: String::from("a_normal"),
+      uv: String::from("a_uv"),
+      uv1: String::from("a_uv1"),
+      tangent: String::from("a_tangent"),
+      color: String::from("a_color"),
+      joints: String::from("a_joints"),
+      weights: String::from("a_weights"),
+      morph_position: String::from("a_morph_position"),
+      morph_normal: String::from("a_morph_normal"),
+      morph_tangent: String::from("a_morph_tangent"),
+    }
+  }
+}
+
+// Any other per-vertex values, bound to the program attribute `name`
+#[derive(Clone, Debug)]
+pub struct VertexAttribute {
+  pub name: String,
+  pub components: usize,
+  // `components` values per vertex, as the shader reads them: normalized
+  // integers are already mapped to 0..1 or -1..1
+  pub values: Vec<f32>,
+  // The GL type the values are uploaded as, such as UNSIGNED_BYTE for
+  // normalized colors or UNSIGNED_SHORT for joint indices
+  pub component_type: u32,
+  pub normalized: bool,
+}
+
+impl VertexAttribute {
+  pub fn new(name: &str, components: usize, values: Vec<f32>) -> VertexAttribute {
+    VertexAttribute {
+      name: String::from(name),
+      components,
+      values,
+      component_type: gli::FLOAT,
+      normalized: false,
+    }
+  }
+}
+
+// Indexed triangle data kept on the CPU, ready to be turned into Geometry.
+// `normals`, `uvs` and `attributes` are either empty or have one entry per
+// position.
+#[derive(Clone, Debug)]
+pub struct MeshData {
+  pub positions: Vec<[f32; 3]>,
+  pub normals: Vec<[f32; 3]>,
+  pub uvs: Vec<[f32; 2]>,
+  pub attributes: Vec<VertexAttribute>,
+  pub indices: Vec<u32>,
+}
+
+fn vec3(v: [f32; 3]) -> Vec3 {
+  Vec3::new(v[0], v[1], v[2])
+}
+
+impl Default for MeshData {
+  fn default() -> MeshData {
+    MeshData::new()
+  }
+}
+
+impl MeshData {
+  pub fn new() -> MeshData {
+    MeshData {
+      positions: Vec::new(),
+      normals: Vec::new(),
+      uvs: Vec::new(),
+      attributes: Vec::new(),
+      indices: Vec::new(),
+    }
+  }
+
+  pub fn attribute(&self, name: &str) -> Option<&VertexAttribute> {
+    self.attributes.iter().find(|a| a.name == name)
+  }
+
+  pub fn vertex_count(&self) -> usize {
+    self.positions.len()
+  }
+
+  pub fn triangle_count(&self) -> usize {
+    self.indices.len() / 3
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.indices.is_empty()
+  }
+
+  // (min, max) corners of the positions, None when there are none
+  pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+    let first = *self.positions.first()?;
+    Some(self.positions.iter().fold((first, first), |(mut min, mut max), p| {
+      for i in 0..3 {
+        min[i] = min[i].min(p[i]);
+        max[i] = max[i].max(p[i]);
+      }
+      (min, max)
+    }))
+  }
+
+  // Replaces the normals with smooth ones, averaging the faces around each
+  // vertex weighted by their area
+  pub fn compute_normals(&mut self) {
+    let mut normals = vec![Vec3::ZERO; self.positions.len()];
+    for triangle in self.indices.chunks_exact(3) {
+      let a = vec3(self.positions[triangle[0] as usize]);
+      let b = vec3(self.positions[triangle[1] as usize]);
+      let c = vec3(self.positions[triangle[2] as usize]);
+      // Twice the area, so larger faces count for more
+      let face = (b - a).cross(c - a);
+      for i in triangle.iter() {
+        normals[*i as usize] = normals[*i as usize] + face;
+      }
+    }
+    self.normals = normals.into_iter().map(|n| {
+      let n = if n.length() > 0.0 { n.normalize() } else { Vec3::Z };
+      [n.x, n.y, n.z]
+    }).collect();
+  }
+
+  // Gives every triangle its own vertices, so compute_normals afterwards
+  // produces flat normals
+  pub fn unweld(&mut self) {
+    fn expand<T: Copy>(values: &[T], indices: &[u32], components: usize) -> Vec<T> {
+      if values.is_empty() {
+        return Vec::new();
+      }
+      indices.iter().flat_map(|i| values[*i as usize * components..(*i as usize + 1) * components].iter().copied()).collect()
+    }
+    self.positions = expand(&self.positions, &self.indices, 1);
+    self.normals = expand(&self.normals, &self.indices, 1);
+    self.uvs = expand(&self.uvs, &self.indices, 1);
+    for attribute in self.attributes.iter_mut() {
+      attribute.values = expand(&attribute.values, &self.indices, attribute.components);
+    }
+    self.indices = (0..self.positions.len() as u32).collect();
+  }
+
+  // Interleaves the attributes `program` declares, in the order position,
+  // normal, uv, then `attributes`, each in its component type. Attributes
+  // the mesh has no data for are filled with zeros, and vec4s read from
+  // narrower data get a w of 1.
+  pub fn to_geometry(&self, program: &Program, names: &AttributeNames) -> Geometry {
+    let mut geometry = Geometry::new();
+    if program.get_attribute(&names.position).is_none() {
+      panic!("Cannot build mesh geometry, the program has no {} attribute", names.position);
+    }
+    let floats = |name, values, components| (name, values, components, gli::FLOAT, false);
+    let standard: [(&String, &[f32], usize, u32, bool); 3] = [
+      floats(&names.position, self.positions.as_flattened(), 3),
+      floats(&names.normal, self.normals.as_flattened(), 3),
+      floats(&names.uv, self.uvs.as_flattened(), 2),
+    ];
+    let extra = self.attributes.iter().map(|a| (&a.name, &a.values[..], a.components, a.component_type, a.normalized));
+    let mut streams: Vec<Stream> = Vec::new();
+    for (name, values, components, mut gl_type, normalized) in standard.iter().cloned().chain(extra) {
+      if let Some(attr) = program.get_attribute(name) {
+        let (size, program_type) = gli::get_attribute_size_and_type(attr.gl_type, false);
+        // Float data going to an integer attribute is stored as the
+        // attribute's own integer type
+        if gl_type == gli::FLOAT && program_type != gli::FLOAT {
+          gl_type = program_type;
+        }
+        geometry.add_typed_attribute(attr, gl_type, normalized);
+        streams.push(Stream { size: size as usize, values, components, gl_type, normalized });
+      }
+    }
+
+    let mut data = Vec::new();
+    for i in 0..self.positions.len() {
+      for stream in streams.iter() {
+        let vertex = stream.values.get(i * stream.components..(i + 1) * stream.components).unwrap_or(&[]);
+        stream.push(&mut data, vertex);
+      }
+    }
+    geometry.buffer_bytes(&data);
+    geometry.set_indices(&self.indices);
+    geometry
+  }
+}
+
+// One attribute's data and how to write it: `size` is what the program reads
+// and `components` what the data has per vertex
+struct Stream<'a> {
+  size: usize,
+  values: &'a [f32],
+  components: usize,
+  gl_type: u32,
+  normalized: bool,
+}
+
+impl<'a> Stream<'a> {
+  // Writes `size` components from `values`, padding with zeros and a final
+  // w of 1 when the attribute is wider than the data, then pads the
+  // attribute to a multiple of 4 bytes
+  fn push(&self, data: &mut Vec<u8>, values: &[f32]) {
+    for i in 0..self.size {
+      let value = match values.get(i) {
+        Some(v) => *v,
+        None if i == 3 => 1.0,
+        None => 0.0,
+      };
+      push_component(data, value, self.gl_type, self.normalized);
+    }
+    while !data.len().is_multiple_of(4) {
+      data.push(0);
+    }
+  }
+}
+
+fn push_component(data: &mut Vec<u8>, value: f32, gl_type: u32, normalized: bool) {
+  // Normalized values are scaled back to the integer range, rounding
+  // so values decoded from the same type come back exactly
+  let scaled = |max: f32| if normalized { (value * max).round() } else { value };
+  match gl_type {
+    gli::BYTE => data.extend_from_slice(&(scaled(127.0) as i8).to_ne_bytes()),
+    gli::UNSIGNED_BYTE => data.extend_from_slice(&(scaled(255.0) as u8).to_ne_bytes()),
+    gli::SHORT => data.extend_from_slice(&(scaled(32767.0) as i16).to_ne_bytes()),
+    gli::UNSIGNED_SHORT => data.extend_from_slice(&(scaled(65535.0) as u16).to_ne_bytes()),
+    gli::INT => data.extend_from_slice(&(scaled(2147483647.0) as i32).to_ne_bytes()),
+    gli::UNSIGNED_INT => data.extend_from_slice(&(scaled(4294967295.0) as u32).to_ne_bytes()),
+    gli::FLOAT => data.extend_from_slice(&value.to_ne_bytes()),
+    other => panic!("Cannot write vertex components of GL type {:#x}", other),
+  }
+}
@@ -1,14 +1,65 @@
 use gl;
-use gl::types::{GLint, GLfloat, GLsizeiptr};
-use std::ffi::CString;
+use gl::types::{GLint, GLintptr, GLsizeiptr, GLsync};
+use std::ffi::{CStr, CString};
 use std::mem;
-use std::os::raw::c_void;
+use std::os::raw::{c_char, c_void};
 use std::ptr;
 
 pub fn init() {
 
 }
 
+pub fn context_version() -> (i32, i32) {
+  let mut major: i32 = 0;
+  let mut minor: i32 = 0;
+  unsafe {
+    gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+    gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+  }
+  (major, minor)
+}
+
+pub fn supports_shader_stage(shader_type: u32) -> bool {
+  let version = context_version();
+  match shader_type {
+    gl::VERTEX_SHADER | gl::FRAGMENT_SHADER => true,
+    gl::GEOMETRY_SHADER => version >= (3, 2),
+    gl::TESS_CONTROL_SHADER | gl::TESS_EVALUATION_SHADER => version >= (4, 0),
+    gl::COMPUTE_SHADER => version >= (4, 3),
+    _ => false,
+  }
+}
+
+pub fn supports_compute() -> bool {
+  supports_shader_stage(gl::COMPUTE_SHADER)
+}
+
+pub fn has_extension(name: &str) -> bool {
+  let mut count: i32 = 0;
+  unsafe {
+    gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+    for i in 0..count {
+      let ext = gl::GetStringi(gl::EXTENSIONS, i as u32);
+      if !ext.is_null() && CStr::from_ptr(ext as *const c_char).to_bytes() == name.as_bytes() {
+        return true;
+      }
+    }
+  }
+  false
+}
+
+// The GL_RENDERER string, e.g. "llvmpipe (LLVM 15.0.7, 256 bits)"
+pub fn renderer() -> String {
+  unsafe {
+    let raw = gl::GetString(gl::RENDERER);
+    if raw.is_null() {
+      String::new()
+    } else {
+      CStr::from_ptr(raw as *const c_char).to_string_lossy().into_owned()
+    }
+  }
+}
+
 pub fn create_shader(shader_type: u32) -> u32 {
   unsafe {
     gl::CreateShader(shader_type)
@@ -78,12 +129,166 @@ pub fn clear_color(r: f32, g: f32, b: f32, a: f32) {
   }
 }
 
+pub fn clear(mask: u32) {
+  unsafe {
+    gl::Clear(mask);
+  }
+}
+
+pub fn viewport(x: i32, y: i32, width: i32, height: i32) {
+  unsafe {
+    gl::Viewport(x, y, width, height);
+  }
+}
+
+fn set_capability(capability: u32, enabled: bool) {
+  unsafe {
+    if enabled {
+      gl::Enable(capability);
+    } else {
+      gl::Disable(capability);
+    }
+  }
+}
+
+pub fn set_depth_test(enabled: bool) {
+  set_capability(gl::DEPTH_TEST, enabled);
+}
+
+pub fn depth_mask(write: bool) {
+  unsafe {
+    gl::DepthMask(if write { gl::TRUE } else { gl::FALSE });
+  }
+}
+
+pub fn set_blending(enabled: bool) {
+  set_capability(gl::BLEND, enabled);
+}
+
+pub fn blend_func(src: u32, dst: u32) {
+  unsafe {
+    gl::BlendFunc(src, dst);
+  }
+}
+
+pub fn set_face_culling(enabled: bool) {
+  set_capability(gl::CULL_FACE, enabled);
+}
+
+pub fn cull_face(face: u32) {
+  unsafe {
+    gl::CullFace(face);
+  }
+}
+
+pub fn create_framebuffer() -> u32 {
+  let mut framebuffer = 0;
+  unsafe {
+    gl::GenFramebuffers(1, &mut framebuffer);
+  }
+  framebuffer
+}
+
+pub fn bind_framebuffer(framebuffer: u32) {
+  unsafe {
+    gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+  }
+}
+
+// The framebuffer draws currently go to
+pub fn bound_framebuffer() -> u32 {
+  let mut framebuffer: GLint = 0;
+  unsafe {
+    gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut framebuffer);
+  }
+  framebuffer as u32
+}
+
+pub fn bind_read_framebuffer(framebuffer: u32) {
+  unsafe {
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+  }
+}
+
+pub fn framebuffer_texture_2d(attachment: u32, texture: u32) {
+  unsafe {
+    gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture, 0);
+  }
+}
+
+pub fn framebuffer_renderbuffer(attachment: u32, renderbuffer: u32) {
+  unsafe {
+    gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, renderbuffer);
+  }
+}
+
+pub fn draw_buffers(count: usize) {
+  let attachments: Vec<u32> = (0..count as u32).map(|i| gl::COLOR_ATTACHMENT0 + i).collect();
+  unsafe {
+    gl::DrawBuffers(count as i32, attachments.as_ptr());
+  }
+}
+
+pub fn read_buffer(attachment: u32) {
+  unsafe {
+    gl::ReadBuffer(attachment);
+  }
+}
+
+// Clears one color attachment of the bound framebuffer, ignoring the clear
+// color state
+pub fn clear_color_attachment(index: usize, color: [f32; 4]) {
+  unsafe {
+    gl::ClearBufferfv(gl::COLOR, index as i32, color.as_ptr());
+  }
+}
+
+pub fn clear_depth_attachment(depth: f32) {
+  unsafe {
+    gl::ClearBufferfv(gl::DEPTH, 0, &depth);
+  }
+}
+
+pub fn framebuffer_complete() -> bool {
+  unsafe {
+    gl::CheckFramebufferStatus(gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE
+  }
+}
+
+pub fn create_renderbuffer() -> u32 {
+  let mut renderbuffer = 0;
+  unsafe {
+    gl::GenRenderbuffers(1, &mut renderbuffer);
+  }
+  renderbuffer
+}
+
+pub fn renderbuffer_storage(renderbuffer: u32, internal_format: u32, width: i32, height: i32) {
+  unsafe {
+    gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+    gl::RenderbufferStorage(gl::RENDERBUFFER, internal_format, width, height);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+  }
+}
+
+pub fn delete_framebuffer(framebuffer: u32) {
+  unsafe {
+    gl::DeleteFramebuffers(1, &framebuffer);
+  }
+}
+
+pub fn delete_renderbuffer(renderbuffer: u32) {
+  unsafe {
+    gl::DeleteRenderbuffers(1, &renderbuffer);
+  }
+}
+
 pub fn get_active_uniform_count(program: u32) -> u32 {
   let mut count: i32 = 0;
   unsafe {
     gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
   }
-  return count as u32;
+  count as u32
 }
 
 pub fn get_active_uniform(program: u32, index: u32) -> (String, i32, u32) {
@@ -116,7 +321,7 @@ pub fn get_active_attribute_count(program: u32) -> u32 {
   unsafe {
     gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
   }
-  return count as u32;
+  count as u32
 }
 
 pub fn get_active_attribute(program: u32, index: u32) -> (String, i32, u32) {
@@ -154,17 +359,36 @@ pub fn get_attribute_size_and_type(attr_type: u32, normalize: bool) -> (i32, u32
     gl::FLOAT_MAT2 => (4, t),
     gl::FLOAT_MAT3 => (9, t),
     gl::FLOAT_MAT4 => (16, t),
+    // Integer attributes are read as they are stored, never normalized
+    gl::INT => (1, gl::INT),
+    gl::INT_VEC2 => (2, gl::INT),
+    gl::INT_VEC3 => (3, gl::INT),
+    gl::INT_VEC4 => (4, gl::INT),
+    gl::UNSIGNED_INT => (1, gl::UNSIGNED_INT),
+    gl::UNSIGNED_INT_VEC2 => (2, gl::UNSIGNED_INT),
+    gl::UNSIGNED_INT_VEC3 => (3, gl::UNSIGNED_INT),
+    gl::UNSIGNED_INT_VEC4 => (4, gl::UNSIGNED_INT),
     _ => (1, t),
   }
 }
 
+// Whether a program attribute of this type needs vertex_attrib_i_pointer
+pub fn is_integer_attribute(attr_type: u32) -> bool {
+  matches!(get_attribute_size_and_type(attr_type, false).1, gl::INT | gl::UNSIGNED_INT)
+}
+
 pub fn size_of_type(gl_type: u32) -> i32 {
   match gl_type {
     gl::BYTE => 1,
     gl::UNSIGNED_BYTE => 1,
     gl::SHORT => 2,
     gl::UNSIGNED_SHORT => 2,
+    gl::HALF_FLOAT => 2,
+    gl::INT => 4,
+    gl::UNSIGNED_INT => 4,
+    gl::UNSIGNED_INT_24_8 => 4,
     gl::FLOAT => 4,
+    gl::FLOAT_32_UNSIGNED_INT_24_8_REV => 8,
     _ => 1,
   }
 }
@@ -177,6 +401,12 @@ pub fn create_vertex_array() -> u32 {
   }
 }
 
+pub fn delete_vertex_array(vao: u32) {
+  unsafe {
+    gl::DeleteVertexArrays(1, &vao);
+  }
+}
+
 pub fn bind_vertex_array(array: u32) {
   unsafe {
     gl::BindVertexArray(array);
@@ -191,6 +421,12 @@ pub fn create_buffer() -> u32 {
   }
 }
 
+pub fn delete_buffer(buffer: u32) {
+  unsafe {
+    gl::DeleteBuffers(1, &buffer);
+  }
+}
+
 pub fn bind_array_buffer(buffer: u32) {
   unsafe {
     gl::BindBuffer(gl::ARRAY_BUFFER, buffer);
@@ -203,34 +439,232 @@ pub fn bind_element_array_buffer(buffer: u32) {
   }
 }
 
-pub fn draw_arrays_triangles(count: i32) {
+pub fn draw_arrays_triangles(first: i32, count: i32) {
+  unsafe {
+    gl::DrawArrays(gl::TRIANGLES, first, count);
+  }
+}
+
+pub fn draw_elements_triangles(count: i32, index_type: u32, base_vertex: i32) {
+  unsafe {
+    if base_vertex == 0 {
+      gl::DrawElements(gl::TRIANGLES, count, index_type, ptr::null());
+    } else {
+      gl::DrawElementsBaseVertex(gl::TRIANGLES, count, index_type, ptr::null(), base_vertex);
+    }
+  }
+}
+
+pub fn patch_vertices(count: i32) {
+  unsafe {
+    gl::PatchParameteri(gl::PATCH_VERTICES, count);
+  }
+}
+
+pub fn draw_arrays_patches(first: i32, count: i32) {
+  unsafe {
+    gl::DrawArrays(gl::PATCHES, first, count);
+  }
+}
+
+pub fn draw_elements_patches(count: i32, index_type: u32, base_vertex: i32) {
+  unsafe {
+    gl::DrawElementsBaseVertex(gl::PATCHES, count, index_type, ptr::null(), base_vertex);
+  }
+}
+
+pub fn dispatch_compute(x: u32, y: u32, z: u32) {
+  unsafe {
+    gl::DispatchCompute(x, y, z);
+  }
+}
+
+pub fn memory_barrier(barriers: u32) {
+  unsafe {
+    gl::MemoryBarrier(barriers);
+  }
+}
+
+pub fn bind_shader_storage_buffer(buffer: u32) {
+  unsafe {
+    gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, buffer);
+  }
+}
+
+pub fn bind_shader_storage_buffer_base(index: u32, buffer: u32) {
+  unsafe {
+    gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, index, buffer);
+  }
+}
+
+pub fn shader_storage_buffer_data(data: &[f32]) {
+  unsafe {
+    gl::BufferData(
+      gl::SHADER_STORAGE_BUFFER,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      gl::DYNAMIC_COPY
+    );
+  }
+}
+
+pub fn get_shader_storage_buffer_data(data: &mut [f32]) {
+  unsafe {
+    gl::GetBufferSubData(
+      gl::SHADER_STORAGE_BUFFER,
+      0,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_mut_ptr() as *mut c_void
+    );
+  }
+}
+
+pub fn bind_image_texture(unit: u32, texture: u32, level: i32, access: u32, format: u32) {
   unsafe {
-    gl::DrawArrays(gl::TRIANGLES, 0, count);
+    gl::BindImageTexture(unit, texture, level, gl::FALSE, 0, access, format);
   }
 }
 
-pub fn draw_elements_triangles(count: i32) {
+pub fn buffer_element_data_u16(data: &[u16], usage: u32) {
   unsafe {
-    gl::DrawElements(gl::TRIANGLES, count, gl::UNSIGNED_SHORT, ptr::null());
+    gl::BufferData(
+      gl::ELEMENT_ARRAY_BUFFER,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      usage
+    );
   }
 }
 
-pub fn buffer_array_data(data: &[f32]) {
-  let float_size = mem::size_of::<GLfloat>();
+pub fn buffer_element_data_u32(data: &[u32], usage: u32) {
+  unsafe {
+    gl::BufferData(
+      gl::ELEMENT_ARRAY_BUFFER,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      usage
+    );
+  }
+}
+
+pub fn buffer_array_data<T>(data: &[T], usage: u32) {
   unsafe {
     gl::BufferData(
       gl::ARRAY_BUFFER,
-      (data.len() * float_size) as GLsizeiptr,
-      &data[0] as *const f32 as *const c_void,
-      gl::STATIC_DRAW
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      usage
     );
   }
 }
 
+pub fn buffer_array_sub_data<T>(offset: i32, data: &[T]) {
+  unsafe {
+    gl::BufferSubData(
+      gl::ARRAY_BUFFER,
+      offset as GLintptr,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void
+    );
+  }
+}
+
+pub fn orphan_array_buffer(size: i32, usage: u32) {
+  unsafe {
+    gl::BufferData(gl::ARRAY_BUFFER, size as GLsizeiptr, ptr::null(), usage);
+  }
+}
+
+pub fn supports_buffer_storage() -> bool {
+  context_version() >= (4, 4)
+}
+
+pub struct MappedBuffer {
+  ptr: *mut u8,
+  size: usize,
+}
+
+impl MappedBuffer {
+  pub fn size(&self) -> usize {
+    self.size
+  }
+
+  pub fn write<T>(&self, offset: usize, data: &[T]) {
+    let length = mem::size_of_val(data);
+    if offset + length > self.size {
+      panic!("Cannot write {} bytes at offset {} into a mapping of {} bytes", length, offset, self.size);
+    }
+    unsafe {
+      ptr::copy_nonoverlapping(data.as_ptr() as *const u8, self.ptr.add(offset), length);
+    }
+  }
+}
+
+pub fn map_persistent_array_buffer(size: i32) -> MappedBuffer {
+  let flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+  unsafe {
+    gl::BufferStorage(gl::ARRAY_BUFFER, size as GLsizeiptr, ptr::null(), flags);
+    let ptr = gl::MapBufferRange(gl::ARRAY_BUFFER, 0, size as GLsizeiptr, flags);
+    if ptr.is_null() {
+      panic!("Failed to map persistent buffer of {} bytes", size);
+    }
+    MappedBuffer {
+      ptr: ptr as *mut u8,
+      size: size as usize,
+    }
+  }
+}
+
+// Invalidates every MappedBuffer of the bound array buffer
+pub fn unmap_array_buffer() {
+  unsafe {
+    gl::UnmapBuffer(gl::ARRAY_BUFFER);
+  }
+}
+
+pub struct Fence(GLsync);
+
+pub fn fence_sync() -> Fence {
+  unsafe {
+    Fence(gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0))
+  }
+}
+
+pub fn wait_sync(fence: &Fence) {
+  unsafe {
+    loop {
+      let result = gl::ClientWaitSync(fence.0, gl::SYNC_FLUSH_COMMANDS_BIT, 1_000_000);
+      if result != gl::TIMEOUT_EXPIRED {
+        break;
+      }
+    }
+  }
+}
+
+// Checks whether the fence has signaled without blocking
+pub fn poll_sync(fence: &Fence) -> bool {
+  unsafe {
+    let result = gl::ClientWaitSync(fence.0, gl::SYNC_FLUSH_COMMANDS_BIT, 0);
+    result == gl::ALREADY_SIGNALED || result == gl::CONDITION_SATISFIED
+  }
+}
+
+pub fn delete_sync(fence: Fence) {
+  unsafe {
+    gl::DeleteSync(fence.0);
+  }
+}
+
 pub fn vertex_attrib_pointer(location: u32, size: i32, gl_type: u32, normalized: bool, stride: i32, offset: i32) {
   unsafe {
     let normalized_value = if normalized { gl::TRUE } else { gl::FALSE };
-    gl::VertexAttribPointer(location, size, gl_type, normalized_value, stride, ptr::null());
+    gl::VertexAttribPointer(location, size, gl_type, normalized_value, stride, offset as *const c_void);
+  }
+}
+
+pub fn vertex_attrib_i_pointer(location: u32, size: i32, gl_type: u32, stride: i32, offset: i32) {
+  unsafe {
+    gl::VertexAttribIPointer(location, size, gl_type, stride, offset as *const c_void);
   }
 }
 
@@ -264,6 +698,31 @@ pub fn uniform_4f(location: u32, x: f32, y: f32, z: f32, w: f32) {
   }
 }
 
+pub fn uniform_matrix_3fv(location: u32, values: &[f32; 9]) {
+  unsafe {
+    gl::UniformMatrix3fv(location as i32, 1, gl::FALSE, values.as_ptr());
+  }
+}
+
+pub fn uniform_matrix_4fv(location: u32, values: &[f32; 16]) {
+  unsafe {
+    gl::UniformMatrix4fv(location as i32, 1, gl::FALSE, values.as_ptr());
+  }
+}
+
+// Uploads consecutive column-major matrices to a mat4 array uniform
+pub fn uniform_matrix_4fv_array(location: u32, values: &[f32]) {
+  unsafe {
+    gl::UniformMatrix4fv(location as i32, (values.len() / 16) as i32, gl::FALSE, values.as_ptr());
+  }
+}
+
+pub fn uniform_1fv(location: u32, values: &[f32]) {
+  unsafe {
+    gl::Uniform1fv(location as i32, values.len() as i32, values.as_ptr());
+  }
+}
+
 pub fn uniform_1i(location: u32, i: i32) {
   unsafe {
     gl::Uniform1i(location as i32, i);
@@ -296,6 +755,12 @@ pub fn create_texture() -> u32 {
   }
 }
 
+pub fn delete_texture(texture: u32) {
+  unsafe {
+    gl::DeleteTextures(1, &texture);
+  }
+}
+
 pub fn active_texture(unit: u32) {
   unsafe {
     gl::ActiveTexture(gl::TEXTURE0 + unit);
@@ -308,29 +773,445 @@ pub fn bind_texture_2d(unit: u32) {
   }
 }
 
+pub fn bind_texture(target: u32, texture: u32) {
+  unsafe {
+    gl::BindTexture(target, texture);
+  }
+}
+
+pub fn tex_parameter(target: u32, param: u32, value: u32) {
+  unsafe {
+    gl::TexParameteri(target, param, value as i32);
+  }
+}
+
 pub fn tex_parameter_2d(param: u32, value: u32) {
   unsafe {
     gl::TexParameteri(gl::TEXTURE_2D, param, value as i32);
   }
 }
 
+pub fn pixel_store_unpack(alignment: i32, row_length: i32, skip_pixels: i32, skip_rows: i32) {
+  unsafe {
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, alignment);
+    gl::PixelStorei(gl::UNPACK_ROW_LENGTH, row_length);
+    gl::PixelStorei(gl::UNPACK_SKIP_PIXELS, skip_pixels);
+    gl::PixelStorei(gl::UNPACK_SKIP_ROWS, skip_rows);
+  }
+}
+
+pub fn tex_parameter_2d_f(param: u32, value: f32) {
+  unsafe {
+    gl::TexParameterf(gl::TEXTURE_2D, param, value);
+  }
+}
+
+pub fn tex_parameter_2d_fv(param: u32, values: &[f32; 4]) {
+  unsafe {
+    gl::TexParameterfv(gl::TEXTURE_2D, param, values.as_ptr());
+  }
+}
+
 pub fn tex_image_2d_from_bytes(internal_format: u32, width: i32, height: i32, format: u32, data: &[u8]) {
+  tex_image_2d_level_from_bytes(0, internal_format, width, height, format, data);
+}
+
+pub fn tex_image_2d_level_from_bytes(level: i32, internal_format: u32, width: i32, height: i32, format: u32, data: &[u8]) {
+  tex_image_2d(level, internal_format, width, height, format, gl::UNSIGNED_BYTE, Some(data));
+}
+
+pub fn tex_image_2d<T>(level: i32, internal_format: u32, width: i32, height: i32, format: u32, pixel_type: u32, data: Option<&[T]>) {
+  let pixels = match data {
+    Some(d) => d.as_ptr() as *const c_void,
+    None => ptr::null(),
+  };
+  unsafe {
+    gl::TexImage2D(gl::TEXTURE_2D, level, internal_format as i32, width, height, 0, format, pixel_type, pixels);
+  }
+}
+
+pub fn tex_sub_image_2d<T>(x: i32, y: i32, width: i32, height: i32, format: u32, pixel_type: u32, data: &[T]) {
+  unsafe {
+    gl::TexSubImage2D(gl::TEXTURE_2D, 0, x, y, width, height, format, pixel_type, data.as_ptr() as *const c_void);
+  }
+}
+
+// Reads the pixels from the bound pixel unpack buffer, starting at `offset`
+pub fn tex_sub_image_2d_from_buffer(x: i32, y: i32, width: i32, height: i32, format: u32, pixel_type: u32, offset: usize) {
+  unsafe {
+    gl::TexSubImage2D(gl::TEXTURE_2D, 0, x, y, width, height, format, pixel_type, offset as *const c_void);
+  }
+}
+
+pub fn supports_pixel_buffers() -> bool {
+  true
+}
+
+fn format_components(format: u32) -> usize {
+  match format {
+    gl::RED | gl::DEPTH_COMPONENT => 1,
+    gl::RG => 2,
+    gl::RGB | gl::BGR => 3,
+    gl::RGBA | gl::BGRA => 4,
+    _ => panic!("Cannot read back pixels in format {:#x}", format),
+  }
+}
+
+// Reads a rectangle of the current read framebuffer as tightly packed
+// unsigned bytes. Rows are returned bottom to top, as GL stores them.
+pub fn read_pixels(x: i32, y: i32, width: i32, height: i32, format: u32) -> Vec<u8> {
+  let mut data = vec![0u8; width as usize * height as usize * format_components(format)];
+  unsafe {
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl::ReadPixels(x, y, width, height, format, gl::UNSIGNED_BYTE, data.as_mut_ptr() as *mut c_void);
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+  }
+  data
+}
+
+// Starts a read into the bound pixel pack buffer; returns immediately
+pub fn read_pixels_to_buffer(x: i32, y: i32, width: i32, height: i32, format: u32) {
+  unsafe {
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl::ReadPixels(x, y, width, height, format, gl::UNSIGNED_BYTE, ptr::null_mut());
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+  }
+}
+
+pub fn read_pixels_size(width: i32, height: i32, format: u32) -> usize {
+  width as usize * height as usize * format_components(format)
+}
+
+// Reads the base level of the bound 2D texture as unsigned bytes
+pub fn get_tex_image_2d(width: i32, height: i32, format: u32) -> Vec<u8> {
+  let mut data = vec![0u8; read_pixels_size(width, height, format)];
+  unsafe {
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl::GetTexImage(gl::TEXTURE_2D, 0, format, gl::UNSIGNED_BYTE, data.as_mut_ptr() as *mut c_void);
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+  }
+  data
+}
+
+pub fn bind_pixel_pack_buffer(buffer: u32) {
+  unsafe {
+    gl::BindBuffer(gl::PIXEL_PACK_BUFFER, buffer);
+  }
+}
+
+pub fn allocate_pixel_pack_buffer(size: usize) {
+  unsafe {
+    gl::BufferData(gl::PIXEL_PACK_BUFFER, size as GLsizeiptr, ptr::null(), gl::STREAM_READ);
+  }
+}
+
+pub fn get_pixel_pack_buffer_data(data: &mut [u8]) {
+  unsafe {
+    gl::GetBufferSubData(
+      gl::PIXEL_PACK_BUFFER,
+      0,
+      data.len() as GLsizeiptr,
+      data.as_mut_ptr() as *mut c_void
+    );
+  }
+}
+
+pub fn supports_copy_image() -> bool {
+  context_version() >= (4, 3) || has_extension("GL_ARB_copy_image")
+}
+
+// Copies a region of the base level of one 2D texture into another
+pub fn copy_texture_2d(source: u32, destination: u32, width: i32, height: i32) {
+  unsafe {
+    gl::CopyImageSubData(
+      source, gl::TEXTURE_2D, 0, 0, 0, 0,
+      destination, gl::TEXTURE_2D, 0, 0, 0, 0,
+      width, height, 1,
+    );
+  }
+}
+
+pub fn bind_pixel_unpack_buffer(buffer: u32) {
+  unsafe {
+    gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, buffer);
+  }
+}
+
+pub fn pixel_unpack_buffer_data<T>(data: &[T]) {
+  unsafe {
+    gl::BufferData(
+      gl::PIXEL_UNPACK_BUFFER,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      gl::STREAM_DRAW
+    );
+  }
+}
+
+pub fn tex_image_cube_face_from_bytes(face: u32, internal_format: u32, size: i32, format: u32, data: &[u8]) {
+  unsafe {
+    gl::TexImage2D(face, 0, internal_format as i32, size, size, 0, format, gl::UNSIGNED_BYTE, data.as_ptr() as *const c_void);
+  }
+}
+
+pub fn tex_image_3d_from_bytes(target: u32, internal_format: u32, width: i32, height: i32, depth: i32, format: u32, data: &[u8]) {
+  unsafe {
+    gl::TexImage3D(target, 0, internal_format as i32, width, height, depth, 0, format, gl::UNSIGNED_BYTE, data.as_ptr() as *const c_void);
+  }
+}
+
+pub fn tex_sub_image_layer_from_bytes(target: u32, layer: i32, width: i32, height: i32, format: u32, data: &[u8]) {
+  unsafe {
+    gl::TexSubImage3D(target, 0, 0, 0, layer, width, height, 1, format, gl::UNSIGNED_BYTE, data.as_ptr() as *const c_void);
+  }
+}
+
+pub fn compressed_tex_image_2d(target: u32, level: i32, internal_format: u32, width: i32, height: i32, data: &[u8]) {
+  unsafe {
+    gl::CompressedTexImage2D(target, level, internal_format, width, height, 0, data.len() as i32, data.as_ptr() as *const c_void);
+  }
+}
+
+pub fn supports_compressed_format(internal_format: u32) -> bool {
+  let mut count: i32 = 0;
+  unsafe {
+    gl::GetIntegerv(gl::NUM_COMPRESSED_TEXTURE_FORMATS, &mut count);
+  }
+  let mut formats = vec![0; count as usize];
+  if count > 0 {
+    unsafe {
+      gl::GetIntegerv(gl::COMPRESSED_TEXTURE_FORMATS, formats.as_mut_ptr());
+    }
+  }
+  if formats.contains(&(internal_format as i32)) {
+    return true;
+  }
+  // Core profiles aren't required to list every format they accept, so fall
+  // back to the version or extension that introduced each family
+  let version = context_version();
+  match internal_format {
+    0x83F0..=0x83F3 | 0x8C4C..=0x8C4F => has_extension("GL_EXT_texture_compression_s3tc"),
+    0x8DBB..=0x8DBE => version >= (3, 0),
+    0x8E8C..=0x8E8F => version >= (4, 2) || has_extension("GL_ARB_texture_compression_bptc"),
+    0x9270..=0x9279 => version >= (4, 3) || has_extension("GL_ARB_ES3_compatibility"),
+    0x93B0..=0x93BD | 0x93D0..=0x93DD => has_extension("GL_KHR_texture_compression_astc_ldr"),
+    _ => false,
+  }
+}
+
+pub fn generate_mipmap(target: u32) {
+  unsafe {
+    gl::GenerateMipmap(target);
+  }
+}
+
+pub fn sampler_target(uniform_type: u32) -> Option<u32> {
+  match uniform_type {
+    gl::SAMPLER_2D | gl::INT_SAMPLER_2D | gl::UNSIGNED_INT_SAMPLER_2D | gl::SAMPLER_2D_SHADOW => Some(gl::TEXTURE_2D),
+    gl::SAMPLER_CUBE | gl::INT_SAMPLER_CUBE | gl::UNSIGNED_INT_SAMPLER_CUBE | gl::SAMPLER_CUBE_SHADOW => Some(gl::TEXTURE_CUBE_MAP),
+    gl::SAMPLER_3D | gl::INT_SAMPLER_3D | gl::UNSIGNED_INT_SAMPLER_3D => Some(gl::TEXTURE_3D),
+    gl::SAMPLER_2D_ARRAY | gl::INT_SAMPLER_2D_ARRAY | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY | gl::SAMPLER_2D_ARRAY_SHADOW => Some(gl::TEXTURE_2D_ARRAY),
+    _ => None,
+  }
+}
+
+pub fn generate_mipmap_2d() {
+  unsafe {
+    gl::GenerateMipmap(gl::TEXTURE_2D);
+  }
+}
+
+pub fn max_texture_anisotropy() -> Option<f32> {
+  if context_version() < (4, 6) && !has_extension("GL_EXT_texture_filter_anisotropic") && !has_extension("GL_ARB_texture_filter_anisotropic") {
+    return None;
+  }
+  let mut max: f32 = 1.0;
   unsafe {
-    gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as i32, width, height, 0, format, gl::UNSIGNED_BYTE, &data[0] as *const u8 as *const c_void);
+    gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
   }
+  Some(max)
+}
+
+pub fn supports_sampler_objects() -> bool {
+  context_version() >= (3, 3) || has_extension("GL_ARB_sampler_objects")
 }
 
+pub fn create_sampler() -> u32 {
+  unsafe {
+    let mut s = 0;
+    gl::GenSamplers(1, &mut s);
+    s
+  }
+}
+
+pub fn bind_sampler(unit: u32, sampler: u32) {
+  unsafe {
+    gl::BindSampler(unit, sampler);
+  }
+}
+
+pub fn delete_sampler(sampler: u32) {
+  unsafe {
+    gl::DeleteSamplers(1, &sampler);
+  }
+}
+
+pub fn sampler_parameter(sampler: u32, param: u32, value: u32) {
+  unsafe {
+    gl::SamplerParameteri(sampler, param, value as i32);
+  }
+}
+
+pub fn sampler_parameter_f(sampler: u32, param: u32, value: f32) {
+  unsafe {
+    gl::SamplerParameterf(sampler, param, value);
+  }
+}
+
+pub fn sampler_parameter_fv(sampler: u32, param: u32, values: &[f32; 4]) {
+  unsafe {
+    gl::SamplerParameterfv(sampler, param, values.as_ptr());
+  }
+}
+
+pub use gl::VERTEX_SHADER;
+pub use gl::FRAGMENT_SHADER;
+pub use gl::GEOMETRY_SHADER;
+pub use gl::TESS_CONTROL_SHADER;
+pub use gl::TESS_EVALUATION_SHADER;
+pub use gl::COMPUTE_SHADER;
+
+pub use gl::READ_ONLY;
+pub use gl::WRITE_ONLY;
+pub use gl::READ_WRITE;
+
+pub use gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT;
+pub use gl::TEXTURE_FETCH_BARRIER_BIT;
+pub use gl::SHADER_IMAGE_ACCESS_BARRIER_BIT;
+pub use gl::BUFFER_UPDATE_BARRIER_BIT;
+pub use gl::SHADER_STORAGE_BARRIER_BIT;
+pub use gl::ALL_BARRIER_BITS;
+
+pub use gl::COLOR_BUFFER_BIT;
+pub use gl::DEPTH_BUFFER_BIT;
+pub use gl::STENCIL_BUFFER_BIT;
+
+pub use gl::COLOR_ATTACHMENT0;
+pub use gl::DEPTH_ATTACHMENT;
+pub use gl::DEPTH_STENCIL_ATTACHMENT;
+
+pub use gl::ZERO;
+pub use gl::ONE;
+pub use gl::SRC_ALPHA;
+pub use gl::ONE_MINUS_SRC_ALPHA;
+pub use gl::FRONT;
+pub use gl::BACK;
+
+pub use gl::STATIC_DRAW;
+pub use gl::DYNAMIC_DRAW;
+pub use gl::STREAM_DRAW;
+
+pub use gl::TEXTURE_2D;
+pub use gl::TEXTURE_3D;
+pub use gl::TEXTURE_2D_ARRAY;
+pub use gl::TEXTURE_CUBE_MAP;
+pub use gl::TEXTURE_CUBE_MAP_POSITIVE_X;
+pub use gl::TEXTURE_CUBE_MAP_NEGATIVE_X;
+pub use gl::TEXTURE_CUBE_MAP_POSITIVE_Y;
+pub use gl::TEXTURE_CUBE_MAP_NEGATIVE_Y;
+pub use gl::TEXTURE_CUBE_MAP_POSITIVE_Z;
+pub use gl::TEXTURE_CUBE_MAP_NEGATIVE_Z;
+
 pub use gl::TEXTURE_MIN_FILTER;
 pub use gl::TEXTURE_MAG_FILTER;
 pub use gl::TEXTURE_WRAP_S;
 pub use gl::TEXTURE_WRAP_T;
+pub use gl::TEXTURE_WRAP_R;
+pub use gl::TEXTURE_MIN_LOD;
+pub use gl::TEXTURE_MAX_LOD;
+pub use gl::TEXTURE_LOD_BIAS;
+pub use gl::TEXTURE_BORDER_COLOR;
+pub const TEXTURE_MAX_ANISOTROPY: u32 = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: u32 = 0x84FF;
 
 pub use gl::NEAREST;
 pub use gl::LINEAR;
+pub use gl::NEAREST_MIPMAP_NEAREST;
+pub use gl::LINEAR_MIPMAP_NEAREST;
+pub use gl::NEAREST_MIPMAP_LINEAR;
+pub use gl::LINEAR_MIPMAP_LINEAR;
 pub use gl::CLAMP_TO_EDGE;
+pub use gl::CLAMP_TO_BORDER;
+pub use gl::MIRRORED_REPEAT;
 pub use gl::REPEAT;
 
+pub use gl::BYTE;
+pub use gl::UNSIGNED_BYTE;
+pub use gl::SHORT;
+pub use gl::UNSIGNED_SHORT;
+pub use gl::INT;
+pub use gl::UNSIGNED_INT;
+pub use gl::HALF_FLOAT;
+pub use gl::FLOAT;
+pub use gl::UNSIGNED_INT_24_8;
+pub use gl::FLOAT_32_UNSIGNED_INT_24_8_REV;
+
+pub use gl::R8;
+pub use gl::RG8;
+pub use gl::RGB8;
+pub use gl::RGBA8;
+pub use gl::R16F;
+pub use gl::RG16F;
+pub use gl::RGB16F;
+pub use gl::RGBA16F;
+pub use gl::R32F;
+pub use gl::RG32F;
+pub use gl::RGB32F;
+pub use gl::RGBA32F;
 pub use gl::R8UI;
+pub use gl::RG8UI;
+pub use gl::RGB8UI;
+pub use gl::RGBA8UI;
+pub use gl::R16UI;
+pub use gl::RG16UI;
+pub use gl::RGB16UI;
+pub use gl::RGBA16UI;
+pub use gl::R32UI;
+pub use gl::RG32UI;
+pub use gl::RGB32UI;
+pub use gl::RGBA32UI;
+pub use gl::R8I;
+pub use gl::RG8I;
+pub use gl::RGB8I;
+pub use gl::RGBA8I;
+pub use gl::R16I;
+pub use gl::RG16I;
+pub use gl::RGB16I;
+pub use gl::RGBA16I;
+pub use gl::R32I;
+pub use gl::RG32I;
+pub use gl::RGB32I;
+pub use gl::RGBA32I;
+pub use gl::SRGB8;
+pub use gl::SRGB8_ALPHA8;
+pub use gl::DEPTH_COMPONENT16;
+pub use gl::DEPTH_COMPONENT24;
+pub use gl::DEPTH_COMPONENT32F;
+pub use gl::DEPTH24_STENCIL8;
+pub use gl::DEPTH32F_STENCIL8;
+
+pub use gl::RED;
+pub use gl::RG;
 pub use gl::RGB;
 pub use gl::RGBA;
 pub use gl::RED_INTEGER;
+pub use gl::RG_INTEGER;
+pub use gl::RGB_INTEGER;
+pub use gl::RGBA_INTEGER;
+pub use gl::BGR;
+pub use gl::BGRA;
+pub use gl::BGR_INTEGER;
+pub use gl::BGRA_INTEGER;
+pub use gl::DEPTH_COMPONENT;
+pub use gl::DEPTH_STENCIL;
+pub use gl::STENCIL_INDEX;
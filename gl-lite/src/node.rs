@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use super::geometry::Geometry;
 use super::gli;
+use super::material::Material;
 use super::program::{Program};
+use super::render_state::RenderState;
 use super::uniforms::{UniformValue, set_value_for_uniform};
 
 type LocalUniformMap = HashMap<String, UniformValue>;
@@ -10,47 +12,152 @@ type LocalUniformMap = HashMap<String, UniformValue>;
 pub struct Node {
   geometry: Geometry,
   program: Rc<Program>,
+  material: Option<Rc<Material>>,
+  // Overrides the material's values when there is one
   uniforms: LocalUniformMap,
 }
 
 impl Node {
   pub fn for_program(program: Rc<Program>) -> Node {
+    Node::with_geometry(program, Geometry::new())
+  }
+
+  pub fn with_geometry(program: Rc<Program>, geometry: Geometry) -> Node {
     Node {
-      geometry: Geometry::new(),
-      program: program,
+      geometry,
+      program,
+      material: None,
       uniforms: HashMap::new(),
     }
   }
 
+  pub fn with_material(material: Rc<Material>, geometry: Geometry) -> Node {
+    let mut node = Node::with_geometry(material.program().clone(), geometry);
+    node.material = Some(material);
+    node
+  }
+
+  pub fn program(&self) -> &Rc<Program> {
+    &self.program
+  }
+
+  pub fn material(&self) -> Option<&Rc<Material>> {
+    self.material.as_ref()
+  }
+
+  // The material has to use the node's program, since the geometry's
+  // attribute locations come from it
+  pub fn set_material(&mut self, material: Rc<Material>) {
+    if !Rc::ptr_eq(material.program(), &self.program) {
+      panic!("Cannot set a material whose program differs from the node's");
+    }
+    self.material = Some(material);
+  }
+
+  pub fn clear_material(&mut self) -> Option<Rc<Material>> {
+    self.material.take()
+  }
+
+  // The material's render state, or the default one without a material
+  pub fn render_state(&self) -> RenderState {
+    self.material.as_ref().map(|m| m.render_state()).unwrap_or_default()
+  }
+
   pub fn add_attribute(&mut self, name: String) {
     if let Some(attr) = self.program.get_attribute(&name) {
       self.geometry.add_attribute(attr);
     }
   }
 
+  pub fn add_stream_attribute(&mut self, stream: usize, name: String) {
+    if let Some(attr) = self.program.get_attribute(&name) {
+      self.geometry.add_stream_attribute(stream, attr);
+    }
+  }
+
   pub fn buffer_data(&mut self, data: &[f32]) {
     self.geometry.buffer_data(data);
   }
 
+  pub fn geometry(&self) -> &Geometry {
+    &self.geometry
+  }
+
+  pub fn geometry_mut(&mut self) -> &mut Geometry {
+    &mut self.geometry
+  }
+
   pub fn set_uniform(&mut self, name: String, value: UniformValue) {
     self.uniforms.insert(name, value);
   }
 
+  pub fn remove_uniform(&mut self, name: &str) -> Option<UniformValue> {
+    self.uniforms.remove(name)
+  }
+
+  // The node's own value, not the material's
+  pub fn uniform(&self, name: &str) -> Option<&UniformValue> {
+    self.uniforms.get(name)
+  }
+
+  // GL names of the textures bound by this node's uniforms and those of its
+  // material it does not override, sorted
+  pub fn textures(&self) -> Vec<u32> {
+    let mut textures: Vec<u32> = self.uniforms.values().filter_map(|u| u.texture_binding()).map(|(_, t, _)| t).collect();
+    if let Some(material) = &self.material {
+      material.with_uniforms(|uniforms| {
+        for (name, value) in uniforms.iter() {
+          if !self.uniforms.contains_key(name) {
+            if let Some((_, t, _)) = value.texture_binding() {
+              textures.push(t);
+            }
+          }
+        }
+      });
+    }
+    textures.sort_unstable();
+    textures
+  }
+
   pub fn draw(&mut self) {
-    let mut tex_slot = 0;
-    for (name, uniform) in self.program.uniforms.iter() {
-      if let Some(local) = self.uniforms.get(name) {
-        if let UniformValue::Texture2D(t) = local {
-          gli::active_texture(tex_slot);
-          gli::bind_texture_2d(*t);
-          gli::uniform_1i(uniform.location, tex_slot as i32);
-          tex_slot += 1;
-        } else {
-          set_value_for_uniform(uniform.location, &local);
+    // Material values the program already holds are not uploaded again
+    if let Some(material) = &self.material {
+      material.bind_values(&self.program);
+    }
+    let empty = HashMap::new();
+    let draw_uniforms = |shared: &LocalUniformMap| {
+      let mut tex_slot = 0;
+      for (name, uniform) in self.program.uniforms.iter() {
+        let local = self.uniforms.get(name);
+        if let Some(value) = local.or_else(|| shared.get(name)) {
+          if let Some((target, texture, sampler)) = value.texture_binding() {
+            if gli::sampler_target(uniform.gl_type) != Some(target) {
+              panic!("Uniform {} cannot be bound to a texture of target {:#x}", name, target);
+            }
+            gli::active_texture(tex_slot);
+            gli::bind_texture(target, texture);
+            // Sampler 0 only needs binding to replace one a previous draw left
+            if sampler != 0 || self.program.supports_sampler_objects() {
+              gli::bind_sampler(tex_slot, sampler);
+            }
+            gli::uniform_1i(uniform.location, tex_slot as i32);
+            tex_slot += 1;
+          } else if local.is_some() {
+            set_value_for_uniform(uniform.location, value);
+            self.program.mark_stale(name);
+          }
         }
       }
+    };
+    match &self.material {
+      Some(material) => material.with_uniforms(draw_uniforms),
+      None => draw_uniforms(&empty),
     }
 
-    self.geometry.draw();
+    if self.program.uses_tessellation() {
+      self.geometry.draw_patches(self.program.patch_vertices());
+    } else {
+      self.geometry.draw();
+    }
   }
 }
\ No newline at end of file
@@ -0,0 +1,698 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+#[cfg(feature = "image")]
+use super::image_loader::{ImageError, ImageOptions};
+use super::material::Material;
+use super::math::Vec3;
+use super::mesh::{AttributeNames, MeshData};
+use super::node::Node;
+use super::program::Program;
+use super::render_state::RenderState;
+use super::texture::Texture;
+use super::uniforms::UniformValue;
+
+#[derive(Debug)]
+pub enum ObjError {
+  Io(PathBuf, io::Error),
+  Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ObjError::Io(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+      ObjError::Parse { line, message } => write!(f, "Invalid data on line {}: {}", line, message),
+    }
+  }
+}
+
+impl Error for ObjError {}
+
+fn parse_error<T>(line: usize, message: String) -> Result<T, ObjError> {
+  Err(ObjError::Parse { line, message })
+}
+
+fn read_file(path: &Path) -> Result<String, ObjError> {
+  fs::read_to_string(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))
+}
+
+// Yields (line number, statement) pairs with comments stripped and lines
+// ending in a backslash joined to the next
+fn statements(source: &str) -> Vec<(usize, String)> {
+  let mut result = Vec::new();
+  let mut pending = String::new();
+  let mut start = 0;
+  for (i, raw) in source.lines().enumerate() {
+    let line = match raw.find('#') {
+      Some(comment) => &raw[..comment],
+      None => raw,
+    };
+    if pending.is_empty() {
+      start = i + 1;
+    }
+    let line = line.trim_end();
+    if let Some(continued) = line.strip_suffix('\\') {
+      pending.push_str(continued);
+      pending.push(' ');
+      continue;
+    }
+    pending.push_str(line);
+    let statement = pending.trim();
+    if !statement.is_empty() {
+      result.push((start, String::from(statement)));
+    }
+    pending.clear();
+  }
+  result
+}
+
+fn parse_floats<const N: usize>(line: usize, args: &[&str], min: usize, defaults: [f32; N]) -> Result<[f32; N], ObjError> {
+  if args.len() < min || args.len() > N {
+    let expected = if min == N { format!("{}", N) } else { format!("{} to {}", min, N) };
+    return parse_error(line, format!("expected {} numbers, found {}", expected, args.len()));
+  }
+  let mut values = defaults;
+  for (value, arg) in values.iter_mut().zip(args.iter()) {
+    *value = match arg.parse() {
+      Ok(v) => v,
+      Err(_) => return parse_error(line, format!("{} is not a number", arg)),
+    };
+  }
+  Ok(values)
+}
+
+// A single value applies to every channel
+fn parse_color(line: usize, args: &[&str]) -> Result<[f32; 3], ObjError> {
+  // Spectral and CIE XYZ colors are not supported
+  if let Some(&kind @ ("spectral" | "xyz")) = args.first() {
+    return parse_error(line, format!("{} colors are not supported", kind));
+  }
+  if args.len() == 1 {
+    let v = parse_floats(line, args, 1, [0.0])?[0];
+    return Ok([v; 3]);
+  }
+  parse_floats(line, args, 3, [0.0; 3])
+}
+
+// Surface description from an MTL file. Map paths are relative to the MTL
+// file's directory once loaded through ObjModel::load or load_mtl.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MtlMaterial {
+  pub name: String,
+  pub ambient: [f32; 3],
+  pub diffuse: [f32; 3],
+  pub specular: [f32; 3],
+  pub emissive: [f32; 3],
+  pub shininess: f32,
+  pub opacity: f32,
+  pub optical_density: f32,
+  pub illumination: u32,
+  pub ambient_map: Option<PathBuf>,
+  pub diffuse_map: Option<PathBuf>,
+  pub specular_map: Option<PathBuf>,
+  pub emissive_map: Option<PathBuf>,
+  pub normal_map: Option<PathBuf>,
+  pub alpha_map: Option<PathBuf>,
+}
+
+impl MtlMaterial {
+  pub fn new(name: &str) -> MtlMaterial {
+    MtlMaterial {
+      name: String::from(name),
+      ambient: [0.0; 3],
+      diffuse: [0.8; 3],
+      specular: [0.0; 3],
+      emissive: [0.0; 3],
+      shininess: 0.0,
+      opacity: 1.0,
+      optical_density: 1.0,
+      illumination: 2,
+      ambient_map: None,
+      diffuse_map: None,
+      specular_map: None,
+      emissive_map: None,
+      normal_map: None,
+      alpha_map: None,
+    }
+  }
+
+  pub fn is_transparent(&self) -> bool {
+    self.opacity < 1.0 || self.alpha_map.is_some()
+  }
+
+  // Values set as u_ambient, u_diffuse, u_specular, u_emissive,
+  // u_shininess and u_opacity
+  pub fn uniforms(&self) -> Vec<(&'static str, UniformValue)> {
+    let color = |c: [f32; 3]| UniformValue::FloatVec3(c[0], c[1], c[2]);
+    vec![
+      ("u_ambient", color(self.ambient)),
+      ("u_diffuse", color(self.diffuse)),
+      ("u_specular", color(self.specular)),
+      ("u_emissive", color(self.emissive)),
+      ("u_shininess", UniformValue::Float(self.shininess)),
+      ("u_opacity", UniformValue::Float(self.opacity)),
+    ]
+  }
+
+  // The maps this material uses, by the sampler uniform they are bound to
+  pub fn texture_maps(&self) -> Vec<(&'static str, &Path)> {
+    let maps = [
+      ("u_ambient_map", &self.ambient_map),
+      ("u_diffuse_map", &self.diffuse_map),
+      ("u_specular_map", &self.specular_map),
+      ("u_emissive_map", &self.emissive_map),
+      ("u_normal_map", &self.normal_map),
+      ("u_alpha_map", &self.alpha_map),
+    ];
+    maps.iter().filter_map(|(name, path)| path.as_ref().map(|p| (*name, p.as_path()))).collect()
+  }
+
+  // Builds a Material from the uniforms and maps above. Maps missing from
+  // `textures` are left unbound. Transparent materials get
+  // RenderState::transparent.
+  pub fn to_material(&self, program: Rc<Program>, textures: &HashMap<PathBuf, Texture>) -> Material {
+    let material = Material::new(program);
+    for (name, value) in self.uniforms() {
+      material.set_uniform(String::from(name), value);
+    }
+    for (name, path) in self.texture_maps() {
+      if let Some(texture) = textures.get(path) {
+        material.set_uniform(String::from(name), texture.as_uniform_value());
+      }
+    }
+    if self.is_transparent() {
+      material.set_render_state(RenderState::transparent());
+    }
+    material
+  }
+}
+
+// Options such as `-bm 0.5` may come before a map's file name, which can
+// contain spaces
+fn map_path(line: usize, args: &[&str]) -> Result<PathBuf, ObjError> {
+  let mut i = 0;
+  while i < args.len() && args[i].starts_with('-') {
+    let max_values = match args[i] {
+      "-o" | "-s" | "-t" => 3,
+      "-mm" => 2,
+      _ => 1,
+    };
+    i += 1;
+    let mut taken = 0;
+    while taken < max_values && i < args.len() {
+      // Numeric options take up to max_values numbers, the rest one word
+      if max_values > 1 && taken > 0 && args[i].parse::<f32>().is_err() {
+        break;
+      }
+      i += 1;
+      taken += 1;
+    }
+  }
+  if i >= args.len() {
+    return parse_error(line, String::from("texture map has no file name"));
+  }
+  Ok(PathBuf::from(args[i..].join(" ")))
+}
+
+// Parses MTL source, leaving map paths as written
+pub fn parse_mtl(source: &str) -> Result<Vec<MtlMaterial>, ObjError> {
+  let mut materials: Vec<MtlMaterial> = Vec::new();
+  for (line, statement) in statements(source) {
+    let tokens: Vec<&str> = statement.split_whitespace().collect();
+    let (keyword, args) = (tokens[0], &tokens[1..]);
+    if keyword == "newmtl" {
+      if args.is_empty() {
+        return parse_error(line, String::from("newmtl needs a name"));
+      }
+      materials.push(MtlMaterial::new(&args.join(" ")));
+      continue;
+    }
+    let material = match materials.last_mut() {
+      Some(material) => material,
+      None => return parse_error(line, format!("{} before any newmtl", keyword)),
+    };
+    match keyword {
+      "Ka" => material.ambient = parse_color(line, args)?,
+      "Kd" => material.diffuse = parse_color(line, args)?,
+      "Ks" => material.specular = parse_color(line, args)?,
+      "Ke" => material.emissive = parse_color(line, args)?,
+      "Ns" => material.shininess = parse_floats(line, args, 1, [0.0])?[0],
+      "d" => material.opacity = parse_floats(line, args, 1, [0.0])?[0],
+      "Tr" => material.opacity = 1.0 - parse_floats(line, args, 1, [0.0])?[0],
+      "Ni" => material.optical_density = parse_floats(line, args, 1, [0.0])?[0],
+      "illum" => material.illumination = parse_floats(line, args, 1, [0.0])?[0] as u32,
+      "map_Ka" => material.ambient_map = Some(map_path(line, args)?),
+      "map_Kd" => material.diffuse_map = Some(map_path(line, args)?),
+      "map_Ks" => material.specular_map = Some(map_path(line, args)?),
+      "map_Ke" => material.emissive_map = Some(map_path(line, args)?),
+      "map_Bump" | "map_bump" | "bump" | "norm" => material.normal_map = Some(map_path(line, args)?),
+      "map_d" => material.alpha_map = Some(map_path(line, args)?),
+      // Filter colors, reflection maps and the like are not used
+      _ => (),
+    }
+  }
+  Ok(materials)
+}
+
+// Reads an MTL file, resolving map paths against its directory
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<Vec<MtlMaterial>, ObjError> {
+  let path = path.as_ref();
+  let mut materials = parse_mtl(&read_file(path)?)?;
+  let dir = path.parent().unwrap_or_else(|| Path::new(""));
+  for material in materials.iter_mut() {
+    for map in [
+      &mut material.ambient_map,
+      &mut material.diffuse_map,
+      &mut material.specular_map,
+      &mut material.emissive_map,
+      &mut material.normal_map,
+      &mut material.alpha_map,
+    ].iter_mut() {
+      if let Some(map) = map.as_mut() {
+        *map = dir.join(&map);
+      }
+    }
+  }
+  Ok(materials)
+}
+
+// The faces of one object, group and material. `object` and `group` are
+// empty when the file doesn't name them.
+#[derive(Clone, Debug)]
+pub struct ObjMesh {
+  pub object: String,
+  pub group: String,
+  pub material: Option<String>,
+  pub data: MeshData,
+}
+
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+struct MeshBuilder {
+  mesh: ObjMesh,
+  vertices: HashMap<VertexKey, u32>,
+  has_normal: Vec<bool>,
+  any_uvs: bool,
+}
+
+impl MeshBuilder {
+  fn new(object: &str, group: &str, material: &Option<String>) -> MeshBuilder {
+    MeshBuilder {
+      mesh: ObjMesh {
+        object: String::from(object),
+        group: String::from(group),
+        material: material.clone(),
+        data: MeshData::new(),
+      },
+      vertices: HashMap::new(),
+      has_normal: Vec::new(),
+      any_uvs: false,
+    }
+  }
+
+  // Returns the index of the vertex, adding it the first time it is seen
+  fn vertex(&mut self, key: VertexKey, positions: &[[f32; 3]], uvs: &[[f32; 2]], normals: &[[f32; 3]]) -> u32 {
+    if let Some(index) = self.vertices.get(&key) {
+      return *index;
+    }
+    let data = &mut self.mesh.data;
+    let index = data.positions.len() as u32;
+    data.positions.push(positions[key.0]);
+    data.uvs.push(key.1.map_or([0.0; 2], |i| uvs[i]));
+    data.normals.push(key.2.map_or([0.0; 3], |i| normals[i]));
+    self.any_uvs |= key.1.is_some();
+    self.has_normal.push(key.2.is_some());
+    self.vertices.insert(key, index);
+    index
+  }
+
+  // Fills in normals the file left out with smooth ones and drops UVs when
+  // no vertex had any
+  fn finish(mut self) -> ObjMesh {
+    let data = &mut self.mesh.data;
+    if !self.any_uvs {
+      data.uvs.clear();
+    }
+    if self.has_normal.iter().any(|has| !has) {
+      let given = data.normals.clone();
+      data.compute_normals();
+      for (i, has) in self.has_normal.iter().enumerate() {
+        if *has {
+          data.normals[i] = given[i];
+        }
+      }
+    }
+    self.mesh
+  }
+}
+
+// Resolves a 1-based or negative (relative to the end) OBJ index
+fn resolve_index(line: usize, token: &str, count: usize, kind: &str) -> Result<usize, ObjError> {
+  let index: i64 = match token.parse() {
+    Ok(index) => index,
+    Err(_) => return parse_error(line, format!("{} is not a valid {} index", token, kind)),
+  };
+  let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+  if index == 0 || resolved < 0 || resolved >= count as i64 {
+    return parse_error(line, format!("{} index {} is out of range", kind, index));
+  }
+  Ok(resolved as usize)
+}
+
+fn face_vertex(line: usize, token: &str, counts: (usize, usize, usize)) -> Result<VertexKey, ObjError> {
+  let mut parts = token.split('/');
+  let position = resolve_index(line, parts.next().unwrap_or(""), counts.0, "position")?;
+  let uv = match parts.next() {
+    Some("") | None => None,
+    Some(t) => Some(resolve_index(line, t, counts.1, "texture coordinate")?),
+  };
+  let normal = match parts.next() {
+    Some("") | None => None,
+    Some(n) => Some(resolve_index(line, n, counts.2, "normal")?),
+  };
+  Ok((position, uv, normal))
+}
+
+// Splits a polygon into triangles by ear clipping in the plane it mostly
+// lies in, keeping its winding. Falls back to a fan for degenerate input.
+pub fn triangulate(points: &[Vec3]) -> Vec<[usize; 3]> {
+  let n = points.len();
+  if n < 3 {
+    return Vec::new();
+  }
+  let fan = |remaining: &[usize]| -> Vec<[usize; 3]> {
+    (1..remaining.len() - 1).map(|i| [remaining[0], remaining[i], remaining[i + 1]]).collect()
+  };
+  // Newell's method gives a normal even for concave polygons
+  let mut normal = Vec3::ZERO;
+  for i in 0..n {
+    let (a, b) = (points[i], points[(i + 1) % n]);
+    normal.x += (a.y - b.y) * (a.z + b.z);
+    normal.y += (a.z - b.z) * (a.x + b.x);
+    normal.z += (a.x - b.x) * (a.y + b.y);
+  }
+  let all: Vec<usize> = (0..n).collect();
+  if n == 3 || normal.length() == 0.0 {
+    return fan(&all);
+  }
+  // Project onto the axis plane facing the normal most, flipping one axis
+  // when the normal points down it so the polygon stays counter-clockwise
+  let (ax, ay) = (normal.x.abs(), normal.y.abs());
+  let project = |p: Vec3| -> (f32, f32) {
+    if ax >= ay && ax >= normal.z.abs() {
+      (p.y, if normal.x > 0.0 { p.z } else { -p.z })
+    } else if ay >= normal.z.abs() {
+      (p.z, if normal.y > 0.0 { p.x } else { -p.x })
+    } else {
+      (p.x, if normal.z > 0.0 { p.y } else { -p.y })
+    }
+  };
+  let flat: Vec<(f32, f32)> = points.iter().map(|p| project(*p)).collect();
+  let cross = |o: (f32, f32), a: (f32, f32), b: (f32, f32)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+
+  let mut remaining = all;
+  let mut triangles = Vec::with_capacity(n - 2);
+  let mut i = 0;
+  let mut since_ear = 0;
+  while remaining.len() > 3 {
+    let count = remaining.len();
+    if since_ear >= count {
+      triangles.extend(fan(&remaining));
+      return triangles;
+    }
+    let (prev, cur, next) = (remaining[(i + count - 1) % count], remaining[i % count], remaining[(i + 1) % count]);
+    let (a, b, c) = (flat[prev], flat[cur], flat[next]);
+    let convex = cross(a, b, c) > 0.0;
+    let contains_other = convex && remaining.iter().any(|&j| {
+      if j == prev || j == cur || j == next {
+        return false;
+      }
+      let p = flat[j];
+      cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+    });
+    if convex && !contains_other {
+      triangles.push([prev, cur, next]);
+      remaining.remove(i % count);
+      since_ear = 0;
+    } else {
+      i += 1;
+      since_ear += 1;
+    }
+    i %= remaining.len();
+  }
+  triangles.push([remaining[0], remaining[1], remaining[2]]);
+  triangles
+}
+
+// A parsed OBJ file. Faces are split into one mesh per run of object,
+// group and material, each with its own deduplicated, indexed vertices.
+#[derive(Clone, Debug)]
+pub struct ObjModel {
+  pub meshes: Vec<ObjMesh>,
+  // Library file names as given by mtllib statements
+  pub material_libraries: Vec<String>,
+  // Empty unless loaded through ObjModel::load
+  pub materials: HashMap<String, MtlMaterial>,
+}
+
+impl ObjModel {
+  // Parses OBJ source without loading any material libraries
+  pub fn parse(source: &str) -> Result<ObjModel, ObjError> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut libraries = Vec::new();
+    let mut meshes = Vec::new();
+    let (mut object, mut group, mut material) = (String::new(), String::new(), None);
+    let mut current = MeshBuilder::new(&object, &group, &material);
+
+    for (line, statement) in statements(source) {
+      let tokens: Vec<&str> = statement.split_whitespace().collect();
+      let (keyword, args) = (tokens[0], &tokens[1..]);
+      match keyword {
+        "v" => {
+          // The optional w, or vertex colors, after the position are ignored
+          let args = if args.len() > 3 { &args[..3] } else { args };
+          positions.push(parse_floats(line, args, 3, [0.0; 3])?);
+        },
+        "vt" => {
+          let t = parse_floats(line, args, 1, [0.0; 3])?;
+          uvs.push([t[0], t[1]]);
+        },
+        "vn" => normals.push(parse_floats(line, args, 3, [0.0; 3])?),
+        "f" => {
+          if args.len() < 3 {
+            return parse_error(line, String::from("faces need at least three vertices"));
+          }
+          let counts = (positions.len(), uvs.len(), normals.len());
+          let keys = args.iter().map(|t| face_vertex(line, t, counts)).collect::<Result<Vec<_>, _>>()?;
+          let corners: Vec<Vec3> = keys.iter().map(|k| {
+            let p = positions[k.0];
+            Vec3::new(p[0], p[1], p[2])
+          }).collect();
+          let indices: Vec<u32> = keys.iter().map(|k| current.vertex(*k, &positions, &uvs, &normals)).collect();
+          for triangle in triangulate(&corners) {
+            current.mesh.data.indices.extend(triangle.iter().map(|i| indices[*i]));
+          }
+        },
+        "o" | "g" | "usemtl" => {
+          let name = args.join(" ");
+          match keyword {
+            "o" => {
+              object = name;
+              group = String::new();
+            },
+            "g" => group = name,
+            _ => material = Some(name),
+          }
+          if current.mesh.data.is_empty() {
+            current.mesh.object = object.clone();
+            current.mesh.group = group.clone();
+            current.mesh.material = material.clone();
+          } else {
+            let finished = std::mem::replace(&mut current, MeshBuilder::new(&object, &group, &material));
+            meshes.push(finished.finish());
+          }
+        },
+        "mtllib" => libraries.push(args.join(" ")),
+        // Smoothing groups, lines, points and curves are not used
+        _ => (),
+      }
+    }
+    if !current.mesh.data.is_empty() {
+      meshes.push(current.finish());
+    }
+    Ok(ObjModel {
+      meshes,
+      material_libraries: libraries,
+      materials: HashMap::new(),
+    })
+  }
+
+  // Reads an OBJ file and the material libraries it names, which are looked
+  // up next to it
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<ObjModel, ObjError> {
+    let path = path.as_ref();
+    let mut model = ObjModel::parse(&read_file(path)?)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for library in model.material_libraries.iter() {
+      for material in load_mtl(dir.join(library))? {
+        model.materials.insert(material.name.clone(), material);
+      }
+    }
+    Ok(model)
+  }
+
+  // Every map path used by the materials, without duplicates
+  pub fn texture_paths(&self) -> Vec<&Path> {
+    let mut paths: Vec<&Path> = self.materials.values().flat_map(|m| m.texture_maps()).map(|(_, p)| p).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+  }
+
+  // Loads every map once, keyed by path for to_material
+  #[cfg(feature = "image")]
+  pub fn load_textures(&self, options: &ImageOptions) -> Result<HashMap<PathBuf, Texture>, ImageError> {
+    let mut textures = HashMap::new();
+    for path in self.texture_paths() {
+      textures.insert(path.to_path_buf(), Texture::from_file(path, options)?);
+    }
+    Ok(textures)
+  }
+
+  // One shared Material per MTL material, all using `program`
+  pub fn create_materials(&self, program: &Rc<Program>, textures: &HashMap<PathBuf, Texture>) -> HashMap<String, Rc<Material>> {
+    self.materials.iter().map(|(name, m)| (name.clone(), Rc::new(m.to_material(program.clone(), textures)))).collect()
+  }
+
+  // A node per mesh, using the material of the same name or `fallback`
+  pub fn create_nodes(&self, materials: &HashMap<String, Rc<Material>>, fallback: &Rc<Material>, names: &AttributeNames) -> Vec<Node> {
+    self.meshes.iter().map(|mesh| {
+      let material = mesh.material.as_ref().and_then(|m| materials.get(m)).unwrap_or(fallback);
+      let geometry = mesh.data.to_geometry(material.program(), names);
+      Node::with_material(material.clone(), geometry)
+    }).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn signed_area(points: &[Vec3], triangle: [usize; 3]) -> f32 {
+    let [a, b, c] = triangle.map(|i| points[i]);
+    ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)) / 2.0
+  }
+
+  #[test]
+  fn concave_polygons_are_ear_clipped() {
+    // An L shape, counter-clockwise, whose reflex corner is at (1, 1)
+    let points: Vec<Vec3> = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]
+      .iter()
+      .map(|(x, y)| Vec3::new(*x, *y, 0.0))
+      .collect();
+    let triangles = triangulate(&points);
+    assert_eq!(triangles.len(), 4);
+    // Every triangle keeps the winding, and together they cover the L
+    // exactly, so none reaches into the notch
+    assert!(triangles.iter().all(|t| signed_area(&points, *t) > 0.0));
+    let area: f32 = triangles.iter().map(|t| signed_area(&points, *t)).sum();
+    assert!((area - 3.0).abs() < 1e-5);
+  }
+
+  #[test]
+  fn clockwise_polygons_keep_their_winding() {
+    let points: Vec<Vec3> = [(0.0, 2.0), (1.0, 2.0), (1.0, 1.0), (2.0, 1.0), (2.0, 0.0), (0.0, 0.0)]
+      .iter()
+      .map(|(x, y)| Vec3::new(*x, *y, 0.0))
+      .collect();
+    let triangles = triangulate(&points);
+    assert_eq!(triangles.len(), 4);
+    assert!(triangles.iter().all(|t| signed_area(&points, *t) < 0.0));
+    let area: f32 = triangles.iter().map(|t| signed_area(&points, *t)).sum();
+    assert!((area + 3.0).abs() < 1e-5);
+  }
+
+  #[test]
+  fn negative_indices_count_from_the_end() {
+    let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 1 1 0\nf 2 -1 3\n";
+    let model = ObjModel::parse(source).unwrap();
+    let data = &model.meshes[0].data;
+    assert_eq!(data.positions, vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]]);
+    assert_eq!(data.indices, vec![0, 1, 2, 1, 3, 2]);
+  }
+
+  #[test]
+  fn out_of_range_indices_are_errors() {
+    for face in ["f 1 2 4", "f 1 2 -4", "f 0 1 2", "f 1/2 2 3"].iter() {
+      let source = format!("v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\n{}\n", face);
+      match ObjModel::parse(&source) {
+        Err(ObjError::Parse { line: 5, .. }) => (),
+        other => panic!("{} parsed as {:?}", face, other),
+      }
+    }
+  }
+
+  #[test]
+  fn shared_vertices_are_deduplicated() {
+    let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nvn 0 0 -1\n\
+      f 1//1 2//1 3//1\nf 1//1 3//1 4//1\nf 1//2 3//2 2//2\n";
+    let model = ObjModel::parse(source).unwrap();
+    let data = &model.meshes[0].data;
+    // The back face shares positions but not normals, so gets its own vertices
+    assert_eq!(data.positions.len(), 7);
+    assert_eq!(data.indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6]);
+    assert_eq!(data.normals[4], [0.0, 0.0, -1.0]);
+    assert!(data.uvs.is_empty());
+  }
+
+  #[test]
+  fn objects_groups_and_materials_split_meshes() {
+    let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+      o body\ng front\nusemtl red\nf 1 2 3\n\
+      usemtl blue\nf 1 2 3\n\
+      g back\nf 1 2 3\n\
+      o wheel\nf 1 2 3\n";
+    let model = ObjModel::parse(source).unwrap();
+    let names: Vec<(&str, &str, Option<&str>)> = model.meshes.iter()
+      .map(|m| (m.object.as_str(), m.group.as_str(), m.material.as_deref()))
+      .collect();
+    assert_eq!(names, vec![
+      ("body", "front", Some("red")),
+      ("body", "front", Some("blue")),
+      ("body", "back", Some("blue")),
+      ("wheel", "", Some("blue")),
+    ]);
+    // Each mesh has its own vertices
+    assert!(model.meshes.iter().all(|m| m.data.positions.len() == 3 && m.data.indices == vec![0, 1, 2]));
+  }
+
+  #[test]
+  fn map_options_are_skipped() {
+    let source = "newmtl paint\n\
+      map_Kd -o 0.5 0.5 -bm 0.2 -clamp on base color.png\n\
+      map_Bump -bm 1 normal.png\n\
+      map_Ks -s 2 spec.png\n\
+      map_d -mm 0 1 -imfchan m alpha.png\n";
+    let materials = parse_mtl(source).unwrap();
+    let paint = &materials[0];
+    assert_eq!(paint.diffuse_map, Some(PathBuf::from("base color.png")));
+    assert_eq!(paint.normal_map, Some(PathBuf::from("normal.png")));
+    assert_eq!(paint.specular_map, Some(PathBuf::from("spec.png")));
+    assert_eq!(paint.alpha_map, Some(PathBuf::from("alpha.png")));
+  }
+
+  #[test]
+  fn maps_without_a_file_name_are_errors() {
+    match parse_mtl("newmtl paint\nmap_Kd -bm 0.5\n") {
+      Err(ObjError::Parse { line: 2, .. }) => (),
+      other => panic!("Parsed as {:?}", other),
+    }
+  }
+}
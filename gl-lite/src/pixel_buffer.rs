@@ -0,0 +1,48 @@
+use std::mem;
+use super::format::TexelData;
+use super::gli;
+use super::texture::Texture;
+
+// Streams texture updates through a ring of pixel unpack buffers. The copy
+// into each buffer returns as soon as the driver has the data, and the
+// transfer to the texture happens asynchronously, so the buffer written on
+// one frame is not touched again until `count` uploads later.
+pub struct PixelUploadBuffer {
+  buffers: Vec<u32>,
+  current: usize,
+}
+
+impl PixelUploadBuffer {
+  pub fn new(count: usize) -> PixelUploadBuffer {
+    if !gli::supports_pixel_buffers() {
+      panic!("Pixel buffer uploads are not supported by the current context");
+    }
+    if count == 0 {
+      panic!("Cannot create a pixel upload buffer with no buffers");
+    }
+    PixelUploadBuffer {
+      buffers: (0..count).map(|_| gli::create_buffer()).collect(),
+      current: 0,
+    }
+  }
+
+  pub fn update_region<T: TexelData>(&mut self, texture: &Texture, x: i32, y: i32, width: i32, height: i32, data: &[T]) {
+    // Checked before binding, so a bad call can't leave the unpack buffer
+    // bound for later uploads from client memory
+    let pixel_type = texture.check_region_upload::<T>(x, y, width, height, mem::size_of_val(data));
+    let buffer = self.buffers[self.current];
+    self.current = (self.current + 1) % self.buffers.len();
+    gli::bind_pixel_unpack_buffer(buffer);
+    gli::pixel_unpack_buffer_data(data);
+    texture.update_region_from_buffer(x, y, width, height, pixel_type);
+    gli::bind_pixel_unpack_buffer(0);
+  }
+}
+
+impl Drop for PixelUploadBuffer {
+  fn drop(&mut self) {
+    for buffer in self.buffers.iter() {
+      gli::delete_buffer(*buffer);
+    }
+  }
+}
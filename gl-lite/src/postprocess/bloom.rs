@@ -0,0 +1,94 @@
+use super::super::framebuffer::Framebuffer;
+use super::super::texture::Texture;
+use super::super::uniforms::UniformValue;
+use super::{scratch_framebuffer, Effect, FullscreenPass, GaussianBlur, RenderTarget};
+
+const BRIGHT_PASS_SHADER: &str = "#version 330
+in vec2 v_uv;
+out vec4 outColor;
+
+uniform sampler2D u_input;
+uniform float u_threshold;
+
+void main() {
+  vec3 color = texture(u_input, v_uv).rgb;
+  float brightness = max(color.r, max(color.g, color.b));
+  float contribution = max(brightness - u_threshold, 0.0) / max(brightness, 0.0001);
+  outColor = vec4(color * contribution, 1.0);
+}";
+
+const COMPOSITE_SHADER: &str = "#version 330
+in vec2 v_uv;
+out vec4 outColor;
+
+uniform sampler2D u_input;
+uniform sampler2D u_bloom;
+uniform float u_intensity;
+
+void main() {
+  vec4 color = texture(u_input, v_uv);
+  outColor = vec4(color.rgb + texture(u_bloom, v_uv).rgb * u_intensity, color.a);
+}";
+
+// Adds a blurred copy of the parts of the image brighter than a threshold.
+// Works best on HDR input, before tonemapping.
+pub struct Bloom {
+  bright_pass: FullscreenPass,
+  composite: FullscreenPass,
+  blur: GaussianBlur,
+  pub threshold: f32,
+  pub intensity: f32,
+  // Resolution of the blurred image relative to the target
+  pub scale: f32,
+  bright: Option<Framebuffer>,
+  blurred: Option<Framebuffer>,
+}
+
+impl Default for Bloom {
+  fn default() -> Bloom {
+    Bloom::new()
+  }
+}
+
+impl Bloom {
+  pub fn new() -> Bloom {
+    Bloom {
+      bright_pass: FullscreenPass::new(BRIGHT_PASS_SHADER),
+      composite: FullscreenPass::new(COMPOSITE_SHADER),
+      blur: GaussianBlur::new(4.0),
+      threshold: 1.0,
+      intensity: 0.8,
+      scale: 0.5,
+      bright: None,
+      blurred: None,
+    }
+  }
+
+  // Blur spread in texels of the downscaled image
+  pub fn set_blur_sigma(&mut self, sigma: f32) {
+    self.blur.sigma = sigma;
+  }
+}
+
+impl Effect for Bloom {
+  fn apply(&mut self, input: &Texture, target: RenderTarget) {
+    let resolved = target.resolve();
+    let width = (resolved.width as f32 * self.scale) as i32;
+    let height = (resolved.height as f32 * self.scale) as i32;
+
+    let bright = scratch_framebuffer(&mut self.bright, width, height);
+    bright.bind();
+    self.bright_pass.set_input("u_input", input);
+    self.bright_pass.set_uniform("u_threshold", UniformValue::Float(self.threshold));
+    self.bright_pass.draw();
+
+    let blurred = scratch_framebuffer(&mut self.blurred, width, height);
+    self.blur.apply(bright.color_texture(0), RenderTarget::Framebuffer(blurred));
+
+    resolved.bind();
+    self.composite.set_input("u_input", input);
+    self.composite.set_input("u_bloom", blurred.color_texture(0));
+    self.composite.set_uniform("u_intensity", UniformValue::Float(self.intensity));
+    self.composite.draw();
+  }
+}
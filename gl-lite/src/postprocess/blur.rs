@@ -0,0 +1,76 @@
+use super::super::framebuffer::Framebuffer;
+use super::super::texture::Texture;
+use super::super::uniforms::UniformValue;
+use super::{scratch_framebuffer, Effect, FullscreenPass, RenderTarget};
+
+const BLUR_SHADER: &str = "#version 330
+in vec2 v_uv;
+out vec4 outColor;
+
+uniform sampler2D u_input;
+// One texel along the blur axis
+uniform vec2 u_direction;
+uniform float u_sigma;
+uniform float u_radius;
+
+void main() {
+  vec4 sum = texture(u_input, v_uv);
+  float total = 1.0;
+  for (float i = 1.0; i <= u_radius; i += 1.0) {
+    float weight = exp(-(i * i) / (2.0 * u_sigma * u_sigma));
+    vec2 offset = u_direction * i;
+    sum += (texture(u_input, v_uv + offset) + texture(u_input, v_uv - offset)) * weight;
+    total += 2.0 * weight;
+  }
+  outColor = sum / total;
+}";
+
+// Separable gaussian blur: a horizontal pass into a scratch framebuffer,
+// then a vertical pass into the target
+pub struct GaussianBlur {
+  pass: FullscreenPass,
+  // Standard deviation in texels
+  pub sigma: f32,
+  // Taps on each side of the center; defaults to three sigma
+  pub radius: Option<u32>,
+  scratch: Option<Framebuffer>,
+}
+
+impl GaussianBlur {
+  pub fn new(sigma: f32) -> GaussianBlur {
+    GaussianBlur {
+      pass: FullscreenPass::new(BLUR_SHADER),
+      sigma,
+      radius: None,
+      scratch: None,
+    }
+  }
+
+  fn radius(&self) -> f32 {
+    match self.radius {
+      Some(radius) => radius as f32,
+      None => (self.sigma * 3.0).ceil(),
+    }
+  }
+}
+
+impl Effect for GaussianBlur {
+  fn apply(&mut self, input: &Texture, target: RenderTarget) {
+    let resolved = target.resolve();
+    let (width, height) = (resolved.width, resolved.height);
+    let radius = self.radius();
+    self.pass.set_uniform("u_sigma", UniformValue::Float(self.sigma.max(0.01)));
+    self.pass.set_uniform("u_radius", UniformValue::Float(radius));
+
+    let scratch = scratch_framebuffer(&mut self.scratch, width, height);
+    scratch.bind();
+    self.pass.set_input("u_input", input);
+    self.pass.set_uniform("u_direction", UniformValue::FloatVec2(1.0 / input.width() as f32, 0.0));
+    self.pass.draw();
+
+    resolved.bind();
+    self.pass.set_input("u_input", scratch.color_texture(0));
+    self.pass.set_uniform("u_direction", UniformValue::FloatVec2(0.0, 1.0 / height as f32));
+    self.pass.draw();
+  }
+}
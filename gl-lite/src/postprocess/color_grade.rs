@@ -0,0 +1,87 @@
+use std::rc::Rc;
+use super::super::gli;
+use super::super::texture::{FilterMode, Texture, WrapMode};
+use super::super::texture_3d::Texture3D;
+use super::super::uniforms::UniformValue;
+use super::{Effect, FullscreenPass, RenderTarget};
+
+const COLOR_GRADE_SHADER: &str = "#version 330
+in vec2 v_uv;
+out vec4 outColor;
+
+uniform sampler2D u_input;
+uniform sampler3D u_lut;
+uniform float u_lut_size;
+uniform float u_intensity;
+
+void main() {
+  vec4 color = texture(u_input, v_uv);
+  // Sample texel centers so 0 and 1 map to the first and last entries
+  vec3 scale = vec3((u_lut_size - 1.0) / u_lut_size);
+  vec3 offset = vec3(0.5 / u_lut_size);
+  vec3 graded = texture(u_lut, clamp(color.rgb, 0.0, 1.0) * scale + offset).rgb;
+  outColor = vec4(mix(color.rgb, graded, u_intensity), color.a);
+}";
+
+// A LUT that maps every color to itself, as a starting point for grading.
+// Entry (r, g, b) is stored at x = r, y = g, z = b.
+pub fn identity_lut(size: i32) -> Texture3D {
+  if !(2..=256).contains(&size) {
+    panic!("Color grading LUTs must have between 2 and 256 entries per axis, got {}", size);
+  }
+  let max = (size - 1) as f32;
+  let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+  for b in 0..size {
+    for g in 0..size {
+      for r in 0..size {
+        for c in [r, g, b].iter() {
+          data.push((*c as f32 / max * 255.0).round() as u8);
+        }
+        data.push(255);
+      }
+    }
+  }
+  let mut lut = Texture3D::new();
+  lut.set_from_bytes(gli::RGBA8, size, size, size, gli::RGBA, &data);
+  lut
+}
+
+// Remaps colors through a 3D lookup table. The input should be in 0..1,
+// so this usually runs after tonemapping.
+pub struct ColorGrade {
+  pass: FullscreenPass,
+  lut: Rc<Texture3D>,
+  // Blend between the original (0) and graded (1) colors
+  pub intensity: f32,
+}
+
+impl ColorGrade {
+  // The LUT must be a cube; its filtering is set to linear and clamped. It
+  // is kept alive for as long as the effect, and may be shared with others.
+  pub fn new(lut: Rc<Texture3D>) -> ColorGrade {
+    if lut.width() != lut.height() || lut.width() != lut.depth() {
+      panic!("Color grading LUTs must be cubes, got {}x{}x{}", lut.width(), lut.height(), lut.depth());
+    }
+    lut.set_wrap_mode(WrapMode::ClampToEdge, WrapMode::ClampToEdge, WrapMode::ClampToEdge);
+    lut.set_filter_mode(FilterMode::Linear, FilterMode::Linear);
+    ColorGrade {
+      pass: FullscreenPass::new(COLOR_GRADE_SHADER),
+      lut,
+      intensity: 1.0,
+    }
+  }
+
+  pub fn lut(&self) -> &Rc<Texture3D> {
+    &self.lut
+  }
+}
+
+impl Effect for ColorGrade {
+  fn apply(&mut self, input: &Texture, target: RenderTarget) {
+    self.pass.set_input("u_input", input);
+    self.pass.set_uniform("u_lut", self.lut.as_uniform_value());
+    self.pass.set_uniform("u_lut_size", UniformValue::Float(self.lut.width() as f32));
+    self.pass.set_uniform("u_intensity", UniformValue::Float(self.intensity));
+    self.pass.draw_to(target);
+  }
+}
@@ -0,0 +1,69 @@
+use super::super::texture::Texture;
+use super::super::uniforms::UniformValue;
+use super::{Effect, FullscreenPass, RenderTarget};
+
+const FXAA_SHADER: &str = "#version 330
+in vec2 v_uv;
+out vec4 outColor;
+
+uniform sampler2D u_input;
+uniform vec2 u_texel_size;
+
+const float REDUCE_MIN = 1.0 / 128.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float SPAN_MAX = 8.0;
+
+void main() {
+  vec3 luma = vec3(0.299, 0.587, 0.114);
+  float lumaNW = dot(texture(u_input, v_uv + vec2(-1.0, -1.0) * u_texel_size).rgb, luma);
+  float lumaNE = dot(texture(u_input, v_uv + vec2(1.0, -1.0) * u_texel_size).rgb, luma);
+  float lumaSW = dot(texture(u_input, v_uv + vec2(-1.0, 1.0) * u_texel_size).rgb, luma);
+  float lumaSE = dot(texture(u_input, v_uv + vec2(1.0, 1.0) * u_texel_size).rgb, luma);
+  vec4 center = texture(u_input, v_uv);
+  float lumaM = dot(center.rgb, luma);
+  float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+  float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+  vec2 dir = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+  float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * (0.25 * REDUCE_MUL), REDUCE_MIN);
+  float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+  dir = clamp(dir * rcpDirMin, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * u_texel_size;
+
+  vec3 rgbA = 0.5 * (
+    texture(u_input, v_uv + dir * (1.0 / 3.0 - 0.5)).rgb +
+    texture(u_input, v_uv + dir * (2.0 / 3.0 - 0.5)).rgb);
+  vec3 rgbB = rgbA * 0.5 + 0.25 * (
+    texture(u_input, v_uv + dir * -0.5).rgb +
+    texture(u_input, v_uv + dir * 0.5).rgb);
+  float lumaB = dot(rgbB, luma);
+  outColor = vec4((lumaB < lumaMin || lumaB > lumaMax) ? rgbA : rgbB, center.a);
+}";
+
+// Fast approximate anti-aliasing. Expects gamma-encoded input, so it
+// usually runs after tonemapping.
+pub struct Fxaa {
+  pass: FullscreenPass,
+}
+
+impl Default for Fxaa {
+  fn default() -> Fxaa {
+    Fxaa::new()
+  }
+}
+
+impl Fxaa {
+  pub fn new() -> Fxaa {
+    Fxaa {
+      pass: FullscreenPass::new(FXAA_SHADER),
+    }
+  }
+}
+
+impl Effect for Fxaa {
+  fn apply(&mut self, input: &Texture, target: RenderTarget) {
+    self.pass.set_input("u_input", input);
+    let texel_size = UniformValue::FloatVec2(1.0 / input.width() as f32, 1.0 / input.height() as f32);
+    self.pass.set_uniform("u_texel_size", texel_size);
+    self.pass.draw_to(target);
+  }
+}
@@ -0,0 +1,244 @@
+use std::rc::Rc;
+use super::format::{Channels, TextureFormat};
+use super::framebuffer::Framebuffer;
+use super::gli;
+use super::node::Node;
+use super::program::Program;
+use super::render_state::{BlendMode, CullMode, RenderState};
+use super::texture::Texture;
+use super::uniforms::UniformValue;
+
+mod bloom;
+mod blur;
+mod color_grade;
+mod fxaa;
+mod tonemap;
+
+pub use self::bloom::Bloom;
+pub use self::blur::GaussianBlur;
+pub use self::color_grade::{identity_lut, ColorGrade};
+pub use self::fxaa::Fxaa;
+pub use self::tonemap::{Tonemap, TonemapOperator};
+
+// Vertex shader used by every FullscreenPass. Fragment shaders receive the
+// texture coordinate of the fragment as `v_uv`.
+pub const FULLSCREEN_VERTEX_SHADER: &str = "#version 330
+in vec2 a_position;
+out vec2 v_uv;
+
+void main() {
+  v_uv = a_position * 0.5 + 0.5;
+  gl_Position = vec4(a_position, 0, 1);
+}";
+
+// One triangle that covers the whole viewport; the parts outside are clipped
+const FULLSCREEN_TRIANGLE: [f32; 6] = [
+  -1.0, -1.0,
+  3.0, -1.0,
+  -1.0, 3.0,
+];
+
+// Intermediate results are kept in half floats so HDR values survive
+const SCRATCH_FORMAT: TextureFormat = TextureFormat::Float16(Channels::RGBA);
+
+#[derive(Clone, Copy)]
+pub enum RenderTarget<'a> {
+  // The default framebuffer, at this size
+  Screen(i32, i32),
+  Framebuffer(&'a Framebuffer),
+  // Whichever framebuffer is bound when the effect runs, such as the
+  // targets of a RenderGraph pass
+  Bound(i32, i32),
+}
+
+impl<'a> RenderTarget<'a> {
+  pub fn size(&self) -> (i32, i32) {
+    match self {
+      RenderTarget::Screen(w, h) | RenderTarget::Bound(w, h) => (*w, *h),
+      RenderTarget::Framebuffer(framebuffer) => (framebuffer.width(), framebuffer.height()),
+    }
+  }
+
+  // Pins down the GL framebuffer, so Bound targets survive effects that
+  // render through their own framebuffers first
+  fn resolve(&self) -> ResolvedTarget {
+    let (width, height) = self.size();
+    let gl_framebuffer = match self {
+      RenderTarget::Screen(..) => 0,
+      RenderTarget::Framebuffer(framebuffer) => framebuffer.gl_framebuffer(),
+      RenderTarget::Bound(..) => gli::bound_framebuffer(),
+    };
+    ResolvedTarget { gl_framebuffer, width, height }
+  }
+}
+
+struct ResolvedTarget {
+  gl_framebuffer: u32,
+  width: i32,
+  height: i32,
+}
+
+impl ResolvedTarget {
+  fn bind(&self) {
+    gli::bind_framebuffer(self.gl_framebuffer);
+    gli::viewport(0, 0, self.width, self.height);
+  }
+}
+
+// Returns `slot`'s framebuffer, (re)creating it at the requested size
+fn scratch_framebuffer(slot: &mut Option<Framebuffer>, width: i32, height: i32) -> &Framebuffer {
+  let width = width.max(1);
+  let height = height.max(1);
+  match slot {
+    Some(framebuffer) => framebuffer.resize(width, height),
+    None => *slot = Some(Framebuffer::with_color_formats(width, height, &[SCRATCH_FORMAT])),
+  }
+  slot.as_ref().unwrap()
+}
+
+// A post-processing step reading one texture and drawing into a target
+pub trait Effect {
+  fn apply(&mut self, input: &Texture, target: RenderTarget);
+}
+
+// Draws a fragment shader over the whole target
+pub struct FullscreenPass {
+  node: Node,
+  state: RenderState,
+}
+
+impl FullscreenPass {
+  // Compiles `fragment_shader` with FULLSCREEN_VERTEX_SHADER
+  pub fn new(fragment_shader: &'static str) -> FullscreenPass {
+    let mut program = Program::new();
+    program
+      .add_shader(FULLSCREEN_VERTEX_SHADER, gli::VERTEX_SHADER)
+      .add_shader(fragment_shader, gli::FRAGMENT_SHADER)
+      .compile();
+    FullscreenPass::with_program(Rc::new(program))
+  }
+
+  // The program's vertex shader needs a vec2 `a_position` input
+  pub fn with_program(program: Rc<Program>) -> FullscreenPass {
+    if program.get_attribute(&String::from("a_position")).is_none() {
+      panic!("Full-screen pass programs need an a_position attribute");
+    }
+    let mut node = Node::for_program(program);
+    node.add_attribute(String::from("a_position"));
+    node.buffer_data(&FULLSCREEN_TRIANGLE);
+    FullscreenPass {
+      node,
+      state: RenderState {
+        depth_test: false,
+        depth_write: false,
+        blend: BlendMode::Opaque,
+        cull: CullMode::None,
+      },
+    }
+  }
+
+  pub fn program(&self) -> &Rc<Program> {
+    self.node.program()
+  }
+
+  pub fn set_uniform(&mut self, name: &str, value: UniformValue) {
+    self.node.set_uniform(String::from(name), value);
+  }
+
+  pub fn set_input(&mut self, name: &str, texture: &Texture) {
+    self.set_uniform(name, texture.as_uniform_value());
+  }
+
+  // Defaults to no depth testing, blending or culling
+  pub fn set_render_state(&mut self, state: RenderState) {
+    self.state = state;
+  }
+
+  // Draws into whatever is currently bound
+  pub fn draw(&mut self) {
+    self.state.apply();
+    self.node.program().make_current();
+    self.node.draw();
+  }
+
+  pub fn draw_to(&mut self, target: RenderTarget) {
+    target.resolve().bind();
+    self.draw();
+  }
+}
+
+// Runs effects in sequence, ping-ponging between two scratch framebuffers
+// sized to the final target
+pub struct EffectChain {
+  effects: Vec<Box<dyn Effect>>,
+  ping: Option<Framebuffer>,
+  pong: Option<Framebuffer>,
+}
+
+impl Default for EffectChain {
+  fn default() -> EffectChain {
+    EffectChain::new()
+  }
+}
+
+impl EffectChain {
+  pub fn new() -> EffectChain {
+    EffectChain {
+      effects: Vec::new(),
+      ping: None,
+      pong: None,
+    }
+  }
+
+  pub fn push<E: Effect + 'static>(&mut self, effect: E) -> &mut EffectChain {
+    self.effects.push(Box::new(effect));
+    self
+  }
+
+  pub fn len(&self) -> usize {
+    self.effects.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.effects.is_empty()
+  }
+
+  pub fn effect_mut(&mut self, index: usize) -> &mut dyn Effect {
+    self.effects[index].as_mut()
+  }
+}
+
+impl Effect for EffectChain {
+  fn apply(&mut self, input: &Texture, target: RenderTarget) {
+    let resolved = target.resolve();
+    let last = match self.effects.len() {
+      0 => panic!("Cannot apply an empty effect chain"),
+      n => n - 1,
+    };
+    let (width, height) = (resolved.width, resolved.height);
+    if last > 0 {
+      scratch_framebuffer(&mut self.ping, width, height);
+    }
+    if last > 1 {
+      scratch_framebuffer(&mut self.pong, width, height);
+    }
+    let EffectChain { effects, ping, pong } = self;
+    // Even steps write to ping and odd steps to pong
+    for (i, effect) in effects.iter_mut().enumerate() {
+      let even = i & 1 == 0;
+      let source = match i {
+        0 => input,
+        _ if even => pong.as_ref().unwrap().color_texture(0),
+        _ => ping.as_ref().unwrap().color_texture(0),
+      };
+      if i == last {
+        resolved.bind();
+        effect.apply(source, RenderTarget::Bound(width, height));
+      } else if even {
+        effect.apply(source, RenderTarget::Framebuffer(ping.as_ref().unwrap()));
+      } else {
+        effect.apply(source, RenderTarget::Framebuffer(pong.as_ref().unwrap()));
+      }
+    }
+  }
+}
@@ -0,0 +1,85 @@
+use super::super::texture::Texture;
+use super::super::uniforms::UniformValue;
+use super::{Effect, FullscreenPass, RenderTarget};
+
+const TONEMAP_SHADER: &str = "#version 330
+in vec2 v_uv;
+out vec4 outColor;
+
+uniform sampler2D u_input;
+uniform float u_exposure;
+uniform float u_gamma;
+// 0 = clamp, 1 = Reinhard, 2 = ACES
+uniform float u_operator;
+
+vec3 aces(vec3 x) {
+  return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+  vec4 color = texture(u_input, v_uv);
+  vec3 hdr = color.rgb * u_exposure;
+  vec3 mapped;
+  if (u_operator < 0.5) {
+    mapped = clamp(hdr, 0.0, 1.0);
+  } else if (u_operator < 1.5) {
+    mapped = hdr / (hdr + vec3(1.0));
+  } else {
+    mapped = aces(hdr);
+  }
+  outColor = vec4(pow(mapped, vec3(1.0 / u_gamma)), color.a);
+}";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TonemapOperator {
+  Clamp,
+  Reinhard,
+  // Narkowicz's fit of the ACES filmic curve
+  Aces,
+}
+
+impl TonemapOperator {
+  fn shader_value(self) -> f32 {
+    match self {
+      TonemapOperator::Clamp => 0.0,
+      TonemapOperator::Reinhard => 1.0,
+      TonemapOperator::Aces => 2.0,
+    }
+  }
+}
+
+// Maps HDR color into 0..1 and applies gamma correction
+pub struct Tonemap {
+  pass: FullscreenPass,
+  pub operator: TonemapOperator,
+  pub exposure: f32,
+  // Use 1.0 when the target is an sRGB framebuffer that encodes on write
+  pub gamma: f32,
+}
+
+impl Default for Tonemap {
+  fn default() -> Tonemap {
+    Tonemap::new(TonemapOperator::Aces)
+  }
+}
+
+impl Tonemap {
+  pub fn new(operator: TonemapOperator) -> Tonemap {
+    Tonemap {
+      pass: FullscreenPass::new(TONEMAP_SHADER),
+      operator,
+      exposure: 1.0,
+      gamma: 2.2,
+    }
+  }
+}
+
+impl Effect for Tonemap {
+  fn apply(&mut self, input: &Texture, target: RenderTarget) {
+    self.pass.set_input("u_input", input);
+    self.pass.set_uniform("u_exposure", UniformValue::Float(self.exposure));
+    self.pass.set_uniform("u_gamma", UniformValue::Float(self.gamma));
+    self.pass.set_uniform("u_operator", UniformValue::Float(self.operator.shader_value()));
+    self.pass.draw_to(target);
+  }
+}
@@ -1,5 +1,8 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use super::gli;
+use super::material::MaterialBinding;
+use super::uniforms::{UniformValue, set_value_for_uniform};
 
 pub struct Attribute {
   pub location: u32,
@@ -24,6 +27,16 @@ pub struct Program {
   pub uniforms: UniformMap,
   program: Option<u32>,
   raw_shaders: Vec<RawShader>,
+  patch_vertices: i32,
+  // Queried once at link time so drawing doesn't ask the context each time
+  sampler_objects: bool,
+  pub(crate) material_binding: RefCell<Option<MaterialBinding>>,
+}
+
+impl Default for Program {
+  fn default() -> Program {
+    Program::new()
+  }
 }
 
 impl Program {
@@ -33,23 +46,32 @@ impl Program {
       uniforms: HashMap::new(),
       program: None,
       raw_shaders: Vec::new(),
+      patch_vertices: 3,
+      sampler_objects: false,
+      material_binding: RefCell::new(None),
     }
   }
 
   pub fn add_shader(&mut self, source: &'static str, shader_type: u32) -> &mut Program {
-    if let Some(_) = self.program {
+    if self.program.is_some() {
       panic!("Cannot add shader, the program has already been compiled");
     }
+    if !gli::supports_shader_stage(shader_type) {
+      panic!("Cannot add shader, stage {:#x} is not supported by the current context", shader_type);
+    }
     self.raw_shaders.push((shader_type, source));
     self
   }
 
   pub fn compile(&mut self) -> &mut Program {
-    if let Some(_) = self.program {
+    if self.program.is_some() {
       panic!("Cannot compile, the program has already been compiled")
     }
+    if self.is_compute() && self.raw_shaders.len() > 1 {
+      panic!("Cannot compile, compute shaders cannot be linked with other stages");
+    }
     let raw = &mut self.raw_shaders;
-    let compiled: Vec<u32> = raw.into_iter().map(|raw_shader| {
+    let compiled: Vec<u32> = raw.iter_mut().map(|raw_shader| {
       let (shader_type, source) = raw_shader;
       let shader = gli::create_shader(*shader_type);
       gli::shader_source(shader, source);
@@ -63,6 +85,7 @@ impl Program {
     }
     gli::link_program(program);
     self.program = Some(program);
+    self.sampler_objects = gli::supports_sampler_objects();
     extract_uniforms(program, &mut self.uniforms);
     extract_attributes(program, &mut self.attributes);
     self
@@ -74,9 +97,68 @@ impl Program {
     }
   }
 
+  // 0 until the program has been compiled
+  pub fn gl_program(&self) -> u32 {
+    self.program.unwrap_or(0)
+  }
+
   pub fn get_attribute(&self, name: &String) -> Option<&Attribute> {
     self.attributes.get(name)
   }
+
+  pub fn has_stage(&self, shader_type: u32) -> bool {
+    self.raw_shaders.iter().any(|(t, _)| *t == shader_type)
+  }
+
+  pub fn is_compute(&self) -> bool {
+    self.has_stage(gli::COMPUTE_SHADER)
+  }
+
+  pub fn uses_tessellation(&self) -> bool {
+    self.has_stage(gli::TESS_CONTROL_SHADER) || self.has_stage(gli::TESS_EVALUATION_SHADER)
+  }
+
+  pub fn set_patch_vertices(&mut self, count: i32) -> &mut Program {
+    self.patch_vertices = count;
+    self
+  }
+
+  pub fn patch_vertices(&self) -> i32 {
+    self.patch_vertices
+  }
+
+  // Whether the context the program was compiled in has sampler objects
+  pub fn supports_sampler_objects(&self) -> bool {
+    self.sampler_objects
+  }
+
+  pub fn set_uniform(&self, name: &str, value: &UniformValue) {
+    if let Some(uniform) = self.uniforms.get(name) {
+      self.make_current();
+      set_value_for_uniform(uniform.location, value);
+      self.mark_stale(name);
+    }
+  }
+
+  // Records that a uniform no longer holds the bound material's value
+  pub(crate) fn mark_stale(&self, name: &str) {
+    if let Some(binding) = self.material_binding.borrow_mut().as_mut() {
+      if !binding.stale.contains(name) {
+        binding.stale.insert(String::from(name));
+      }
+    }
+  }
+
+  pub fn dispatch_compute(&self, x: u32, y: u32, z: u32) {
+    if !self.is_compute() {
+      panic!("Cannot dispatch, the program does not contain a compute shader");
+    }
+    if self.program.is_none() {
+      panic!("Cannot dispatch, the program has not been compiled");
+    }
+    self.make_current();
+    gli::dispatch_compute(x, y, z);
+  }
 }
 
 fn extract_uniforms(program: u32, map: &mut UniformMap) {
@@ -87,9 +169,14 @@ fn extract_uniforms(program: u32, map: &mut UniformMap) {
     if location > -1 {
       let uniform = Uniform {
         location: location as u32,
-        size: size,
+        size,
         gl_type: uniform_type,
       };
+      // Arrays are reported as "name[0]", but set through their plain name
+      let name = match name.strip_suffix("[0]") {
+        Some(base) => String::from(base),
+        None => name,
+      };
       map.insert(name, uniform);
     }
   }
@@ -103,7 +190,7 @@ fn extract_attributes(program: u32, map: &mut AttributeMap) {
     if location > -1 {
       let attrib = Attribute {
         location: location as u32,
-        size: size,
+        size,
         gl_type: uniform_type,
       };
       map.insert(name, attrib);
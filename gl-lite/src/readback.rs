@@ -0,0 +1,207 @@
+use std::collections::VecDeque;
+#[cfg(feature = "image")]
+use std::path::Path;
+use super::gli;
+#[cfg(feature = "image")]
+use super::image_loader::ImageError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelRect {
+  pub x: i32,
+  pub y: i32,
+  pub width: i32,
+  pub height: i32,
+}
+
+impl PixelRect {
+  pub fn new(x: i32, y: i32, width: i32, height: i32) -> PixelRect {
+    PixelRect { x, y, width, height }
+  }
+
+  fn check(&self) {
+    if self.x < 0 || self.y < 0 || self.width < 1 || self.height < 1 {
+      panic!("Cannot read back a {}x{} region at ({}, {})", self.width, self.height, self.x, self.y);
+    }
+  }
+}
+
+pub struct PixelData {
+  pub width: i32,
+  pub height: i32,
+  pub format: u32,
+  // Tightly packed rows, bottom row first
+  pub data: Vec<u8>,
+}
+
+// Reads from the currently bound read framebuffer, which is the default
+// framebuffer unless an offscreen target is bound
+pub fn read_pixels(rect: PixelRect, format: u32) -> PixelData {
+  rect.check();
+  PixelData {
+    width: rect.width,
+    height: rect.height,
+    format,
+    data: gli::read_pixels(rect.x, rect.y, rect.width, rect.height, format),
+  }
+}
+
+// Reverses row order in place, converting between GL's bottom-up rows and
+// the top-down rows image files use
+pub fn flip_rows(data: &mut [u8], width: i32, height: i32, bytes_per_pixel: usize) {
+  let row = width as usize * bytes_per_pixel;
+  if data.len() != row * height as usize {
+    panic!("Expected {} bytes for {} rows of {} pixels, got {}", row * height as usize, height, width, data.len());
+  }
+  let rows = height as usize;
+  for top in 0..rows / 2 {
+    let bottom = rows - 1 - top;
+    let (upper, lower) = data.split_at_mut(bottom * row);
+    upper[top * row..(top + 1) * row].swap_with_slice(&mut lower[..row]);
+  }
+}
+
+impl PixelData {
+  pub fn bytes_per_pixel(&self) -> usize {
+    self.data.len() / (self.width as usize * self.height as usize)
+  }
+
+  pub fn flip_rows(&mut self) {
+    let bytes_per_pixel = self.bytes_per_pixel();
+    flip_rows(&mut self.data, self.width, self.height, bytes_per_pixel);
+  }
+
+  // Writes the pixels to a PNG, flipping them so the bottom row read from GL
+  // ends up at the bottom of the image
+  #[cfg(feature = "image")]
+  pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), ImageError> {
+    let color = match self.format {
+      gli::RED => image::ColorType::L8,
+      gli::RG => image::ColorType::La8,
+      gli::RGB => image::ColorType::Rgb8,
+      gli::RGBA => image::ColorType::Rgba8,
+      other => return Err(ImageError::Unsupported(format!("cannot write pixel format {:#x} as PNG", other))),
+    };
+    let mut rows = self.data.clone();
+    flip_rows(&mut rows, self.width, self.height, self.bytes_per_pixel());
+    image::save_buffer_with_format(path, &rows, self.width as u32, self.height as u32, color, image::ImageFormat::Png)?;
+    Ok(())
+  }
+}
+
+#[cfg(feature = "image")]
+pub fn save_screenshot<P: AsRef<Path>>(path: P, rect: PixelRect) -> Result<(), ImageError> {
+  read_pixels(rect, gli::RGBA).save_png(path)
+}
+
+struct PendingRead {
+  buffer: usize,
+  width: i32,
+  height: i32,
+  format: u32,
+  fence: gli::Fence,
+}
+
+// Reads pixels through a ring of pixel pack buffers. Each request returns as
+// soon as the copy is queued, and the data is collected a few frames later
+// once its fence has signaled, so capture never waits on the GPU.
+pub struct AsyncReadback {
+  buffers: Vec<u32>,
+  sizes: Vec<usize>,
+  pending: VecDeque<PendingRead>,
+  next: usize,
+}
+
+impl AsyncReadback {
+  pub fn new(count: usize) -> AsyncReadback {
+    if !gli::supports_pixel_buffers() {
+      panic!("Pixel buffer readback is not supported by the current context");
+    }
+    if count == 0 {
+      panic!("Cannot create an async readback with no buffers");
+    }
+    AsyncReadback {
+      buffers: (0..count).map(|_| gli::create_buffer()).collect(),
+      sizes: vec![0; count],
+      pending: VecDeque::with_capacity(count),
+      next: 0,
+    }
+  }
+
+  // Queues a read of the current read framebuffer. Returns false when every
+  // buffer still holds an uncollected read.
+  pub fn request(&mut self, rect: PixelRect, format: u32) -> bool {
+    rect.check();
+    if self.pending.len() == self.buffers.len() {
+      return false;
+    }
+    let index = self.next;
+    self.next = (self.next + 1) % self.buffers.len();
+    let size = gli::read_pixels_size(rect.width, rect.height, format);
+    gli::bind_pixel_pack_buffer(self.buffers[index]);
+    if self.sizes[index] != size {
+      gli::allocate_pixel_pack_buffer(size);
+      self.sizes[index] = size;
+    }
+    gli::read_pixels_to_buffer(rect.x, rect.y, rect.width, rect.height, format);
+    gli::bind_pixel_pack_buffer(0);
+    self.pending.push_back(PendingRead {
+      buffer: index,
+      width: rect.width,
+      height: rect.height,
+      format,
+      fence: gli::fence_sync(),
+    });
+    true
+  }
+
+  // Returns the oldest read if the GPU has finished it
+  pub fn try_read(&mut self) -> Option<PixelData> {
+    let ready = match self.pending.front() {
+      Some(read) => gli::poll_sync(&read.fence),
+      None => false,
+    };
+    if ready {
+      self.collect()
+    } else {
+      None
+    }
+  }
+
+  // Blocks until the oldest read is finished
+  pub fn read_blocking(&mut self) -> Option<PixelData> {
+    if let Some(read) = self.pending.front() {
+      gli::wait_sync(&read.fence);
+    }
+    self.collect()
+  }
+
+  pub fn pending(&self) -> usize {
+    self.pending.len()
+  }
+
+  fn collect(&mut self) -> Option<PixelData> {
+    let read = self.pending.pop_front()?;
+    let mut data = vec![0u8; self.sizes[read.buffer]];
+    gli::bind_pixel_pack_buffer(self.buffers[read.buffer]);
+    gli::get_pixel_pack_buffer_data(&mut data);
+    gli::bind_pixel_pack_buffer(0);
+    gli::delete_sync(read.fence);
+    Some(PixelData {
+      width: read.width,
+      height: read.height,
+      format: read.format,
+      data,
+    })
+  }
+}
+
+impl Drop for AsyncReadback {
+  fn drop(&mut self) {
+    for read in self.pending.drain(..) {
+      gli::delete_sync(read.fence);
+    }
+    for buffer in self.buffers.iter() {
+      gli::delete_buffer(*buffer);
+    }
+  }
+}
@@ -0,0 +1,735 @@
+use super::format::TextureFormat;
+use super::framebuffer::depth_attachment_point;
+use super::gli;
+use super::texture::Texture;
+use super::uniforms::UniformValue;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextureSize {
+  Fixed(i32, i32),
+  // Relative to the size the graph is executed at
+  Scaled(f32),
+}
+
+impl TextureSize {
+  fn resolve(self, width: i32, height: i32) -> (i32, i32) {
+    match self {
+      TextureSize::Fixed(w, h) => (w, h),
+      TextureSize::Scaled(s) => (((width as f32 * s) as i32).max(1), ((height as f32 * s) as i32).max(1)),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureDesc {
+  pub format: TextureFormat,
+  pub size: TextureSize,
+  // Cleared to this before the first pass that writes it. Depth textures
+  // use the first component as the depth value.
+  pub clear: Option<[f32; 4]>,
+}
+
+impl TextureDesc {
+  // Full size, not cleared
+  pub fn new(format: TextureFormat) -> TextureDesc {
+    TextureDesc {
+      format,
+      size: TextureSize::Scaled(1.0),
+      clear: None,
+    }
+  }
+
+  pub fn scaled(format: TextureFormat, scale: f32) -> TextureDesc {
+    TextureDesc {
+      size: TextureSize::Scaled(scale),
+      ..TextureDesc::new(format)
+    }
+  }
+
+  pub fn fixed(format: TextureFormat, width: i32, height: i32) -> TextureDesc {
+    TextureDesc {
+      size: TextureSize::Fixed(width, height),
+      ..TextureDesc::new(format)
+    }
+  }
+
+  pub fn with_clear(mut self, clear: [f32; 4]) -> TextureDesc {
+    self.clear = Some(clear);
+    self
+  }
+}
+
+// A texture declared in a RenderGraph. Transient textures only exist while
+// the graph executes and may share storage with other transient textures
+// whose lifetimes don't overlap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle(usize);
+
+enum Resource<'a> {
+  Transient(TextureDesc),
+  Imported(&'a Texture),
+}
+
+struct ResourceEntry<'a> {
+  name: String,
+  resource: Resource<'a>,
+  exported: bool,
+}
+
+impl<'a> ResourceEntry<'a> {
+  // Imported textures outlive the graph, so writes to them are outputs just
+  // like writes to exported ones
+  fn is_output(&self) -> bool {
+    self.exported || matches!(self.resource, Resource::Imported(_))
+  }
+}
+
+type PassCallback<'a> = Box<dyn FnMut(&PassContext) + 'a>;
+
+struct Pass<'a> {
+  name: String,
+  reads: Vec<TextureHandle>,
+  writes: Vec<TextureHandle>,
+  depth: Option<TextureHandle>,
+  to_screen: bool,
+  clear: Option<[f32; 4]>,
+  execute: Option<PassCallback<'a>>,
+}
+
+impl<'a> Pass<'a> {
+  fn targets(&self) -> impl Iterator<Item = &TextureHandle> {
+    self.writes.iter().chain(self.depth.iter())
+  }
+
+  fn resources(&self) -> impl Iterator<Item = &TextureHandle> {
+    self.reads.iter().chain(self.targets())
+  }
+}
+
+// Declares one pass. Finish with execute, which adds the pass to the graph.
+pub struct PassBuilder<'g, 'a> {
+  graph: &'g mut RenderGraph<'a>,
+  pass: Pass<'a>,
+}
+
+impl<'g, 'a> PassBuilder<'g, 'a> {
+  // A texture the pass samples from
+  pub fn read(mut self, texture: TextureHandle) -> PassBuilder<'g, 'a> {
+    self.graph.check_handle(texture);
+    self.pass.reads.push(texture);
+    self
+  }
+
+  // A color target, attached in the order the writes are declared
+  pub fn write(mut self, texture: TextureHandle) -> PassBuilder<'g, 'a> {
+    self.graph.check_handle(texture);
+    self.pass.writes.push(texture);
+    self
+  }
+
+  pub fn depth(mut self, texture: TextureHandle) -> PassBuilder<'g, 'a> {
+    self.graph.check_handle(texture);
+    self.pass.depth = Some(texture);
+    self
+  }
+
+  // Renders to the default framebuffer instead of textures. Screen passes
+  // are always kept when the graph culls unused passes.
+  pub fn to_screen(mut self) -> PassBuilder<'g, 'a> {
+    self.pass.to_screen = true;
+    self
+  }
+
+  // Clears every target of the pass before it runs
+  pub fn clear(mut self, color: [f32; 4]) -> PassBuilder<'g, 'a> {
+    self.pass.clear = Some(color);
+    self
+  }
+
+  pub fn execute<F: FnMut(&PassContext) + 'a>(mut self, callback: F) {
+    if self.pass.to_screen && self.pass.targets().next().is_some() {
+      panic!("Pass {} cannot write textures and render to the screen", self.pass.name);
+    }
+    self.pass.execute = Some(Box::new(callback));
+    self.graph.passes.push(self.pass);
+  }
+}
+
+// What a pass callback can see: the textures it declared and the size of its
+// targets. The targets are bound and the viewport covers them.
+pub struct PassContext<'p> {
+  textures: Vec<Option<&'p Texture>>,
+  width: i32,
+  height: i32,
+}
+
+impl<'p> PassContext<'p> {
+  pub fn texture(&self, handle: TextureHandle) -> &'p Texture {
+    match self.textures.get(handle.0) {
+      Some(Some(texture)) => texture,
+      _ => panic!("Texture {} was not declared by this pass", handle.0),
+    }
+  }
+
+  pub fn uniform(&self, handle: TextureHandle) -> UniformValue {
+    self.texture(handle).as_uniform_value()
+  }
+
+  pub fn size(&self) -> (i32, i32) {
+    (self.width, self.height)
+  }
+}
+
+struct PoolEntry {
+  texture: Texture,
+  format: TextureFormat,
+  width: i32,
+  height: i32,
+  in_use: bool,
+  used_this_frame: bool,
+}
+
+struct PassFramebuffer {
+  gl_framebuffer: u32,
+  color_count: usize,
+  has_depth: bool,
+}
+
+// GL objects backing RenderGraph executions. Keep one around between frames
+// so textures and framebuffers are reused rather than recreated.
+pub struct TexturePool {
+  entries: Vec<PoolEntry>,
+  framebuffers: Vec<PassFramebuffer>,
+}
+
+impl Default for TexturePool {
+  fn default() -> TexturePool {
+    TexturePool::new()
+  }
+}
+
+impl TexturePool {
+  pub fn new() -> TexturePool {
+    TexturePool {
+      entries: Vec::new(),
+      framebuffers: Vec::new(),
+    }
+  }
+
+  // Number of textures allocated so far
+  pub fn texture_count(&self) -> usize {
+    self.entries.len()
+  }
+
+  fn begin_frame(&mut self) {
+    for entry in self.entries.iter_mut() {
+      entry.in_use = false;
+      entry.used_this_frame = false;
+    }
+  }
+
+  // Prefers a free texture of the same shape, then one that hasn't been used
+  // yet this frame, and only then allocates
+  fn acquire(&mut self, format: TextureFormat, width: i32, height: i32) -> usize {
+    let matching = self.entries.iter().position(|e| {
+      !e.in_use && e.format == format && e.width == width && e.height == height
+    });
+    let index = match matching {
+      Some(index) => index,
+      None => match self.entries.iter().position(|e| !e.in_use && !e.used_this_frame) {
+        Some(index) => {
+          let entry = &mut self.entries[index];
+          entry.texture.allocate(format, width, height);
+          entry.format = format;
+          entry.width = width;
+          entry.height = height;
+          index
+        },
+        None => {
+          let mut texture = Texture::new();
+          texture.allocate(format, width, height);
+          self.entries.push(PoolEntry {
+            texture,
+            format,
+            width,
+            height,
+            in_use: false,
+            used_this_frame: false,
+          });
+          self.entries.len() - 1
+        },
+      },
+    };
+    let entry = &mut self.entries[index];
+    entry.in_use = true;
+    entry.used_this_frame = true;
+    index
+  }
+
+  fn release(&mut self, index: usize) {
+    self.entries[index].in_use = false;
+  }
+
+  fn framebuffer(&mut self, index: usize) -> &mut PassFramebuffer {
+    while self.framebuffers.len() <= index {
+      self.framebuffers.push(PassFramebuffer {
+        gl_framebuffer: gli::create_framebuffer(),
+        color_count: 0,
+        has_depth: false,
+      });
+    }
+    &mut self.framebuffers[index]
+  }
+}
+
+impl Drop for TexturePool {
+  fn drop(&mut self) {
+    for framebuffer in self.framebuffers.iter() {
+      gli::delete_framebuffer(framebuffer.gl_framebuffer);
+    }
+  }
+}
+
+// A frame's passes and the textures flowing between them. A read sees the
+// last write to the texture declared before it, or the last write overall
+// when none is, so consumers may be declared ahead of their producers.
+// Execution follows those dependencies, and a write waits for earlier reads
+// of the version it replaces, so textures can be reused for ping-ponging. When
+// anything renders to the screen or writes an exported or imported texture,
+// passes that don't contribute to those outputs are skipped.
+pub struct RenderGraph<'a> {
+  resources: Vec<ResourceEntry<'a>>,
+  passes: Vec<Pass<'a>>,
+  // Pool entry holding each transient texture in the last execution
+  assigned: Vec<Option<usize>>,
+}
+
+impl<'a> Default for RenderGraph<'a> {
+  fn default() -> RenderGraph<'a> {
+    RenderGraph::new()
+  }
+}
+
+impl<'a> RenderGraph<'a> {
+  pub fn new() -> RenderGraph<'a> {
+    RenderGraph {
+      resources: Vec::new(),
+      passes: Vec::new(),
+      assigned: Vec::new(),
+    }
+  }
+
+  pub fn create_texture(&mut self, name: &str, desc: TextureDesc) -> TextureHandle {
+    self.add_resource(name, Resource::Transient(desc))
+  }
+
+  // Makes an existing texture available to passes. Writes to it go straight
+  // to the texture, and it is never aliased.
+  pub fn import_texture(&mut self, name: &str, texture: &'a Texture) -> TextureHandle {
+    if texture.texture_format().is_none() {
+      panic!("Imported texture {} needs storage allocated with a TextureFormat", name);
+    }
+    self.add_resource(name, Resource::Imported(texture))
+  }
+
+  fn add_resource(&mut self, name: &str, resource: Resource<'a>) -> TextureHandle {
+    self.resources.push(ResourceEntry {
+      name: String::from(name),
+      resource,
+      exported: false,
+    });
+    TextureHandle(self.resources.len() - 1)
+  }
+
+  // Keeps a transient texture alive to the end of execution so it can be
+  // read afterwards with texture(), and keeps the passes that produce it
+  pub fn export(&mut self, texture: TextureHandle) {
+    self.check_handle(texture);
+    self.resources[texture.0].exported = true;
+  }
+
+  pub fn add_pass<'g>(&'g mut self, name: &str) -> PassBuilder<'g, 'a> {
+    PassBuilder {
+      graph: self,
+      pass: Pass {
+        name: String::from(name),
+        reads: Vec::new(),
+        writes: Vec::new(),
+        depth: None,
+        to_screen: false,
+        clear: None,
+        execute: None,
+      },
+    }
+  }
+
+  fn check_handle(&self, texture: TextureHandle) {
+    if texture.0 >= self.resources.len() {
+      panic!("Texture {} does not belong to this render graph", texture.0);
+    }
+  }
+
+  // Passes in execution order, after culling
+  pub fn pass_order(&self) -> Vec<&str> {
+    self.compile().into_iter().map(|p| self.passes[p].name.as_str()).collect()
+  }
+
+  fn writers(&self, texture: TextureHandle) -> impl Iterator<Item = usize> + '_ {
+    self.passes.iter().enumerate().filter(move |(_, p)| p.targets().any(|t| *t == texture)).map(|(i, _)| i)
+  }
+
+  fn readers(&self, texture: TextureHandle) -> impl Iterator<Item = usize> + '_ {
+    self.passes.iter().enumerate().filter(move |(_, p)| p.reads.contains(&texture)).map(|(i, _)| i)
+  }
+
+  // The pass whose output `pass` sees when it reads `texture`
+  fn source(&self, pass: usize, texture: TextureHandle) -> Option<usize> {
+    let writers: Vec<usize> = self.writers(texture).filter(|w| *w != pass).collect();
+    writers.iter().rev().find(|w| **w < pass).or_else(|| writers.last()).cloned()
+  }
+
+  // Passes whose results `pass` uses: the sources of what it reads, and
+  // earlier-declared writers of what it writes, since it draws over them
+  fn inputs(&self, pass: usize) -> Vec<usize> {
+    let mut deps = Vec::new();
+    let p = &self.passes[pass];
+    for texture in p.reads.iter() {
+      deps.extend(self.source(pass, *texture));
+    }
+    for texture in p.targets() {
+      deps.extend(self.writers(*texture).filter(|w| *w < pass));
+    }
+    deps.sort_unstable();
+    deps.dedup();
+    deps
+  }
+
+  // Passes that must run before `pass`: its inputs, and the passes reading
+  // an older version of a texture it overwrites
+  fn dependencies(&self, pass: usize) -> Vec<usize> {
+    let mut deps = self.inputs(pass);
+    for texture in self.passes[pass].targets() {
+      deps.extend(self.readers(*texture).filter(|r| {
+        *r != pass && matches!(self.source(*r, *texture), Some(s) if s < pass)
+      }));
+    }
+    deps.sort_unstable();
+    deps.dedup();
+    deps
+  }
+
+  fn compile(&self) -> Vec<usize> {
+    let inputs: Vec<Vec<usize>> = (0..self.passes.len()).map(|p| self.inputs(p)).collect();
+    let dependencies: Vec<Vec<usize>> = (0..self.passes.len()).map(|p| self.dependencies(p)).collect();
+
+    // Keep only passes that feed an output, if there are any outputs
+    let mut live = vec![false; self.passes.len()];
+    let mut stack: Vec<usize> = self.passes.iter().enumerate()
+      .filter(|(_, p)| p.to_screen || p.targets().any(|t| self.resources[t.0].is_output()))
+      .map(|(i, _)| i)
+      .collect();
+    if stack.is_empty() {
+      live = vec![true; self.passes.len()];
+    }
+    while let Some(p) = stack.pop() {
+      if !live[p] {
+        live[p] = true;
+        stack.extend(inputs[p].iter().cloned());
+      }
+    }
+
+    // Topological sort, preferring declaration order among ready passes
+    let mut order = Vec::new();
+    let mut done = vec![false; self.passes.len()];
+    let live_count = live.iter().filter(|l| **l).count();
+    while order.len() < live_count {
+      let next = (0..self.passes.len()).find(|p| {
+        live[*p] && !done[*p] && dependencies[*p].iter().all(|d| done[*d] || !live[*d])
+      });
+      match next {
+        Some(p) => {
+          done[p] = true;
+          order.push(p);
+        },
+        None => {
+          let stuck: Vec<&str> = (0..self.passes.len())
+            .filter(|p| live[*p] && !done[*p])
+            .map(|p| self.passes[p].name.as_str())
+            .collect();
+          panic!("Render graph passes form a cycle: {}", stuck.join(", "));
+        },
+      }
+    }
+    order
+  }
+
+  // Runs the live passes at the given output size
+  pub fn execute(&mut self, pool: &mut TexturePool, width: i32, height: i32) {
+    let order = self.compile();
+    let count = self.resources.len();
+    let mut first_use = vec![None; count];
+    let mut last_use = vec![0; count];
+    for (step, pass) in order.iter().enumerate() {
+      for texture in self.passes[*pass].resources() {
+        first_use[texture.0].get_or_insert(step);
+        last_use[texture.0] = step;
+      }
+    }
+
+    pool.begin_frame();
+    let mut assigned = vec![None; count];
+    for (step, pass_index) in order.iter().enumerate() {
+      let pass = &self.passes[*pass_index];
+      let mut fresh = Vec::new();
+      for texture in pass.resources() {
+        if first_use[texture.0] != Some(step) || assigned[texture.0].is_some() {
+          continue;
+        }
+        if let Resource::Transient(desc) = &self.resources[texture.0].resource {
+          if pass.reads.contains(texture) {
+            panic!("Pass {} reads {} before any pass writes it", pass.name, self.resources[texture.0].name);
+          }
+          let (w, h) = desc.size.resolve(width, height);
+          assigned[texture.0] = Some(pool.acquire(desc.format, w, h));
+        }
+        fresh.push(*texture);
+      }
+
+      let (target_width, target_height) = self.bind_targets(pool, &assigned, *pass_index, step, width, height);
+      self.clear_targets(*pass_index, &fresh);
+
+      let context = PassContext {
+        textures: (0..count).map(|r| {
+          let handle = TextureHandle(r);
+          if !pass.resources().any(|t| *t == handle) {
+            return None;
+          }
+          match &self.resources[r].resource {
+            Resource::Imported(texture) => Some(*texture),
+            Resource::Transient(_) => assigned[r].map(|i| &pool.entries[i].texture),
+          }
+        }).collect(),
+        width: target_width,
+        height: target_height,
+      };
+      if let Some(callback) = self.passes[*pass_index].execute.as_mut() {
+        callback(&context);
+      }
+
+      for texture in self.passes[*pass_index].resources() {
+        if last_use[texture.0] == step && !self.resources[texture.0].exported {
+          if let Some(index) = assigned[texture.0] {
+            pool.release(index);
+          }
+        }
+      }
+    }
+    gli::bind_framebuffer(0);
+    self.assigned = assigned;
+  }
+
+  // Binds a pass's framebuffer and viewport, returning the target size
+  fn bind_targets(&self, pool: &mut TexturePool, assigned: &[Option<usize>], pass: usize, step: usize, width: i32, height: i32) -> (i32, i32) {
+    let pass = &self.passes[pass];
+    if pass.to_screen || pass.targets().next().is_none() {
+      gli::bind_framebuffer(0);
+      gli::viewport(0, 0, width, height);
+      return (width, height);
+    }
+    let texture_for = |handle: &TextureHandle| -> &Texture {
+      match &self.resources[handle.0].resource {
+        Resource::Imported(texture) => texture,
+        Resource::Transient(_) => &pool.entries[assigned[handle.0].unwrap()].texture,
+      }
+    };
+    let mut size = None;
+    let mut attachments = Vec::new();
+    for handle in pass.targets() {
+      let texture = texture_for(handle);
+      let texture_size = (texture.width(), texture.height());
+      if *size.get_or_insert(texture_size) != texture_size {
+        panic!("Pass {} writes textures of different sizes", pass.name);
+      }
+      attachments.push((texture.gl_texture(), texture.texture_format()));
+    }
+    let depth = pass.depth.map(|_| attachments.pop().unwrap());
+    let framebuffer = pool.framebuffer(step);
+    gli::bind_framebuffer(framebuffer.gl_framebuffer);
+    for (i, (texture, _)) in attachments.iter().enumerate() {
+      gli::framebuffer_texture_2d(gli::COLOR_ATTACHMENT0 + i as u32, *texture);
+    }
+    for i in attachments.len()..framebuffer.color_count {
+      gli::framebuffer_texture_2d(gli::COLOR_ATTACHMENT0 + i as u32, 0);
+    }
+    framebuffer.color_count = attachments.len();
+    match depth {
+      Some((texture, format)) => {
+        let format = format.unwrap_or(TextureFormat::Depth24Stencil8);
+        if framebuffer.has_depth {
+          gli::framebuffer_texture_2d(gli::DEPTH_STENCIL_ATTACHMENT, 0);
+        }
+        gli::framebuffer_texture_2d(depth_attachment_point(format), texture);
+        framebuffer.has_depth = true;
+      },
+      None if framebuffer.has_depth => {
+        gli::framebuffer_texture_2d(gli::DEPTH_STENCIL_ATTACHMENT, 0);
+        framebuffer.has_depth = false;
+      },
+      None => (),
+    }
+    gli::draw_buffers(attachments.len());
+    let (w, h) = size.unwrap();
+    gli::viewport(0, 0, w, h);
+    (w, h)
+  }
+
+  // Applies the pass's clear, then the declared clears of textures this pass
+  // writes first
+  fn clear_targets(&self, pass: usize, fresh: &[TextureHandle]) {
+    let pass = &self.passes[pass];
+    if let Some([r, g, b, a]) = pass.clear {
+      gli::clear_color(r, g, b, a);
+      let mut mask = gli::COLOR_BUFFER_BIT;
+      if pass.to_screen || pass.depth.is_some() {
+        mask |= gli::DEPTH_BUFFER_BIT;
+      }
+      gli::clear(mask);
+    }
+    for (i, handle) in pass.writes.iter().enumerate() {
+      if let (true, Resource::Transient(TextureDesc { clear: Some(color), .. })) = (fresh.contains(handle), &self.resources[handle.0].resource) {
+        gli::clear_color_attachment(i, *color);
+      }
+    }
+    if let Some(handle) = pass.depth {
+      if let (true, Resource::Transient(TextureDesc { clear: Some(value), .. })) = (fresh.contains(&handle), &self.resources[handle.0].resource) {
+        gli::clear_depth_attachment(value[0]);
+      }
+    }
+  }
+
+  // An exported or imported texture after execute
+  pub fn texture<'p>(&'p self, pool: &'p TexturePool, handle: TextureHandle) -> Option<&'p Texture> {
+    self.check_handle(handle);
+    let entry = &self.resources[handle.0];
+    match &entry.resource {
+      Resource::Imported(texture) => Some(*texture),
+      Resource::Transient(_) if entry.exported => self.assigned.get(handle.0).cloned().flatten().map(|i| &pool.entries[i].texture),
+      Resource::Transient(_) => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::format::Channels;
+
+  fn color() -> TextureDesc {
+    TextureDesc::new(TextureFormat::Unorm8(Channels::RGBA))
+  }
+
+  #[test]
+  fn passes_run_after_the_passes_they_read_from() {
+    let mut graph = RenderGraph::new();
+    let lit = graph.create_texture("lit", color());
+    let gbuffer = graph.create_texture("gbuffer", color());
+    graph.add_pass("composite").read(lit).to_screen().execute(|_| ());
+    graph.add_pass("lighting").read(gbuffer).write(lit).execute(|_| ());
+    graph.add_pass("geometry").write(gbuffer).execute(|_| ());
+    assert_eq!(graph.pass_order(), vec!["geometry", "lighting", "composite"]);
+  }
+
+  #[test]
+  fn writes_to_the_same_texture_keep_declaration_order() {
+    let mut graph = RenderGraph::new();
+    let target = graph.create_texture("target", color());
+    graph.export(target);
+    graph.add_pass("opaque").write(target).execute(|_| ());
+    graph.add_pass("transparent").write(target).execute(|_| ());
+    assert_eq!(graph.pass_order(), vec!["opaque", "transparent"]);
+  }
+
+  #[test]
+  fn passes_not_feeding_an_output_are_culled() {
+    let mut graph = RenderGraph::new();
+    let used = graph.create_texture("used", color());
+    let unused = graph.create_texture("unused", color());
+    graph.add_pass("debug").write(unused).execute(|_| ());
+    graph.add_pass("scene").write(used).execute(|_| ());
+    graph.add_pass("present").read(used).to_screen().execute(|_| ());
+    assert_eq!(graph.pass_order(), vec!["scene", "present"]);
+  }
+
+  #[test]
+  fn exported_textures_keep_their_writers() {
+    let mut graph = RenderGraph::new();
+    let shadow = graph.create_texture("shadow", color());
+    let unused = graph.create_texture("unused", color());
+    graph.export(shadow);
+    graph.add_pass("shadow").write(shadow).execute(|_| ());
+    graph.add_pass("debug").write(unused).execute(|_| ());
+    assert_eq!(graph.pass_order(), vec!["shadow"]);
+  }
+
+  #[test]
+  fn nothing_is_culled_without_outputs() {
+    let mut graph = RenderGraph::new();
+    let a = graph.create_texture("a", color());
+    let b = graph.create_texture("b", color());
+    graph.add_pass("first").write(a).execute(|_| ());
+    graph.add_pass("second").write(b).execute(|_| ());
+    assert_eq!(graph.pass_order(), vec!["first", "second"]);
+  }
+
+  #[test]
+  #[should_panic(expected = "Render graph passes form a cycle: ping, pong")]
+  fn cycles_panic() {
+    let mut graph = RenderGraph::new();
+    let a = graph.create_texture("a", color());
+    let b = graph.create_texture("b", color());
+    graph.add_pass("ping").read(b).write(a).execute(|_| ());
+    graph.add_pass("pong").read(a).write(b).execute(|_| ());
+    graph.add_pass("present").read(a).to_screen().execute(|_| ());
+    graph.pass_order();
+  }
+
+  #[test]
+  fn ping_pong_textures_can_be_reused() {
+    let mut graph = RenderGraph::new();
+    let tmp = graph.create_texture("tmp", color());
+    let dst = graph.create_texture("dst", color());
+    graph.add_pass("blur_h1").write(tmp).execute(|_| ());
+    graph.add_pass("blur_v1").read(tmp).write(dst).execute(|_| ());
+    graph.add_pass("blur_h2").read(dst).write(tmp).execute(|_| ());
+    graph.add_pass("present").read(tmp).to_screen().execute(|_| ());
+    assert_eq!(graph.pass_order(), vec!["blur_h1", "blur_v1", "blur_h2", "present"]);
+  }
+
+  #[test]
+  fn writes_wait_for_earlier_reads() {
+    let mut graph = RenderGraph::new();
+    let t = graph.create_texture("t", color());
+    let s = graph.create_texture("s", color());
+    let out = graph.create_texture("out", color());
+    graph.add_pass("first").write(t).execute(|_| ());
+    // Blocked on "mask" below, so "second" would otherwise be ready first
+    graph.add_pass("consume").read(t).read(s).write(out).execute(|_| ());
+    graph.add_pass("second").write(t).execute(|_| ());
+    graph.add_pass("mask").write(s).execute(|_| ());
+    graph.add_pass("present").read(out).read(t).to_screen().execute(|_| ());
+    assert_eq!(graph.pass_order(), vec!["first", "mask", "consume", "second", "present"]);
+  }
+
+  #[test]
+  fn reads_only_depend_on_the_version_they_see() {
+    let mut graph = RenderGraph::new();
+    let t = graph.create_texture("t", color());
+    let out = graph.create_texture("out", color());
+    graph.add_pass("early").write(t).execute(|_| ());
+    graph.add_pass("consume").read(t).write(out).execute(|_| ());
+    graph.add_pass("late").write(t).execute(|_| ());
+    graph.add_pass("present").read(out).to_screen().execute(|_| ());
+    // "late" overwrites t after "consume", which nothing else reads
+    assert_eq!(graph.pass_order(), vec!["early", "consume", "present"]);
+  }
+}
@@ -0,0 +1,256 @@
+use std::cmp::Ordering;
+use super::math::{Mat4, Vec3};
+use super::node::Node;
+use super::render_state::RenderState;
+
+// Distance in front of the camera for a world-space position, for use as a
+// draw item's depth
+pub fn view_depth(view: &Mat4, position: Vec3) -> f32 {
+  -view.transform_point(position).z
+}
+
+// What a submit did, counting each binding that differed from the previous
+// draw. Program and render state changes are only applied when they differ.
+// Node::draw binds its textures and vertex array every time, so those two
+// counts measure how well the sort grouped draws, not GL calls saved, and
+// are left out of state_changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+  pub draw_calls: usize,
+  pub program_changes: usize,
+  pub texture_changes: usize,
+  pub vertex_array_changes: usize,
+  pub render_state_changes: usize,
+}
+
+impl RenderStats {
+  // Program and render state changes actually made
+  pub fn state_changes(&self) -> usize {
+    self.program_changes + self.render_state_changes
+  }
+}
+
+// What the queue sorts a draw by
+#[derive(Clone, Debug, PartialEq)]
+struct SortKey {
+  program: u32,
+  // Material id, 0 without one
+  material: usize,
+  textures: Vec<u32>,
+  vertex_array: u32,
+  depth: f32,
+}
+
+fn compare_depth(a: f32, b: f32) -> Ordering {
+  a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+impl SortKey {
+  // Groups by program, then material, textures and vertex array, and goes
+  // front to back within a group
+  fn opaque_order(&self, other: &SortKey) -> Ordering {
+    self.program.cmp(&other.program)
+      .then_with(|| self.material.cmp(&other.material))
+      .then_with(|| self.textures.cmp(&other.textures))
+      .then_with(|| self.vertex_array.cmp(&other.vertex_array))
+      .then_with(|| compare_depth(self.depth, other.depth))
+  }
+
+  // Back to front only, since blending depends on draw order
+  fn transparent_order(&self, other: &SortKey) -> Ordering {
+    compare_depth(other.depth, self.depth)
+  }
+}
+
+struct DrawItem<'a> {
+  node: &'a mut Node,
+  state: RenderState,
+  key: SortKey,
+}
+
+// Collects the draws for a frame and submits them in an order that keeps
+// state changes down. Opaque items are grouped by program, then material,
+// then textures, then vertex array, and drawn front to back within a group.
+// Transparent items are drawn afterwards, back to front.
+pub struct RenderQueue<'a> {
+  opaque: Vec<DrawItem<'a>>,
+  transparent: Vec<DrawItem<'a>>,
+}
+
+impl<'a> Default for RenderQueue<'a> {
+  fn default() -> RenderQueue<'a> {
+    RenderQueue::new()
+  }
+}
+
+impl<'a> RenderQueue<'a> {
+  pub fn new() -> RenderQueue<'a> {
+    RenderQueue {
+      opaque: Vec::new(),
+      transparent: Vec::new(),
+    }
+  }
+
+  // `depth` is the distance from the camera; see view_depth. Items whose
+  // state blends are treated as transparent.
+  pub fn push(&mut self, node: &'a mut Node, state: RenderState, depth: f32) {
+    let key = SortKey {
+      program: node.program().gl_program(),
+      material: node.material().map_or(0, |m| m.id()),
+      textures: node.textures(),
+      vertex_array: node.geometry().vertex_array(),
+      depth,
+    };
+    let item = DrawItem {
+      node,
+      state,
+      key,
+    };
+    if state.is_transparent() {
+      self.transparent.push(item);
+    } else {
+      self.opaque.push(item);
+    }
+  }
+
+  // Pushes with the node's own render state; see Node::render_state
+  pub fn push_node(&mut self, node: &'a mut Node, depth: f32) {
+    let state = node.render_state();
+    self.push(node, state, depth);
+  }
+
+  pub fn len(&self) -> usize {
+    self.opaque.len() + self.transparent.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn clear(&mut self) {
+    self.opaque.clear();
+    self.transparent.clear();
+  }
+
+  fn sort(&mut self) {
+    self.opaque.sort_by(|a, b| a.key.opaque_order(&b.key));
+    // Stable, so items at equal depth keep their submission order
+    self.transparent.sort_by(|a, b| a.key.transparent_order(&b.key));
+  }
+
+  // Sorts and draws everything, leaving the queue empty. GL state is left
+  // as the last item set it.
+  pub fn submit(&mut self) -> RenderStats {
+    self.sort();
+    let mut stats = RenderStats::default();
+    let mut previous: Option<(SortKey, RenderState)> = None;
+    for item in self.opaque.drain(..).chain(self.transparent.drain(..)) {
+      let key = &item.key;
+      match &previous {
+        Some((previous_key, state)) => {
+          if previous_key.program != key.program {
+            stats.program_changes += 1;
+            item.node.program().make_current();
+          }
+          // Counted only; the node binds its textures and vertex array itself
+          if previous_key.textures != key.textures {
+            stats.texture_changes += 1;
+          }
+          if previous_key.vertex_array != key.vertex_array {
+            stats.vertex_array_changes += 1;
+          }
+          if *state != item.state {
+            stats.render_state_changes += 1;
+            item.state.apply_changes(state);
+          }
+        },
+        None => {
+          stats.program_changes += 1;
+          if !key.textures.is_empty() {
+            stats.texture_changes += 1;
+          }
+          stats.vertex_array_changes += 1;
+          stats.render_state_changes += 1;
+          item.node.program().make_current();
+          item.state.apply();
+        },
+      }
+      item.node.draw();
+      stats.draw_calls += 1;
+      previous = Some((item.key, item.state));
+    }
+    stats
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(program: u32, material: usize, textures: &[u32], vertex_array: u32, depth: f32) -> SortKey {
+    SortKey {
+      program,
+      material,
+      textures: textures.to_vec(),
+      vertex_array,
+      depth,
+    }
+  }
+
+  #[test]
+  fn opaque_keys_group_by_state_then_go_front_to_back() {
+    let mut keys = vec![
+      key(2, 0, &[], 1, 1.0),
+      key(1, 2, &[], 1, 1.0),
+      key(1, 1, &[5], 2, 9.0),
+      key(1, 1, &[5], 2, 3.0),
+      key(1, 1, &[5], 1, 7.0),
+      key(1, 1, &[4, 6], 3, 2.0),
+      key(1, 1, &[4], 3, 2.0),
+    ];
+    keys.sort_by(|a, b| a.opaque_order(b));
+    assert_eq!(keys, vec![
+      key(1, 1, &[4], 3, 2.0),
+      key(1, 1, &[4, 6], 3, 2.0),
+      key(1, 1, &[5], 1, 7.0),
+      key(1, 1, &[5], 2, 3.0),
+      key(1, 1, &[5], 2, 9.0),
+      key(1, 2, &[], 1, 1.0),
+      key(2, 0, &[], 1, 1.0),
+    ]);
+  }
+
+  #[test]
+  fn program_outranks_depth() {
+    let near = key(2, 0, &[], 1, 0.5);
+    let far = key(1, 0, &[], 1, 50.0);
+    assert_eq!(far.opaque_order(&near), Ordering::Less);
+  }
+
+  #[test]
+  fn transparent_keys_go_back_to_front_keeping_ties_in_order() {
+    // Programs differ so ties can be told apart; state is ignored
+    let mut keys = [
+      key(1, 0, &[], 1, 2.0),
+      key(2, 0, &[], 1, 8.0),
+      key(3, 0, &[], 1, 2.0),
+      key(4, 0, &[], 1, 5.0),
+      key(0, 0, &[], 1, 2.0),
+    ];
+    keys.sort_by(|a, b| a.transparent_order(b));
+    let programs: Vec<u32> = keys.iter().map(|k| k.program).collect();
+    assert_eq!(programs, vec![2, 4, 1, 3, 0]);
+  }
+
+  #[test]
+  fn state_changes_only_count_what_submit_applies() {
+    let stats = RenderStats {
+      draw_calls: 10,
+      program_changes: 2,
+      texture_changes: 4,
+      vertex_array_changes: 5,
+      render_state_changes: 3,
+    };
+    assert_eq!(stats.state_changes(), 5);
+  }
+}
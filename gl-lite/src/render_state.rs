@@ -0,0 +1,117 @@
+use super::gli;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+  Opaque,
+  // Straight alpha: src * a + dst * (1 - a)
+  Alpha,
+  // Color already multiplied by alpha: src + dst * (1 - a)
+  Premultiplied,
+  Additive,
+}
+
+impl BlendMode {
+  // (source, destination) factors, or None when blending is disabled
+  pub fn gl_factors(self) -> Option<(u32, u32)> {
+    match self {
+      BlendMode::Opaque => None,
+      BlendMode::Alpha => Some((gli::SRC_ALPHA, gli::ONE_MINUS_SRC_ALPHA)),
+      BlendMode::Premultiplied => Some((gli::ONE, gli::ONE_MINUS_SRC_ALPHA)),
+      BlendMode::Additive => Some((gli::ONE, gli::ONE)),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CullMode {
+  None,
+  Back,
+  Front,
+}
+
+fn apply_blend(mode: BlendMode) {
+  match mode.gl_factors() {
+    Some((src, dst)) => {
+      gli::set_blending(true);
+      gli::blend_func(src, dst);
+    },
+    None => gli::set_blending(false),
+  }
+}
+
+fn apply_cull(mode: CullMode) {
+  match mode {
+    CullMode::None => gli::set_face_culling(false),
+    CullMode::Back => {
+      gli::set_face_culling(true);
+      gli::cull_face(gli::BACK);
+    },
+    CullMode::Front => {
+      gli::set_face_culling(true);
+      gli::cull_face(gli::FRONT);
+    },
+  }
+}
+
+// Fixed-function state set before a draw
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderState {
+  pub depth_test: bool,
+  pub depth_write: bool,
+  pub blend: BlendMode,
+  pub cull: CullMode,
+}
+
+impl Default for RenderState {
+  fn default() -> RenderState {
+    RenderState::opaque()
+  }
+}
+
+impl RenderState {
+  pub fn opaque() -> RenderState {
+    RenderState {
+      depth_test: true,
+      depth_write: true,
+      blend: BlendMode::Opaque,
+      cull: CullMode::Back,
+    }
+  }
+
+  // Alpha blended, depth tested but not written, and double sided
+  pub fn transparent() -> RenderState {
+    RenderState {
+      depth_test: true,
+      depth_write: false,
+      blend: BlendMode::Alpha,
+      cull: CullMode::None,
+    }
+  }
+
+  pub fn is_transparent(&self) -> bool {
+    self.blend != BlendMode::Opaque
+  }
+
+  pub fn apply(&self) {
+    gli::set_depth_test(self.depth_test);
+    gli::depth_mask(self.depth_write);
+    apply_blend(self.blend);
+    apply_cull(self.cull);
+  }
+
+  // Applies only the parts that differ from `previous`
+  pub fn apply_changes(&self, previous: &RenderState) {
+    if self.depth_test != previous.depth_test {
+      gli::set_depth_test(self.depth_test);
+    }
+    if self.depth_write != previous.depth_write {
+      gli::depth_mask(self.depth_write);
+    }
+    if self.blend != previous.blend {
+      apply_blend(self.blend);
+    }
+    if self.cull != previous.cull {
+      apply_cull(self.cull);
+    }
+  }
+}
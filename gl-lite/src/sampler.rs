@@ -0,0 +1,78 @@
+use super::gli;
+use super::texture::{FilterMode, WrapMode};
+
+pub struct Sampler {
+  gl_sampler: u32,
+}
+
+impl Default for Sampler {
+  fn default() -> Sampler {
+    Sampler::new()
+  }
+}
+
+impl Sampler {
+  pub fn new() -> Sampler {
+    if !gli::supports_sampler_objects() {
+      panic!("Sampler objects need OpenGL 3.3 or GL_ARB_sampler_objects");
+    }
+    let sampler = gli::create_sampler();
+    gli::sampler_parameter(sampler, gli::TEXTURE_WRAP_S, gli::CLAMP_TO_EDGE);
+    gli::sampler_parameter(sampler, gli::TEXTURE_WRAP_T, gli::CLAMP_TO_EDGE);
+    gli::sampler_parameter(sampler, gli::TEXTURE_MIN_FILTER, gli::LINEAR);
+    gli::sampler_parameter(sampler, gli::TEXTURE_MAG_FILTER, gli::LINEAR);
+    Sampler {
+      gl_sampler: sampler,
+    }
+  }
+
+  pub fn gl_sampler(&self) -> u32 {
+    self.gl_sampler
+  }
+
+  pub fn set_wrap_mode(&self, s: WrapMode, t: WrapMode) {
+    gli::sampler_parameter(self.gl_sampler, gli::TEXTURE_WRAP_S, s.gl_wrap());
+    gli::sampler_parameter(self.gl_sampler, gli::TEXTURE_WRAP_T, t.gl_wrap());
+  }
+
+  pub fn set_filter_mode(&self, min: FilterMode, mag: FilterMode) {
+    if mag.uses_mipmaps() {
+      panic!("Magnification filter cannot use mipmaps, got {:?}", mag);
+    }
+    gli::sampler_parameter(self.gl_sampler, gli::TEXTURE_MIN_FILTER, min.gl_filter());
+    gli::sampler_parameter(self.gl_sampler, gli::TEXTURE_MAG_FILTER, mag.gl_filter());
+  }
+
+  pub fn set_lod_bias(&self, bias: f32) {
+    gli::sampler_parameter_f(self.gl_sampler, gli::TEXTURE_LOD_BIAS, bias);
+  }
+
+  pub fn set_lod_range(&self, min: f32, max: f32) {
+    gli::sampler_parameter_f(self.gl_sampler, gli::TEXTURE_MIN_LOD, min);
+    gli::sampler_parameter_f(self.gl_sampler, gli::TEXTURE_MAX_LOD, max);
+  }
+
+  pub fn set_anisotropy(&self, level: f32) -> bool {
+    match gli::max_texture_anisotropy() {
+      Some(max) => {
+        gli::sampler_parameter_f(self.gl_sampler, gli::TEXTURE_MAX_ANISOTROPY, level.max(1.0).min(max));
+        true
+      },
+      None => false,
+    }
+  }
+
+  pub fn set_border_color(&self, color: [f32; 4]) {
+    gli::sampler_parameter_fv(self.gl_sampler, gli::TEXTURE_BORDER_COLOR, &color);
+  }
+
+  pub fn bind_to_slot(&self, slot: u32) {
+    gli::bind_sampler(slot, self.gl_sampler);
+  }
+}
+
+impl Drop for Sampler {
+  fn drop(&mut self) {
+    gli::delete_sampler(self.gl_sampler);
+  }
+}
@@ -0,0 +1,489 @@
+use std::rc::Rc;
+use super::camera::{Camera, CameraUniforms};
+use super::math::{Mat4, Quat, Vec3};
+use super::node::Node;
+use super::program::Program;
+use super::render_state::RenderState;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+  pub translation: Vec3,
+  pub rotation: Quat,
+  pub scale: Vec3,
+}
+
+impl Default for Transform {
+  fn default() -> Transform {
+    Transform::new()
+  }
+}
+
+impl Transform {
+  pub fn new() -> Transform {
+    Transform {
+      translation: Vec3::ZERO,
+      rotation: Quat::IDENTITY,
+      scale: Vec3::ONE,
+    }
+  }
+
+  pub fn from_translation(translation: Vec3) -> Transform {
+    Transform {
+      translation,
+      ..Transform::new()
+    }
+  }
+
+  pub fn matrix(&self) -> Mat4 {
+    Mat4::from_trs(self.translation, self.rotation, self.scale)
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+  index: usize,
+  // Bumped each time the slot is freed, so ids of removed nodes never match
+  // a node that later reuses the slot
+  generation: u32,
+}
+
+struct SceneNode {
+  local: Transform,
+  world: Mat4,
+  // Set when the local transform changed since the last update
+  dirty: bool,
+  visible: bool,
+  parent: Option<NodeId>,
+  children: Vec<NodeId>,
+  node: Option<Node>,
+}
+
+// A hierarchy of nodes with local transforms. World matrices are cached and
+// only recomputed for nodes whose transform, or an ancestor's, changed.
+pub struct Scene {
+  nodes: Vec<Option<SceneNode>>,
+  generations: Vec<u32>,
+  free: Vec<usize>,
+  roots: Vec<NodeId>,
+  model_uniform: String,
+  camera_uniforms: CameraUniforms,
+}
+
+impl Default for Scene {
+  fn default() -> Scene {
+    Scene::new()
+  }
+}
+
+impl Scene {
+  pub fn new() -> Scene {
+    Scene {
+      nodes: Vec::new(),
+      generations: Vec::new(),
+      free: Vec::new(),
+      roots: Vec::new(),
+      model_uniform: String::from("u_model"),
+      camera_uniforms: CameraUniforms::new(),
+    }
+  }
+
+  // Name of the mat4 uniform that receives each node's world matrix
+  pub fn set_model_uniform(&mut self, name: &str) {
+    self.model_uniform = String::from(name);
+  }
+
+  pub fn model_uniform(&self) -> &str {
+    &self.model_uniform
+  }
+
+  // Names used by draw_with_camera
+  pub fn set_camera_uniforms(&mut self, uniforms: CameraUniforms) {
+    self.camera_uniforms = uniforms;
+  }
+
+  pub fn add(&mut self, node: Node) -> NodeId {
+    self.insert(None, Some(node))
+  }
+
+  // Adds a transform-only node, useful for grouping
+  pub fn add_empty(&mut self) -> NodeId {
+    self.insert(None, None)
+  }
+
+  pub fn add_child(&mut self, parent: NodeId, node: Node) -> NodeId {
+    self.insert(Some(parent), Some(node))
+  }
+
+  pub fn add_empty_child(&mut self, parent: NodeId) -> NodeId {
+    self.insert(Some(parent), None)
+  }
+
+  fn insert(&mut self, parent: Option<NodeId>, node: Option<Node>) -> NodeId {
+    if let Some(p) = parent {
+      self.get(p);
+    }
+    let entry = SceneNode {
+      local: Transform::new(),
+      world: Mat4::IDENTITY,
+      dirty: true,
+      visible: true,
+      parent,
+      children: Vec::new(),
+      node,
+    };
+    let index = match self.free.pop() {
+      Some(index) => {
+        self.nodes[index] = Some(entry);
+        index
+      },
+      None => {
+        self.nodes.push(Some(entry));
+        self.generations.push(0);
+        self.nodes.len() - 1
+      },
+    };
+    let id = NodeId { index, generation: self.generations[index] };
+    match parent {
+      Some(p) => self.get_mut(p).children.push(id),
+      None => self.roots.push(id),
+    }
+    id
+  }
+
+  fn is_current(&self, id: NodeId) -> bool {
+    self.generations.get(id.index) == Some(&id.generation)
+  }
+
+  fn get(&self, id: NodeId) -> &SceneNode {
+    match self.nodes.get(id.index) {
+      Some(Some(node)) if self.is_current(id) => node,
+      _ => panic!("Scene node {} does not exist", id.index),
+    }
+  }
+
+  fn get_mut(&mut self, id: NodeId) -> &mut SceneNode {
+    let current = self.is_current(id);
+    match self.nodes.get_mut(id.index) {
+      Some(Some(node)) if current => node,
+      _ => panic!("Scene node {} does not exist", id.index),
+    }
+  }
+
+  pub fn contains(&self, id: NodeId) -> bool {
+    self.is_current(id) && matches!(self.nodes.get(id.index), Some(Some(_)))
+  }
+
+  fn free_slot(&mut self, index: usize) {
+    self.generations[index] = self.generations[index].wrapping_add(1);
+    self.free.push(index);
+  }
+
+  pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+    self.get(id).parent
+  }
+
+  pub fn children(&self, id: NodeId) -> &[NodeId] {
+    &self.get(id).children
+  }
+
+  pub fn roots(&self) -> &[NodeId] {
+    &self.roots
+  }
+
+  // Moves `id` under `parent`, or to the top level when `parent` is None.
+  // The local transform is kept, so the node moves with its new parent.
+  pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+    if let Some(p) = parent {
+      let mut ancestor = Some(p);
+      while let Some(a) = ancestor {
+        if a == id {
+          panic!("Cannot parent scene node {} to its own descendant {}", id.index, p.index);
+        }
+        ancestor = self.get(a).parent;
+      }
+    }
+    match self.get(id).parent {
+      Some(old) => self.get_mut(old).children.retain(|c| *c != id),
+      None => self.roots.retain(|r| *r != id),
+    }
+    match parent {
+      Some(p) => self.get_mut(p).children.push(id),
+      None => self.roots.push(id),
+    }
+    let entry = self.get_mut(id);
+    entry.parent = parent;
+    entry.dirty = true;
+  }
+
+  // Removes a node and all of its descendants, returning the node's contents
+  pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+    match self.get(id).parent {
+      Some(p) => self.get_mut(p).children.retain(|c| *c != id),
+      None => self.roots.retain(|r| *r != id),
+    }
+    let mut stack = self.get(id).children.clone();
+    while let Some(child) = stack.pop() {
+      if let Some(entry) = self.nodes[child.index].take() {
+        stack.extend(entry.children);
+        self.free_slot(child.index);
+      }
+    }
+    self.free_slot(id.index);
+    self.nodes[id.index].take().and_then(|entry| entry.node)
+  }
+
+  pub fn transform(&self, id: NodeId) -> &Transform {
+    &self.get(id).local
+  }
+
+  pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+    let entry = self.get_mut(id);
+    entry.local = transform;
+    entry.dirty = true;
+  }
+
+  pub fn set_translation(&mut self, id: NodeId, translation: Vec3) {
+    let entry = self.get_mut(id);
+    entry.local.translation = translation;
+    entry.dirty = true;
+  }
+
+  pub fn set_rotation(&mut self, id: NodeId, rotation: Quat) {
+    let entry = self.get_mut(id);
+    entry.local.rotation = rotation;
+    entry.dirty = true;
+  }
+
+  pub fn set_scale(&mut self, id: NodeId, scale: Vec3) {
+    let entry = self.get_mut(id);
+    entry.local.scale = scale;
+    entry.dirty = true;
+  }
+
+  // Hiding a node also hides its descendants
+  pub fn set_visible(&mut self, id: NodeId, visible: bool) {
+    self.get_mut(id).visible = visible;
+  }
+
+  pub fn is_visible(&self, id: NodeId) -> bool {
+    self.get(id).visible
+  }
+
+  pub fn node(&self, id: NodeId) -> Option<&Node> {
+    self.get(id).node.as_ref()
+  }
+
+  pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+    self.get_mut(id).node.as_mut()
+  }
+
+  // World matrix as of the last update or draw
+  pub fn world_matrix(&self, id: NodeId) -> Mat4 {
+    self.get(id).world
+  }
+
+  // Recomputes world matrices for dirty nodes and everything below them
+  pub fn update(&mut self) {
+    let mut stack: Vec<(NodeId, Mat4, bool)> = self.roots.iter().rev().map(|r| (*r, Mat4::IDENTITY, false)).collect();
+    while let Some((id, parent_world, parent_changed)) = stack.pop() {
+      let entry = self.get_mut(id);
+      let changed = parent_changed || entry.dirty;
+      if changed {
+        entry.world = parent_world * entry.local.matrix();
+        entry.dirty = false;
+      }
+      let world = entry.world;
+      stack.extend(entry.children.iter().rev().map(|c| (*c, world, changed)));
+    }
+  }
+
+  // Nodes in draw order: depth-first, skipping hidden nodes and everything
+  // below them
+  pub fn visible_nodes(&self) -> Vec<NodeId> {
+    let mut visible = Vec::new();
+    let mut stack: Vec<NodeId> = self.roots.iter().rev().cloned().collect();
+    while let Some(id) = stack.pop() {
+      let entry = self.get(id);
+      if entry.visible {
+        visible.push(id);
+        stack.extend(entry.children.iter().rev());
+      }
+    }
+    visible
+  }
+
+  // Updates transforms, then draws every visible node depth-first with its
+  // world matrix in the model uniform and its render state applied. GL
+  // state is left as the last node set it.
+  pub fn draw(&mut self) {
+    self.draw_nodes(None);
+  }
+
+  // Like draw, also uploading the camera uniforms once to each program the
+  // visible nodes use
+  pub fn draw_with_camera(&mut self, camera: &dyn Camera) {
+    self.draw_nodes(Some(camera));
+  }
+
+  fn draw_nodes(&mut self, camera: Option<&dyn Camera>) {
+    self.update();
+    let model_uniform = self.model_uniform.clone();
+    let mut camera_programs: Vec<*const Program> = Vec::new();
+    let mut previous_state: Option<RenderState> = None;
+    for id in self.visible_nodes() {
+      let entry = match self.nodes[id.index].as_mut() {
+        Some(entry) => entry,
+        None => continue,
+      };
+      let world = entry.world;
+      if let Some(node) = entry.node.as_mut() {
+        let program = node.program();
+        if let Some(camera) = camera {
+          if !camera_programs.contains(&Rc::as_ptr(program)) {
+            camera_programs.push(Rc::as_ptr(program));
+            self.camera_uniforms.apply(camera, program);
+          }
+        }
+        let state = node.render_state();
+        match previous_state {
+          Some(previous) if previous != state => state.apply_changes(&previous),
+          Some(_) => (),
+          None => state.apply(),
+        }
+        previous_state = Some(state);
+        program.make_current();
+        node.set_uniform(model_uniform.clone(), world.into());
+        node.draw();
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::FRAC_PI_2;
+
+  #[test]
+  fn removed_ids_do_not_match_reused_slots() {
+    let mut scene = Scene::new();
+    let parent = scene.add_empty();
+    let child = scene.add_empty_child(parent);
+    scene.remove(parent);
+    assert!(!scene.contains(parent));
+    assert!(!scene.contains(child));
+
+    let reused = scene.add_empty();
+    let reused_child = scene.add_empty_child(reused);
+    assert!(scene.contains(reused) && scene.contains(reused_child));
+    assert!(reused != parent && reused != child);
+    assert!(reused_child != parent && reused_child != child);
+    assert!(!scene.contains(parent));
+  }
+
+  #[test]
+  #[should_panic(expected = "does not exist")]
+  fn stale_ids_panic_on_access() {
+    let mut scene = Scene::new();
+    let old = scene.add_empty();
+    scene.remove(old);
+    scene.add_empty();
+    scene.set_translation(old, Vec3::new(1.0, 0.0, 0.0));
+  }
+
+  fn assert_point_eq(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+  }
+
+  fn origin(scene: &Scene, id: NodeId) -> Vec3 {
+    scene.world_matrix(id).transform_point(Vec3::ZERO)
+  }
+
+  #[test]
+  fn world_matrices_combine_parent_and_child_transforms() {
+    let mut scene = Scene::new();
+    let parent = scene.add_empty();
+    let child = scene.add_empty_child(parent);
+    let grandchild = scene.add_empty_child(child);
+    scene.set_transform(parent, Transform {
+      translation: Vec3::new(10.0, 0.0, 0.0),
+      rotation: Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), FRAC_PI_2),
+      scale: Vec3::new(2.0, 2.0, 2.0),
+    });
+    scene.set_translation(child, Vec3::new(1.0, 0.0, 0.0));
+    scene.set_translation(grandchild, Vec3::new(0.0, 1.0, 0.0));
+    scene.update();
+
+    assert_point_eq(origin(&scene, parent), Vec3::new(10.0, 0.0, 0.0));
+    // Scaled by 2 and rotated a quarter turn, +x becomes +y
+    assert_point_eq(origin(&scene, child), Vec3::new(10.0, 2.0, 0.0));
+    assert_point_eq(origin(&scene, grandchild), Vec3::new(8.0, 2.0, 0.0));
+    let expected = scene.world_matrix(parent) * scene.transform(child).matrix() * scene.transform(grandchild).matrix();
+    assert_eq!(scene.world_matrix(grandchild), expected);
+  }
+
+  #[test]
+  fn moving_an_ancestor_updates_its_descendants() {
+    let mut scene = Scene::new();
+    let root = scene.add_empty();
+    let middle = scene.add_empty_child(root);
+    let leaf = scene.add_empty_child(middle);
+    let sibling = scene.add_empty();
+    scene.set_translation(leaf, Vec3::new(0.0, 0.0, 1.0));
+    scene.set_translation(sibling, Vec3::new(5.0, 0.0, 0.0));
+    scene.update();
+    assert_point_eq(origin(&scene, leaf), Vec3::new(0.0, 0.0, 1.0));
+
+    scene.set_translation(root, Vec3::new(0.0, 3.0, 0.0));
+    // Nothing is recomputed until the next update
+    assert_point_eq(origin(&scene, leaf), Vec3::new(0.0, 0.0, 1.0));
+    scene.update();
+    assert_point_eq(origin(&scene, middle), Vec3::new(0.0, 3.0, 0.0));
+    assert_point_eq(origin(&scene, leaf), Vec3::new(0.0, 3.0, 1.0));
+    assert_point_eq(origin(&scene, sibling), Vec3::new(5.0, 0.0, 0.0));
+  }
+
+  #[test]
+  fn reparenting_keeps_the_local_transform() {
+    let mut scene = Scene::new();
+    let a = scene.add_empty();
+    let b = scene.add_empty();
+    let child = scene.add_empty_child(a);
+    scene.set_translation(a, Vec3::new(1.0, 0.0, 0.0));
+    scene.set_translation(b, Vec3::new(0.0, 0.0, -4.0));
+    scene.set_translation(child, Vec3::new(0.0, 2.0, 0.0));
+    scene.update();
+    assert_point_eq(origin(&scene, child), Vec3::new(1.0, 2.0, 0.0));
+
+    scene.set_parent(child, Some(b));
+    scene.update();
+    assert_eq!(scene.transform(child).translation, Vec3::new(0.0, 2.0, 0.0));
+    assert_point_eq(origin(&scene, child), Vec3::new(0.0, 2.0, -4.0));
+    assert_eq!(scene.parent(child), Some(b));
+    assert!(scene.children(a).is_empty());
+
+    scene.set_parent(child, None);
+    scene.update();
+    assert_point_eq(origin(&scene, child), Vec3::new(0.0, 2.0, 0.0));
+    assert_eq!(scene.roots(), &[a, b, child]);
+  }
+
+  #[test]
+  fn hidden_subtrees_are_skipped() {
+    let mut scene = Scene::new();
+    let shown = scene.add_empty();
+    let hidden = scene.add_empty();
+    let shown_child = scene.add_empty_child(shown);
+    let hidden_child = scene.add_empty_child(hidden);
+    let hidden_grandchild = scene.add_empty_child(hidden_child);
+    scene.set_visible(hidden, false);
+    assert_eq!(scene.visible_nodes(), vec![shown, shown_child]);
+    // Children keep their own flag, so they reappear with their parent
+    assert!(scene.is_visible(hidden_child));
+
+    scene.set_visible(hidden, true);
+    scene.set_visible(hidden_child, false);
+    assert_eq!(scene.visible_nodes(), vec![shown, shown_child, hidden]);
+    assert!(!scene.visible_nodes().contains(&hidden_grandchild));
+  }
+}
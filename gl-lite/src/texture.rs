@@ -1,10 +1,123 @@
+use std::mem;
+use super::format::{TexelData, TextureFormat};
 use super::gli;
+use super::sampler::Sampler;
 use super::uniforms::UniformValue;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterMode {
+  Nearest,
+  Linear,
+  NearestMipmapNearest,
+  LinearMipmapNearest,
+  NearestMipmapLinear,
+  LinearMipmapLinear,
+}
+
+impl FilterMode {
+  pub fn gl_filter(self) -> u32 {
+    match self {
+      FilterMode::Nearest => gli::NEAREST,
+      FilterMode::Linear => gli::LINEAR,
+      FilterMode::NearestMipmapNearest => gli::NEAREST_MIPMAP_NEAREST,
+      FilterMode::LinearMipmapNearest => gli::LINEAR_MIPMAP_NEAREST,
+      FilterMode::NearestMipmapLinear => gli::NEAREST_MIPMAP_LINEAR,
+      FilterMode::LinearMipmapLinear => gli::LINEAR_MIPMAP_LINEAR,
+    }
+  }
+
+  pub fn uses_mipmaps(self) -> bool {
+    !matches!(self, FilterMode::Nearest | FilterMode::Linear)
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WrapMode {
+  ClampToEdge,
+  ClampToBorder,
+  Repeat,
+  MirroredRepeat,
+}
+
+impl WrapMode {
+  pub fn gl_wrap(self) -> u32 {
+    match self {
+      WrapMode::ClampToEdge => gli::CLAMP_TO_EDGE,
+      WrapMode::ClampToBorder => gli::CLAMP_TO_BORDER,
+      WrapMode::Repeat => gli::REPEAT,
+      WrapMode::MirroredRepeat => gli::MIRRORED_REPEAT,
+    }
+  }
+}
+
+// Describes how client pixel data is laid out for region updates, mirroring
+// the GL_UNPACK_* pixel store parameters. A row_length of 0 means rows are
+// as wide as the region being uploaded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnpackLayout {
+  pub alignment: i32,
+  pub row_length: i32,
+  pub skip_pixels: i32,
+  pub skip_rows: i32,
+}
+
+impl Default for UnpackLayout {
+  fn default() -> UnpackLayout {
+    UnpackLayout {
+      alignment: 4,
+      row_length: 0,
+      skip_pixels: 0,
+      skip_rows: 0,
+    }
+  }
+}
+
+impl UnpackLayout {
+  pub fn tightly_packed() -> UnpackLayout {
+    UnpackLayout {
+      alignment: 1,
+      ..UnpackLayout::default()
+    }
+  }
+
+  // The minimum number of bytes a source must contain to supply a region
+  pub fn required_bytes(&self, width: i32, height: i32, bytes_per_pixel: usize) -> usize {
+    let row_pixels = if self.row_length > 0 { self.row_length } else { width } as usize;
+    let alignment = self.alignment.max(1) as usize;
+    let row_bytes = (row_pixels * bytes_per_pixel).div_ceil(alignment) * alignment;
+    let rows = (self.skip_rows + height - 1).max(0) as usize;
+    rows * row_bytes + (self.skip_pixels + width) as usize * bytes_per_pixel
+  }
+
+  pub(crate) fn apply(&self) {
+    gli::pixel_store_unpack(self.alignment, self.row_length, self.skip_pixels, self.skip_rows);
+  }
+}
+
 pub struct Texture {
   gl_texture: u32,
   width: i32,
   height: i32,
+  internal_format: u32,
+  format: u32,
+  pixel_type: u32,
+  texture_format: Option<TextureFormat>,
+  mip_levels: i32,
+  unpack: UnpackLayout,
+  wrap_s: WrapMode,
+  wrap_t: WrapMode,
+  min_filter: FilterMode,
+  mag_filter: FilterMode,
+  lod_bias: f32,
+  lod_range: (f32, f32),
+  anisotropy: f32,
+  border_color: [f32; 4],
+}
+
+impl Default for Texture {
+  fn default() -> Texture {
+    Texture::new()
+  }
 }
 
 impl Texture {
@@ -21,32 +134,349 @@ impl Texture {
       gl_texture: tex,
       width: 1,
       height: 1,
+      internal_format: gli::RGBA,
+      format: gli::RGBA,
+      pixel_type: gli::UNSIGNED_BYTE,
+      texture_format: None,
+      mip_levels: 1,
+      unpack: UnpackLayout::default(),
+      wrap_s: WrapMode::ClampToEdge,
+      wrap_t: WrapMode::ClampToEdge,
+      min_filter: FilterMode::Linear,
+      mag_filter: FilterMode::Linear,
+      lod_bias: 0.0,
+      lod_range: (-1000.0, 1000.0),
+      anisotropy: 1.0,
+      border_color: [0.0, 0.0, 0.0, 0.0],
+    }
+  }
+
+  pub fn gl_texture(&self) -> u32 {
+    self.gl_texture
+  }
+
+  pub fn width(&self) -> i32 {
+    self.width
+  }
+
+  pub fn height(&self) -> i32 {
+    self.height
+  }
+
+  pub fn internal_format(&self) -> u32 {
+    self.internal_format
+  }
+
+  pub fn format(&self) -> u32 {
+    self.format
+  }
+
+  pub fn pixel_type(&self) -> u32 {
+    self.pixel_type
+  }
+
+  // The typed format the texture was created with, if it was created
+  // through set_data or allocate
+  pub fn texture_format(&self) -> Option<TextureFormat> {
+    self.texture_format
+  }
+
+  pub fn mip_levels(&self) -> i32 {
+    self.mip_levels
+  }
+
+  pub fn unpack_layout(&self) -> UnpackLayout {
+    self.unpack
+  }
+
+  pub fn set_unpack_layout(&mut self, layout: UnpackLayout) {
+    self.unpack = layout;
+  }
+
+  pub fn wrap_mode(&self) -> (WrapMode, WrapMode) {
+    (self.wrap_s, self.wrap_t)
+  }
+
+  pub fn filter_mode(&self) -> (FilterMode, FilterMode) {
+    (self.min_filter, self.mag_filter)
+  }
+
+  pub fn lod_bias(&self) -> f32 {
+    self.lod_bias
+  }
+
+  pub fn lod_range(&self) -> (f32, f32) {
+    self.lod_range
+  }
+
+  pub fn anisotropy(&self) -> f32 {
+    self.anisotropy
+  }
+
+  pub fn border_color(&self) -> [f32; 4] {
+    self.border_color
+  }
+
+  pub fn set_wrap_mode(&mut self, s: WrapMode, t: WrapMode) {
+    gli::bind_texture_2d(self.gl_texture);
+    gli::tex_parameter_2d(gli::TEXTURE_WRAP_S, s.gl_wrap());
+    gli::tex_parameter_2d(gli::TEXTURE_WRAP_T, t.gl_wrap());
+    self.wrap_s = s;
+    self.wrap_t = t;
+  }
+
+  pub fn set_filter_mode(&mut self, min: FilterMode, mag: FilterMode) {
+    if mag.uses_mipmaps() {
+      panic!("Magnification filter cannot use mipmaps, got {:?}", mag);
     }
+    gli::bind_texture_2d(self.gl_texture);
+    gli::tex_parameter_2d(gli::TEXTURE_MIN_FILTER, min.gl_filter());
+    gli::tex_parameter_2d(gli::TEXTURE_MAG_FILTER, mag.gl_filter());
+    self.min_filter = min;
+    self.mag_filter = mag;
   }
 
-  pub fn set_wrap_mode(&self, s: u32, t: u32) {
+  pub fn set_lod_bias(&mut self, bias: f32) {
     gli::bind_texture_2d(self.gl_texture);
-    gli::tex_parameter_2d(gli::TEXTURE_WRAP_S, s);
-    gli::tex_parameter_2d(gli::TEXTURE_WRAP_T, t);
+    gli::tex_parameter_2d_f(gli::TEXTURE_LOD_BIAS, bias);
+    self.lod_bias = bias;
+  }
+
+  pub fn set_lod_range(&mut self, min: f32, max: f32) {
+    gli::bind_texture_2d(self.gl_texture);
+    gli::tex_parameter_2d_f(gli::TEXTURE_MIN_LOD, min);
+    gli::tex_parameter_2d_f(gli::TEXTURE_MAX_LOD, max);
+    self.lod_range = (min, max);
+  }
+
+  // Clamps to the context's maximum, returning false when anisotropic
+  // filtering is unavailable
+  pub fn set_anisotropy(&mut self, level: f32) -> bool {
+    match gli::max_texture_anisotropy() {
+      Some(max) => {
+        let level = level.max(1.0).min(max);
+        gli::bind_texture_2d(self.gl_texture);
+        gli::tex_parameter_2d_f(gli::TEXTURE_MAX_ANISOTROPY, level);
+        self.anisotropy = level;
+        true
+      },
+      None => false,
+    }
   }
 
-  pub fn set_filter_mode(&self, min: u32, mag: u32) {
+  pub fn set_border_color(&mut self, color: [f32; 4]) {
     gli::bind_texture_2d(self.gl_texture);
-    gli::tex_parameter_2d(gli::TEXTURE_MIN_FILTER, min);
-    gli::tex_parameter_2d(gli::TEXTURE_MAG_FILTER, mag);
+    gli::tex_parameter_2d_fv(gli::TEXTURE_BORDER_COLOR, &color);
+    self.border_color = color;
   }
 
   pub fn as_uniform_value(&self) -> UniformValue {
     UniformValue::Texture2D(self.gl_texture)
   }
 
-  pub fn set_from_bytes(&self, internal: u32, width: i32, height: i32, format: u32, data: &[u8]) {
+  pub fn with_sampler(&self, sampler: &Sampler) -> UniformValue {
+    UniformValue::SampledTexture2D(self.gl_texture, sampler.gl_sampler())
+  }
+
+  pub fn set_from_bytes(&mut self, internal: u32, width: i32, height: i32, format: u32, data: &[u8]) {
+    if width < 1 || height < 1 {
+      panic!("Cannot create a texture of size {}x{}", width, height);
+    }
+    let expected = width as usize * height as usize * bytes_per_pixel(format);
+    if data.len() != expected {
+      panic!("Expected {} bytes for a {}x{} texture, got {}", expected, width, height, data.len());
+    }
     gli::bind_texture_2d(self.gl_texture);
+    UnpackLayout::tightly_packed().apply();
     gli::tex_image_2d_from_bytes(internal, width, height, format, data);
+    UnpackLayout::default().apply();
+    self.width = width;
+    self.height = height;
+    self.internal_format = internal;
+    self.format = format;
+    self.pixel_type = gli::UNSIGNED_BYTE;
+    self.texture_format = None;
+    self.mip_levels = 1;
+  }
+
+  pub fn set_data<T: TexelData>(&mut self, format: TextureFormat, width: i32, height: i32, data: &[T]) {
+    let pixel_type = match format.upload_type(T::GL_TYPE) {
+      Some(t) => t,
+      None => panic!("Cannot upload data of type {:#x} to a texture of format {:?}", T::GL_TYPE, format),
+    };
+    if width < 1 || height < 1 {
+      panic!("Cannot create a texture of size {}x{}", width, height);
+    }
+    let expected = width as usize * height as usize * format.components();
+    if data.len() != expected {
+      panic!("Expected {} values for a {}x{} {:?} texture, got {}", expected, width, height, format, data.len());
+    }
+    gli::bind_texture_2d(self.gl_texture);
+    UnpackLayout::tightly_packed().apply();
+    gli::tex_image_2d(0, format.internal_format(), width, height, format.format(), pixel_type, Some(data));
+    UnpackLayout::default().apply();
+    self.set_format_state(format, width, height);
+  }
+
+  // Allocates uninitialized storage, typically for use as a render target
+  pub fn allocate(&mut self, format: TextureFormat, width: i32, height: i32) {
+    if width < 1 || height < 1 {
+      panic!("Cannot create a texture of size {}x{}", width, height);
+    }
+    gli::bind_texture_2d(self.gl_texture);
+    gli::tex_image_2d::<u8>(0, format.internal_format(), width, height, format.format(), format.pixel_type(), None);
+    self.set_format_state(format, width, height);
+  }
+
+  fn set_format_state(&mut self, format: TextureFormat, width: i32, height: i32) {
+    self.width = width;
+    self.height = height;
+    self.internal_format = format.internal_format();
+    self.format = format.format();
+    self.pixel_type = format.pixel_type();
+    self.texture_format = Some(format);
+    self.mip_levels = 1;
+  }
+
+  fn bytes_per_pixel(&self) -> usize {
+    match self.texture_format {
+      Some(format) => format.bytes_per_pixel(),
+      None => bytes_per_pixel(self.format),
+    }
+  }
+
+  // Returns the GL type to upload `T` with and the size of one pixel of `T`
+  fn check_region<T: TexelData>(&self, x: i32, y: i32, width: i32, height: i32) -> (u32, usize) {
+    if x < 0 || y < 0 || width < 1 || height < 1 || x + width > self.width || y + height > self.height {
+      panic!("Region {}x{} at ({}, {}) is outside of the {}x{} texture", width, height, x, y, self.width, self.height);
+    }
+    let pixel_type = match self.texture_format {
+      Some(format) => format.upload_type(T::GL_TYPE),
+      None if T::GL_TYPE == self.pixel_type => Some(self.pixel_type),
+      None => None,
+    };
+    let pixel_type = match pixel_type {
+      Some(t) => t,
+      None => panic!("Cannot upload data of type {:#x} to a texture with pixel type {:#x}", T::GL_TYPE, self.pixel_type),
+    };
+    // Float16 textures accept f32 data, so the texture's own pixel size
+    // can't be used to size the upload
+    let components = match self.texture_format {
+      Some(format) => format.components(),
+      None => bytes_per_pixel(self.format),
+    };
+    (pixel_type, mem::size_of::<T>() * components)
+  }
+
+  // Checks a region update of `data_length` bytes, returning the pixel type
+  // to upload it with
+  pub(crate) fn check_region_upload<T: TexelData>(&self, x: i32, y: i32, width: i32, height: i32, data_length: usize) -> u32 {
+    let (pixel_type, pixel_size) = self.check_region::<T>(x, y, width, height);
+    let required = self.unpack.required_bytes(width, height, pixel_size);
+    if data_length < required {
+      panic!("Expected at least {} bytes for a {}x{} region, got {}", required, width, height, data_length);
+    }
+    pixel_type
+  }
+
+  // Replaces a sub-rectangle of the base level. `data` is read according to
+  // the texture's unpack layout, so it may be a window into a larger image.
+  pub fn update_region<T: TexelData>(&self, x: i32, y: i32, width: i32, height: i32, data: &[T]) {
+    let pixel_type = self.check_region_upload::<T>(x, y, width, height, mem::size_of_val(data));
+    gli::bind_texture_2d(self.gl_texture);
+    self.unpack.apply();
+    gli::tex_sub_image_2d(x, y, width, height, self.format, pixel_type, data);
+    UnpackLayout::default().apply();
+  }
+
+  // Uploads from the bound pixel unpack buffer, after check_region_upload
+  pub(crate) fn update_region_from_buffer(&self, x: i32, y: i32, width: i32, height: i32, pixel_type: u32) {
+    gli::bind_texture_2d(self.gl_texture);
+    self.unpack.apply();
+    gli::tex_sub_image_2d_from_buffer(x, y, width, height, self.format, pixel_type, 0);
+    UnpackLayout::default().apply();
+  }
+
+  pub fn set_mip_level_from_bytes(&mut self, level: i32, data: &[u8]) {
+    if level < 1 || level >= full_mip_count(self.width, self.height) {
+      panic!("Mip level {} is out of range for a {}x{} texture", level, self.width, self.height);
+    }
+    let width = (self.width >> level).max(1);
+    let height = (self.height >> level).max(1);
+    let expected = width as usize * height as usize * self.bytes_per_pixel();
+    if data.len() != expected {
+      panic!("Expected {} bytes for mip level {} ({}x{}), got {}", expected, level, width, height, data.len());
+    }
+    gli::bind_texture_2d(self.gl_texture);
+    UnpackLayout::tightly_packed().apply();
+    gli::tex_image_2d(level, self.internal_format, width, height, self.format, self.pixel_type, Some(data));
+    UnpackLayout::default().apply();
+    self.mip_levels = self.mip_levels.max(level + 1);
+  }
+
+  pub(crate) fn set_compressed_level(&mut self, level: i32, internal: u32, width: i32, height: i32, data: &[u8]) {
+    gli::bind_texture_2d(self.gl_texture);
+    gli::compressed_tex_image_2d(gli::TEXTURE_2D, level, internal, width, height, data);
+    if level == 0 {
+      self.width = width;
+      self.height = height;
+      self.internal_format = internal;
+      self.format = internal;
+      self.pixel_type = 0;
+      self.texture_format = None;
+      self.mip_levels = 1;
+    } else {
+      self.mip_levels = self.mip_levels.max(level + 1);
+    }
+  }
+
+  // Copies `source`'s base level into the top-left corner of this texture
+  pub(crate) fn copy_from(&self, source: &Texture) {
+    if source.width > self.width || source.height > self.height {
+      panic!("Cannot copy a {}x{} texture into a {}x{} texture", source.width, source.height, self.width, self.height);
+    }
+    gli::copy_texture_2d(source.gl_texture, self.gl_texture, source.width, source.height);
+  }
+
+  // Reads the base level back as unsigned bytes, bottom row first
+  pub fn read_pixels(&self, format: u32) -> Vec<u8> {
+    gli::bind_texture_2d(self.gl_texture);
+    gli::get_tex_image_2d(self.width, self.height, format)
+  }
+
+  pub fn generate_mipmaps(&mut self) {
+    gli::bind_texture_2d(self.gl_texture);
+    gli::generate_mipmap_2d();
+    self.mip_levels = full_mip_count(self.width, self.height);
   }
 
   pub fn bind_to_slot(&self, slot: u32) {
     gli::active_texture(slot);
     gli::bind_texture_2d(self.gl_texture);
   }
+
+  pub fn bind_to_image_unit(&self, unit: u32, access: u32, format: u32) {
+    gli::bind_image_texture(unit, self.gl_texture, 0, access, format);
+  }
+}
+
+impl Drop for Texture {
+  fn drop(&mut self) {
+    gli::delete_texture(self.gl_texture);
+  }
+}
+
+pub(crate) fn full_mip_count(width: i32, height: i32) -> i32 {
+  32 - (width.max(height) as u32).leading_zeros() as i32
+}
+
+// Size of a pixel of `format` with one byte per component
+pub(crate) fn bytes_per_pixel(format: u32) -> usize {
+  match format {
+    gli::RED | gli::RED_INTEGER | gli::DEPTH_COMPONENT | gli::STENCIL_INDEX => 1,
+    gli::RG | gli::RG_INTEGER => 2,
+    gli::RGB | gli::BGR | gli::RGB_INTEGER | gli::BGR_INTEGER => 3,
+    gli::RGBA | gli::BGRA | gli::RGBA_INTEGER | gli::BGRA_INTEGER => 4,
+    _ => panic!("Pixel format {:#x} cannot be uploaded from unsigned bytes", format),
+  }
 }
@@ -0,0 +1,121 @@
+use super::gli;
+use super::texture::{FilterMode, UnpackLayout, WrapMode, bytes_per_pixel, full_mip_count};
+use super::uniforms::UniformValue;
+
+pub struct Texture3D {
+  gl_texture: u32,
+  width: i32,
+  height: i32,
+  depth: i32,
+  internal_format: u32,
+  format: u32,
+  mip_levels: i32,
+}
+
+impl Default for Texture3D {
+  fn default() -> Texture3D {
+    Texture3D::new()
+  }
+}
+
+impl Texture3D {
+  pub fn new() -> Texture3D {
+    let tex = gli::create_texture();
+    gli::bind_texture(gli::TEXTURE_3D, tex);
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_WRAP_S, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_WRAP_T, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_WRAP_R, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_MIN_FILTER, gli::LINEAR);
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_MAG_FILTER, gli::LINEAR);
+    Texture3D {
+      gl_texture: tex,
+      width: 0,
+      height: 0,
+      depth: 0,
+      internal_format: gli::RGBA,
+      format: gli::RGBA,
+      mip_levels: 1,
+    }
+  }
+
+  pub fn width(&self) -> i32 {
+    self.width
+  }
+
+  pub fn height(&self) -> i32 {
+    self.height
+  }
+
+  pub fn depth(&self) -> i32 {
+    self.depth
+  }
+
+  pub fn internal_format(&self) -> u32 {
+    self.internal_format
+  }
+
+  pub fn format(&self) -> u32 {
+    self.format
+  }
+
+  pub fn mip_levels(&self) -> i32 {
+    self.mip_levels
+  }
+
+  pub fn set_wrap_mode(&self, s: WrapMode, t: WrapMode, r: WrapMode) {
+    gli::bind_texture(gli::TEXTURE_3D, self.gl_texture);
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_WRAP_S, s.gl_wrap());
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_WRAP_T, t.gl_wrap());
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_WRAP_R, r.gl_wrap());
+  }
+
+  pub fn set_filter_mode(&self, min: FilterMode, mag: FilterMode) {
+    if mag.uses_mipmaps() {
+      panic!("Magnification filter cannot use mipmaps, got {:?}", mag);
+    }
+    gli::bind_texture(gli::TEXTURE_3D, self.gl_texture);
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_MIN_FILTER, min.gl_filter());
+    gli::tex_parameter(gli::TEXTURE_3D, gli::TEXTURE_MAG_FILTER, mag.gl_filter());
+  }
+
+  pub fn set_from_bytes(&mut self, internal: u32, width: i32, height: i32, depth: i32, format: u32, data: &[u8]) {
+    if width < 1 || height < 1 || depth < 1 {
+      panic!("Cannot create a 3D texture of size {}x{}x{}", width, height, depth);
+    }
+    let expected = width as usize * height as usize * depth as usize * bytes_per_pixel(format);
+    if data.len() != expected {
+      panic!("Expected {} bytes for a {}x{}x{} texture, got {}", expected, width, height, depth, data.len());
+    }
+    gli::bind_texture(gli::TEXTURE_3D, self.gl_texture);
+    UnpackLayout::tightly_packed().apply();
+    gli::tex_image_3d_from_bytes(gli::TEXTURE_3D, internal, width, height, depth, format, data);
+    UnpackLayout::default().apply();
+    self.width = width;
+    self.height = height;
+    self.depth = depth;
+    self.internal_format = internal;
+    self.format = format;
+    self.mip_levels = 1;
+  }
+
+  pub fn generate_mipmaps(&mut self) {
+    gli::bind_texture(gli::TEXTURE_3D, self.gl_texture);
+    gli::generate_mipmap(gli::TEXTURE_3D);
+    self.mip_levels = full_mip_count(self.width.max(self.height), self.depth);
+  }
+
+  pub fn as_uniform_value(&self) -> UniformValue {
+    UniformValue::Texture3D(self.gl_texture)
+  }
+
+  pub fn bind_to_slot(&self, slot: u32) {
+    gli::active_texture(slot);
+    gli::bind_texture(gli::TEXTURE_3D, self.gl_texture);
+  }
+}
+
+impl Drop for Texture3D {
+  fn drop(&mut self) {
+    gli::delete_texture(self.gl_texture);
+  }
+}
@@ -0,0 +1,143 @@
+use super::gli;
+use super::texture::{FilterMode, UnpackLayout, WrapMode, bytes_per_pixel, full_mip_count};
+use super::uniforms::UniformValue;
+
+pub struct TextureArray {
+  gl_texture: u32,
+  width: i32,
+  height: i32,
+  layers: i32,
+  internal_format: u32,
+  format: u32,
+  mip_levels: i32,
+}
+
+impl Default for TextureArray {
+  fn default() -> TextureArray {
+    TextureArray::new()
+  }
+}
+
+impl TextureArray {
+  pub fn new() -> TextureArray {
+    let tex = gli::create_texture();
+    gli::bind_texture(gli::TEXTURE_2D_ARRAY, tex);
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_WRAP_S, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_WRAP_T, gli::CLAMP_TO_EDGE);
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_MIN_FILTER, gli::LINEAR);
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_MAG_FILTER, gli::LINEAR);
+    TextureArray {
+      gl_texture: tex,
+      width: 0,
+      height: 0,
+      layers: 0,
+      internal_format: gli::RGBA,
+      format: gli::RGBA,
+      mip_levels: 1,
+    }
+  }
+
+  pub fn width(&self) -> i32 {
+    self.width
+  }
+
+  pub fn height(&self) -> i32 {
+    self.height
+  }
+
+  pub fn layers(&self) -> i32 {
+    self.layers
+  }
+
+  pub fn internal_format(&self) -> u32 {
+    self.internal_format
+  }
+
+  pub fn format(&self) -> u32 {
+    self.format
+  }
+
+  pub fn mip_levels(&self) -> i32 {
+    self.mip_levels
+  }
+
+  pub fn set_wrap_mode(&self, s: WrapMode, t: WrapMode) {
+    gli::bind_texture(gli::TEXTURE_2D_ARRAY, self.gl_texture);
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_WRAP_S, s.gl_wrap());
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_WRAP_T, t.gl_wrap());
+  }
+
+  pub fn set_filter_mode(&self, min: FilterMode, mag: FilterMode) {
+    if mag.uses_mipmaps() {
+      panic!("Magnification filter cannot use mipmaps, got {:?}", mag);
+    }
+    gli::bind_texture(gli::TEXTURE_2D_ARRAY, self.gl_texture);
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_MIN_FILTER, min.gl_filter());
+    gli::tex_parameter(gli::TEXTURE_2D_ARRAY, gli::TEXTURE_MAG_FILTER, mag.gl_filter());
+  }
+
+  // Allocates storage for every layer, optionally filling it with `data`
+  // laid out one layer after another
+  pub fn allocate(&mut self, internal: u32, width: i32, height: i32, layers: i32, format: u32, data: Option<&[u8]>) {
+    if width < 1 || height < 1 || layers < 1 {
+      panic!("Cannot create a texture array of size {}x{} with {} layers", width, height, layers);
+    }
+    let expected = width as usize * height as usize * layers as usize * bytes_per_pixel(format);
+    let zeroed;
+    let bytes = match data {
+      Some(d) => d,
+      None => {
+        zeroed = vec![0; expected];
+        &zeroed[..]
+      },
+    };
+    if bytes.len() != expected {
+      panic!("Expected {} bytes for {} layers of {}x{}, got {}", expected, layers, width, height, bytes.len());
+    }
+    gli::bind_texture(gli::TEXTURE_2D_ARRAY, self.gl_texture);
+    UnpackLayout::tightly_packed().apply();
+    gli::tex_image_3d_from_bytes(gli::TEXTURE_2D_ARRAY, internal, width, height, layers, format, bytes);
+    UnpackLayout::default().apply();
+    self.width = width;
+    self.height = height;
+    self.layers = layers;
+    self.internal_format = internal;
+    self.format = format;
+    self.mip_levels = 1;
+  }
+
+  pub fn set_layer_from_bytes(&self, layer: i32, data: &[u8]) {
+    if layer < 0 || layer >= self.layers {
+      panic!("Layer {} is out of range for an array of {} layers", layer, self.layers);
+    }
+    let expected = self.width as usize * self.height as usize * bytes_per_pixel(self.format);
+    if data.len() != expected {
+      panic!("Expected {} bytes for a {}x{} layer, got {}", expected, self.width, self.height, data.len());
+    }
+    gli::bind_texture(gli::TEXTURE_2D_ARRAY, self.gl_texture);
+    UnpackLayout::tightly_packed().apply();
+    gli::tex_sub_image_layer_from_bytes(gli::TEXTURE_2D_ARRAY, layer, self.width, self.height, self.format, data);
+    UnpackLayout::default().apply();
+  }
+
+  pub fn generate_mipmaps(&mut self) {
+    gli::bind_texture(gli::TEXTURE_2D_ARRAY, self.gl_texture);
+    gli::generate_mipmap(gli::TEXTURE_2D_ARRAY);
+    self.mip_levels = full_mip_count(self.width, self.height);
+  }
+
+  pub fn as_uniform_value(&self) -> UniformValue {
+    UniformValue::Texture2DArray(self.gl_texture)
+  }
+
+  pub fn bind_to_slot(&self, slot: u32) {
+    gli::active_texture(slot);
+    gli::bind_texture(gli::TEXTURE_2D_ARRAY, self.gl_texture);
+  }
+}
+
+impl Drop for TextureArray {
+  fn drop(&mut self) {
+    gli::delete_texture(self.gl_texture);
+  }
+}
@@ -1,12 +1,37 @@
 use super::gli;
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum UniformValue {
   Float(f32),
   FloatVec2(f32, f32),
   FloatVec3(f32, f32, f32),
   FloatVec4(f32, f32, f32, f32),
+  // Column-major matrices
+  FloatMat3([f32; 9]),
+  FloatMat4([f32; 16]),
+  // Arrays, uploaded starting at the array's first element
+  FloatArray(Vec<f32>),
+  FloatMat4Array(Vec<[f32; 16]>),
 
   Texture2D(u32),
+  SampledTexture2D(u32, u32),
+  TextureCube(u32),
+  Texture3D(u32),
+  Texture2DArray(u32),
+}
+
+impl UniformValue {
+  // Returns the (target, texture, sampler) to bind for texture values
+  pub fn texture_binding(&self) -> Option<(u32, u32, u32)> {
+    match self {
+      UniformValue::Texture2D(t) => Some((gli::TEXTURE_2D, *t, 0)),
+      UniformValue::SampledTexture2D(t, s) => Some((gli::TEXTURE_2D, *t, *s)),
+      UniformValue::TextureCube(t) => Some((gli::TEXTURE_CUBE_MAP, *t, 0)),
+      UniformValue::Texture3D(t) => Some((gli::TEXTURE_3D, *t, 0)),
+      UniformValue::Texture2DArray(t) => Some((gli::TEXTURE_2D_ARRAY, *t, 0)),
+      _ => None,
+    }
+  }
 }
 
 pub fn set_value_for_uniform(location: u32, value: &UniformValue) {
@@ -15,6 +40,10 @@ pub fn set_value_for_uniform(location: u32, value: &UniformValue) {
     UniformValue::FloatVec2(x, y) => gli::uniform_2f(location, *x, *y),
     UniformValue::FloatVec3(x, y, z) => gli::uniform_3f(location, *x, *y, *z),
     UniformValue::FloatVec4(x, y, z, w) => gli::uniform_4f(location, *x, *y, *z, *w),
+    UniformValue::FloatMat3(m) => gli::uniform_matrix_3fv(location, m),
+    UniformValue::FloatMat4(m) => gli::uniform_matrix_4fv(location, m),
+    UniformValue::FloatArray(values) => gli::uniform_1fv(location, values),
+    UniformValue::FloatMat4Array(m) => gli::uniform_matrix_4fv_array(location, m.as_flattened()),
     _ => (),
   }
 }
@@ -4,6 +4,386 @@ extern {
 
 }
 
+pub const VERTEX_SHADER: u32 = 0x8B31;
+pub const FRAGMENT_SHADER: u32 = 0x8B30;
+pub const GEOMETRY_SHADER: u32 = 0x8DD9;
+pub const TESS_CONTROL_SHADER: u32 = 0x8E88;
+pub const TESS_EVALUATION_SHADER: u32 = 0x8E87;
+pub const COMPUTE_SHADER: u32 = 0x91B9;
+
+pub const STATIC_DRAW: u32 = 0x88E4;
+pub const DYNAMIC_DRAW: u32 = 0x88E8;
+pub const STREAM_DRAW: u32 = 0x88E0;
+
+pub const READ_ONLY: u32 = 0x88B8;
+pub const WRITE_ONLY: u32 = 0x88B9;
+pub const READ_WRITE: u32 = 0x88BA;
+
+pub const VERTEX_ATTRIB_ARRAY_BARRIER_BIT: u32 = 0x0001;
+pub const TEXTURE_FETCH_BARRIER_BIT: u32 = 0x0008;
+pub const SHADER_IMAGE_ACCESS_BARRIER_BIT: u32 = 0x0020;
+pub const BUFFER_UPDATE_BARRIER_BIT: u32 = 0x0200;
+pub const SHADER_STORAGE_BARRIER_BIT: u32 = 0x2000;
+pub const ALL_BARRIER_BITS: u32 = 0xFFFFFFFF;
+
+pub const BYTE: u32 = 0x1400;
+pub const UNSIGNED_BYTE: u32 = 0x1401;
+pub const SHORT: u32 = 0x1402;
+pub const UNSIGNED_SHORT: u32 = 0x1403;
+pub const INT: u32 = 0x1404;
+pub const UNSIGNED_INT: u32 = 0x1405;
+pub const HALF_FLOAT: u32 = 0x140B;
+pub const FLOAT: u32 = 0x1406;
+pub const UNSIGNED_INT_24_8: u32 = 0x84FA;
+pub const FLOAT_32_UNSIGNED_INT_24_8_REV: u32 = 0x8DAD;
+
+pub const R8: u32 = 0x8229;
+pub const RG8: u32 = 0x822B;
+pub const RGB8: u32 = 0x8051;
+pub const RGBA8: u32 = 0x8058;
+pub const SRGB8: u32 = 0x8C41;
+pub const SRGB8_ALPHA8: u32 = 0x8C43;
+pub const R16F: u32 = 0x822D;
+pub const RG16F: u32 = 0x822F;
+pub const RGB16F: u32 = 0x881B;
+pub const RGBA16F: u32 = 0x881A;
+pub const R32F: u32 = 0x822E;
+pub const RG32F: u32 = 0x8230;
+pub const RGB32F: u32 = 0x8815;
+pub const RGBA32F: u32 = 0x8814;
+
+pub const R8UI: u32 = 0x8232;
+pub const RG8UI: u32 = 0x8238;
+pub const RGB8UI: u32 = 0x8D7D;
+pub const RGBA8UI: u32 = 0x8D7C;
+pub const R16UI: u32 = 0x8234;
+pub const RG16UI: u32 = 0x823A;
+pub const RGB16UI: u32 = 0x8D77;
+pub const RGBA16UI: u32 = 0x8D76;
+pub const R32UI: u32 = 0x8236;
+pub const RG32UI: u32 = 0x823C;
+pub const RGB32UI: u32 = 0x8D71;
+pub const RGBA32UI: u32 = 0x8D70;
+pub const R8I: u32 = 0x8231;
+pub const RG8I: u32 = 0x8237;
+pub const RGB8I: u32 = 0x8D8F;
+pub const RGBA8I: u32 = 0x8D8E;
+pub const R16I: u32 = 0x8233;
+pub const RG16I: u32 = 0x8239;
+pub const RGB16I: u32 = 0x8D89;
+pub const RGBA16I: u32 = 0x8D88;
+pub const R32I: u32 = 0x8235;
+pub const RG32I: u32 = 0x823B;
+pub const RGB32I: u32 = 0x8D83;
+pub const RGBA32I: u32 = 0x8D82;
+
+pub const DEPTH_COMPONENT16: u32 = 0x81A5;
+pub const DEPTH_COMPONENT24: u32 = 0x81A6;
+pub const DEPTH_COMPONENT32F: u32 = 0x8CAC;
+pub const DEPTH24_STENCIL8: u32 = 0x88F0;
+pub const DEPTH32F_STENCIL8: u32 = 0x8CAD;
+
+pub const RED: u32 = 0x1903;
+pub const RG: u32 = 0x8227;
+pub const RGB: u32 = 0x1907;
+pub const RGBA: u32 = 0x1908;
+pub const RED_INTEGER: u32 = 0x8D94;
+pub const RG_INTEGER: u32 = 0x8228;
+pub const RGB_INTEGER: u32 = 0x8D98;
+pub const RGBA_INTEGER: u32 = 0x8D99;
+pub const DEPTH_COMPONENT: u32 = 0x1902;
+pub const DEPTH_STENCIL: u32 = 0x84F9;
+
+pub const COLOR_BUFFER_BIT: u32 = 0x4000;
+pub const DEPTH_BUFFER_BIT: u32 = 0x0100;
+pub const STENCIL_BUFFER_BIT: u32 = 0x0400;
+
+pub const COLOR_ATTACHMENT0: u32 = 0x8CE0;
+pub const DEPTH_ATTACHMENT: u32 = 0x8D00;
+pub const DEPTH_STENCIL_ATTACHMENT: u32 = 0x821A;
+
+pub const ZERO: u32 = 0;
+pub const ONE: u32 = 1;
+pub const SRC_ALPHA: u32 = 0x0302;
+pub const ONE_MINUS_SRC_ALPHA: u32 = 0x0303;
+pub const FRONT: u32 = 0x0404;
+pub const BACK: u32 = 0x0405;
+
+pub fn supports_shader_stage(shader_type: u32) -> bool {
+  match shader_type {
+    VERTEX_SHADER | FRAGMENT_SHADER => true,
+    _ => false,
+  }
+}
+
+pub fn supports_compute() -> bool {
+  false
+}
+
+pub fn renderer() -> String {
+  unsafe {
+    let raw = gl::GetString(gl::RENDERER);
+    if raw.is_null() {
+      String::new()
+    } else {
+      std::ffi::CStr::from_ptr(raw as *const std::os::raw::c_char).to_string_lossy().into_owned()
+    }
+  }
+}
+
+pub fn patch_vertices(_count: i32) {
+  panic!("Tessellation is not supported on WebGL");
+}
+
+pub fn draw_arrays_patches(_first: i32, _count: i32) {
+  panic!("Tessellation is not supported on WebGL");
+}
+
+pub fn draw_elements_patches(_count: i32, _index_type: u32, _base_vertex: i32) {
+  panic!("Tessellation is not supported on WebGL");
+}
+
+pub fn dispatch_compute(_x: u32, _y: u32, _z: u32) {
+  panic!("Compute shaders are not supported on WebGL");
+}
+
+pub fn memory_barrier(_barriers: u32) {
+  panic!("Compute shaders are not supported on WebGL");
+}
+
+pub fn bind_shader_storage_buffer(_buffer: u32) {
+  panic!("Shader storage buffers are not supported on WebGL");
+}
+
+pub fn bind_shader_storage_buffer_base(_index: u32, _buffer: u32) {
+  panic!("Shader storage buffers are not supported on WebGL");
+}
+
+pub fn shader_storage_buffer_data(_data: &[f32]) {
+  panic!("Shader storage buffers are not supported on WebGL");
+}
+
+pub fn get_shader_storage_buffer_data(_data: &mut [f32]) {
+  panic!("Shader storage buffers are not supported on WebGL");
+}
+
+pub fn bind_image_texture(_unit: u32, _texture: u32, _level: i32, _access: u32, _format: u32) {
+  panic!("Image load/store is not supported on WebGL");
+}
+
+pub fn supports_pixel_buffers() -> bool {
+  false
+}
+
+pub fn bind_pixel_unpack_buffer(_buffer: u32) {
+  panic!("Pixel buffer uploads are not supported on WebGL");
+}
+
+pub fn pixel_unpack_buffer_data<T>(_data: &[T]) {
+  panic!("Pixel buffer uploads are not supported on WebGL");
+}
+
+pub fn tex_sub_image_2d_from_buffer(_x: i32, _y: i32, _width: i32, _height: i32, _format: u32, _pixel_type: u32, _offset: usize) {
+  panic!("Pixel buffer uploads are not supported on WebGL");
+}
+
+pub fn read_pixels(_x: i32, _y: i32, _width: i32, _height: i32, _format: u32) -> Vec<u8> {
+  panic!("Pixel readback is not implemented for WebGL yet");
+}
+
+pub fn read_pixels_to_buffer(_x: i32, _y: i32, _width: i32, _height: i32, _format: u32) {
+  panic!("Pixel buffer readback is not supported on WebGL");
+}
+
+pub fn read_pixels_size(_width: i32, _height: i32, _format: u32) -> usize {
+  panic!("Pixel readback is not implemented for WebGL yet");
+}
+
+pub fn get_tex_image_2d(_width: i32, _height: i32, _format: u32) -> Vec<u8> {
+  panic!("Reading texture images is not supported on WebGL");
+}
+
+pub fn bind_pixel_pack_buffer(_buffer: u32) {
+  panic!("Pixel buffer readback is not supported on WebGL");
+}
+
+pub fn allocate_pixel_pack_buffer(_size: usize) {
+  panic!("Pixel buffer readback is not supported on WebGL");
+}
+
+pub fn get_pixel_pack_buffer_data(_data: &mut [u8]) {
+  panic!("Pixel buffer readback is not supported on WebGL");
+}
+
+pub fn clear(mask: u32) {
+  unsafe {
+    gl::Clear(mask);
+  }
+}
+
+pub fn viewport(x: i32, y: i32, width: i32, height: i32) {
+  unsafe {
+    gl::Viewport(x, y, width, height);
+  }
+}
+
+pub fn set_depth_test(_enabled: bool) {
+  panic!("Render state is not implemented for WebGL yet");
+}
+
+pub fn depth_mask(_write: bool) {
+  panic!("Render state is not implemented for WebGL yet");
+}
+
+pub fn set_blending(_enabled: bool) {
+  panic!("Render state is not implemented for WebGL yet");
+}
+
+pub fn blend_func(_src: u32, _dst: u32) {
+  panic!("Render state is not implemented for WebGL yet");
+}
+
+pub fn set_face_culling(_enabled: bool) {
+  panic!("Render state is not implemented for WebGL yet");
+}
+
+pub fn cull_face(_face: u32) {
+  panic!("Render state is not implemented for WebGL yet");
+}
+
+pub fn create_framebuffer() -> u32 {
+  let mut framebuffer = 0;
+  unsafe {
+    gl::GenFramebuffers(1, &mut framebuffer);
+  }
+  framebuffer
+}
+
+pub fn bind_framebuffer(framebuffer: u32) {
+  unsafe {
+    gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+  }
+}
+
+// The framebuffer draws currently go to
+pub fn bound_framebuffer() -> u32 {
+  let mut framebuffer: i32 = 0;
+  unsafe {
+    gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut framebuffer);
+  }
+  framebuffer as u32
+}
+
+pub fn bind_read_framebuffer(framebuffer: u32) {
+  unsafe {
+    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+  }
+}
+
+pub fn framebuffer_texture_2d(attachment: u32, texture: u32) {
+  unsafe {
+    gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture, 0);
+  }
+}
+
+pub fn framebuffer_renderbuffer(attachment: u32, renderbuffer: u32) {
+  unsafe {
+    gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, renderbuffer);
+  }
+}
+
+pub fn draw_buffers(count: usize) {
+  let attachments: Vec<u32> = (0..count as u32).map(|i| gl::COLOR_ATTACHMENT0 + i).collect();
+  unsafe {
+    gl::DrawBuffers(count as i32, attachments.as_ptr());
+  }
+}
+
+pub fn read_buffer(attachment: u32) {
+  unsafe {
+    gl::ReadBuffer(attachment);
+  }
+}
+
+// Clears one color attachment of the bound framebuffer, ignoring the clear
+// color state
+pub fn clear_color_attachment(index: usize, color: [f32; 4]) {
+  unsafe {
+    gl::ClearBufferfv(gl::COLOR, index as i32, color.as_ptr());
+  }
+}
+
+pub fn clear_depth_attachment(depth: f32) {
+  unsafe {
+    gl::ClearBufferfv(gl::DEPTH, 0, &depth);
+  }
+}
+
+pub fn framebuffer_complete() -> bool {
+  unsafe {
+    gl::CheckFramebufferStatus(gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE
+  }
+}
+
+pub fn create_renderbuffer() -> u32 {
+  let mut renderbuffer = 0;
+  unsafe {
+    gl::GenRenderbuffers(1, &mut renderbuffer);
+  }
+  renderbuffer
+}
+
+pub fn renderbuffer_storage(renderbuffer: u32, internal_format: u32, width: i32, height: i32) {
+  unsafe {
+    gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+    gl::RenderbufferStorage(gl::RENDERBUFFER, internal_format, width, height);
+    gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+  }
+}
+
+pub fn delete_framebuffer(framebuffer: u32) {
+  unsafe {
+    gl::DeleteFramebuffers(1, &framebuffer);
+  }
+}
+
+pub fn delete_renderbuffer(renderbuffer: u32) {
+  unsafe {
+    gl::DeleteRenderbuffers(1, &renderbuffer);
+  }
+}
+
+pub fn supports_sampler_objects() -> bool {
+  false
+}
+
+pub fn create_sampler() -> u32 {
+  panic!("Sampler objects are not implemented for WebGL yet");
+}
+
+pub fn bind_sampler(_unit: u32, _sampler: u32) {
+  panic!("Sampler objects are not implemented for WebGL yet");
+}
+
+pub fn delete_sampler(_sampler: u32) {
+  panic!("Sampler objects are not implemented for WebGL yet");
+}
+
+pub fn supports_copy_image() -> bool {
+  false
+}
+
+pub fn copy_texture_2d(_source: u32, _destination: u32, _width: i32, _height: i32) {
+  panic!("Texture copies are not supported on WebGL");
+}
+
+pub fn supports_compressed_format(_internal_format: u32) -> bool {
+  false
+}
+
+pub fn compressed_tex_image_2d(_target: u32, _level: i32, _internal_format: u32, _width: i32, _height: i32, _data: &[u8]) {
+  panic!("Compressed textures are not supported on WebGL");
+}
 
 pub fn create_shader(shader_type: u32) -> u32 {
   unsafe {
@@ -149,17 +529,36 @@ pub fn get_attribute_size_and_type(attr_type: u32, normalize: bool) -> (i32, u32
     gl::FLOAT_MAT2 => (4, t),
     gl::FLOAT_MAT3 => (9, t),
     gl::FLOAT_MAT4 => (16, t),
+    // Integer attributes are read as they are stored, never normalized
+    gl::INT => (1, gl::INT),
+    gl::INT_VEC2 => (2, gl::INT),
+    gl::INT_VEC3 => (3, gl::INT),
+    gl::INT_VEC4 => (4, gl::INT),
+    gl::UNSIGNED_INT => (1, gl::UNSIGNED_INT),
+    gl::UNSIGNED_INT_VEC2 => (2, gl::UNSIGNED_INT),
+    gl::UNSIGNED_INT_VEC3 => (3, gl::UNSIGNED_INT),
+    gl::UNSIGNED_INT_VEC4 => (4, gl::UNSIGNED_INT),
     _ => (1, t),
   }
 }
 
+// Whether a program attribute of this type needs vertex_attrib_i_pointer
+pub fn is_integer_attribute(attr_type: u32) -> bool {
+  matches!(get_attribute_size_and_type(attr_type, false).1, gl::INT | gl::UNSIGNED_INT)
+}
+
 pub fn size_of_type(gl_type: u32) -> i32 {
   match gl_type {
     gl::BYTE => 1,
     gl::UNSIGNED_BYTE => 1,
     gl::SHORT => 2,
     gl::UNSIGNED_SHORT => 2,
+    gl::HALF_FLOAT => 2,
+    gl::INT => 4,
+    gl::UNSIGNED_INT => 4,
+    gl::UNSIGNED_INT_24_8 => 4,
     gl::FLOAT => 4,
+    gl::FLOAT_32_UNSIGNED_INT_24_8_REV => 8,
     _ => 1,
   }
 }
@@ -172,6 +571,12 @@ pub fn create_vertex_array() -> u32 {
   }
 }
 
+pub fn delete_vertex_array(vao: u32) {
+  unsafe {
+    gl::DeleteVertexArrays(1, &vao);
+  }
+}
+
 pub fn bind_vertex_array(array: u32) {
   unsafe {
     gl::BindVertexArray(array);
@@ -186,6 +591,12 @@ pub fn create_buffer() -> u32 {
   }
 }
 
+pub fn delete_buffer(buffer: u32) {
+  unsafe {
+    gl::DeleteBuffers(1, &buffer);
+  }
+}
+
 pub fn bind_array_buffer(buffer: u32) {
   unsafe {
     gl::BindBuffer(gl::ARRAY_BUFFER, buffer);
@@ -198,34 +609,123 @@ pub fn bind_element_array_buffer(buffer: u32) {
   }
 }
 
-pub fn draw_arrays_triangles(count: i32) {
+pub fn draw_arrays_triangles(first: i32, count: i32) {
   unsafe {
-    gl::DrawArrays(gl::TRIANGLES, 0, count);
+    gl::DrawArrays(gl::TRIANGLES, first, count);
   }
 }
 
-pub fn draw_elements_triangles(count: i32) {
+pub fn draw_elements_triangles(count: i32, index_type: u32, base_vertex: i32) {
+  if base_vertex != 0 {
+    panic!("Base vertex draws are not supported on WebGL");
+  }
   unsafe {
-    gl::DrawElements(gl::TRIANGLES, count, gl::UNSIGNED_SHORT, ptr::null());
+    gl::DrawElements(gl::TRIANGLES, count, index_type, ptr::null());
   }
 }
 
-pub fn buffer_array_data(data: &[f32]) {
-  let float_size = mem::size_of::<GLfloat>();
+pub fn buffer_element_data_u16(data: &[u16], usage: u32) {
   unsafe {
     gl::BufferData(
+      gl::ELEMENT_ARRAY_BUFFER,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      usage
+    );
+  }
+}
+
+pub fn buffer_element_data_u32(data: &[u32], usage: u32) {
+  unsafe {
+    gl::BufferData(
+      gl::ELEMENT_ARRAY_BUFFER,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      usage
+    );
+  }
+}
+
+pub fn buffer_array_data<T>(data: &[T], usage: u32) {
+  unsafe {
+    gl::BufferData(
+      gl::ARRAY_BUFFER,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void,
+      usage
+    );
+  }
+}
+
+pub fn buffer_array_sub_data<T>(offset: i32, data: &[T]) {
+  unsafe {
+    gl::BufferSubData(
       gl::ARRAY_BUFFER,
-      (data.len() * float_size) as GLsizeiptr,
-      &data[0] as *const f32 as *const c_void,
-      gl::STATIC_DRAW
+      offset as GLintptr,
+      mem::size_of_val(data) as GLsizeiptr,
+      data.as_ptr() as *const c_void
     );
   }
 }
 
+pub fn orphan_array_buffer(size: i32, usage: u32) {
+  unsafe {
+    gl::BufferData(gl::ARRAY_BUFFER, size as GLsizeiptr, ptr::null(), usage);
+  }
+}
+
+pub fn supports_buffer_storage() -> bool {
+  false
+}
+
+pub struct MappedBuffer;
+
+impl MappedBuffer {
+  pub fn size(&self) -> usize {
+    0
+  }
+
+  pub fn write<T>(&self, _offset: usize, _data: &[T]) {
+    panic!("Persistent buffer mapping is not supported on WebGL");
+  }
+}
+
+pub fn map_persistent_array_buffer(_size: i32) -> MappedBuffer {
+  panic!("Persistent buffer mapping is not supported on WebGL");
+}
+
+pub struct Fence;
+
+pub fn fence_sync() -> Fence {
+  panic!("Persistent buffer mapping is not supported on WebGL");
+}
+
+pub fn wait_sync(_fence: &Fence) {
+  panic!("Persistent buffer mapping is not supported on WebGL");
+}
+
+pub fn poll_sync(_fence: &Fence) -> bool {
+  panic!("Fences are not supported on WebGL");
+}
+
+pub fn unmap_array_buffer() {
+  panic!("Persistent buffer mapping is not supported on WebGL");
+}
+
+pub fn delete_sync(_fence: Fence) {
+  panic!("Persistent buffer mapping is not supported on WebGL");
+}
+
 pub fn vertex_attrib_pointer(location: u32, size: i32, gl_type: u32, normalized: bool, stride: i32, offset: i32) {
   unsafe {
     let normalized_value = if normalized { gl::TRUE } else { gl::FALSE };
-    gl::VertexAttribPointer(location, size, gl_type, normalized_value, stride, ptr::null());
+    gl::VertexAttribPointer(location, size, gl_type, normalized_value, stride, offset as *const c_void);
+  }
+}
+
+pub fn vertex_attrib_i_pointer(location: u32, size: i32, gl_type: u32, stride: i32, offset: i32) {
+  unsafe {
+    gl::VertexAttribIPointer(location, size, gl_type, stride, offset as *const c_void);
   }
 }
 
@@ -257,4 +757,54 @@ pub fn uniform_4f(location: u32, x: f32, y: f32, z: f32, w: f32) {
   unsafe {
     gl::Uniform4f(location as i32, x, y, z, w);
   }
-}
\ No newline at end of file
+}
+
+pub fn delete_texture(texture: u32) {
+  unsafe {
+    gl::DeleteTextures(1, &texture);
+  }
+}
+
+pub fn pixel_store_unpack(alignment: i32, row_length: i32, skip_pixels: i32, skip_rows: i32) {
+  unsafe {
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, alignment);
+    gl::PixelStorei(gl::UNPACK_ROW_LENGTH, row_length);
+    gl::PixelStorei(gl::UNPACK_SKIP_PIXELS, skip_pixels);
+    gl::PixelStorei(gl::UNPACK_SKIP_ROWS, skip_rows);
+  }
+}
+
+pub fn tex_image_2d<T>(level: i32, internal_format: u32, width: i32, height: i32, format: u32, pixel_type: u32, data: Option<&[T]>) {
+  let pixels = match data {
+    Some(d) => d.as_ptr() as *const c_void,
+    None => std::ptr::null(),
+  };
+  unsafe {
+    gl::TexImage2D(gl::TEXTURE_2D, level, internal_format as i32, width, height, 0, format, pixel_type, pixels);
+  }
+}
+
+pub fn uniform_matrix_3fv(location: u32, values: &[f32; 9]) {
+  unsafe {
+    gl::UniformMatrix3fv(location as i32, 1, gl::FALSE, values.as_ptr());
+  }
+}
+
+pub fn uniform_matrix_4fv(location: u32, values: &[f32; 16]) {
+  unsafe {
+    gl::UniformMatrix4fv(location as i32, 1, gl::FALSE, values.as_ptr());
+  }
+}
+
+// Uploads consecutive column-major matrices to a mat4 array uniform
+pub fn uniform_matrix_4fv_array(location: u32, values: &[f32]) {
+  unsafe {
+    gl::UniformMatrix4fv(location as i32, (values.len() / 16) as i32, gl::FALSE, values.as_ptr());
+  }
+}
+
+pub fn uniform_1fv(location: u32, values: &[f32]) {
+  unsafe {
+    gl::Uniform1fv(location as i32, values.len() as i32, values.as_ptr());
+  }
+}
@@ -0,0 +1,31 @@
+#![cfg(any(target_os = "linux", feature = "app"))]
+
+use gllite::context::{HeadlessBackend, HeadlessContext};
+use gllite::gli;
+
+fn red_frame(context: &HeadlessContext) -> Vec<u8> {
+  context.make_current().expect("Failed to make the headless context current");
+  context.clear([1.0, 0.0, 0.0, 1.0]);
+  context.read_pixels().data[..4].to_vec()
+}
+
+#[test]
+fn dropping_one_context_keeps_others_working() {
+  let first = HeadlessContext::new(4, 4).expect("Context tests need a headless GL context");
+  let second = HeadlessContext::new(4, 4).expect("Context tests need a headless GL context");
+  assert_eq!(red_frame(&second), vec![255, 0, 0, 255]);
+  drop(second);
+  assert_eq!(red_frame(&first), vec![255, 0, 0, 255]);
+}
+
+#[test]
+fn software_contexts_never_use_the_gpu() {
+  let context = HeadlessContext::software(4, 4).expect("Context tests need a software GL context");
+  assert!(HeadlessBackend::software().contains(&context.backend()));
+  let renderer = gli::renderer();
+  assert!(
+    ["llvmpipe", "softpipe", "swrast"].iter().any(|name| renderer.contains(name)),
+    "Expected a software renderer, got {}", renderer
+  );
+  assert_eq!(red_frame(&context), vec![255, 0, 0, 255]);
+}
@@ -0,0 +1,88 @@
+#![cfg(all(feature = "gltf", any(target_os = "linux", feature = "app")))]
+
+use gllite::animation::MORPH_WEIGHTS_UNIFORM;
+use gllite::context::HeadlessContext;
+use gllite::gli;
+use gllite::gltf_loader::GltfData;
+use gllite::math::Vec3;
+use gllite::mesh::AttributeNames;
+use gllite::program::Program;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const VERTEX: &str = "#version 330
+in vec3 a_position;
+
+void main() {
+  gl_Position = vec4(a_position, 1);
+}";
+
+const FRAGMENT: &str = "#version 330
+out vec4 outColor;
+
+void main() {
+  outColor = vec4(1);
+}";
+
+// Node 0 is the root of the default scene, with node 1 drawing a single
+// primitive and node 2 drawing two. Node 4 only belongs to the other scene.
+fn hierarchy() -> String {
+  format!(r#"{{
+    "asset": {{ "version": "2.0" }},
+    "buffers": [{{ "byteLength": 36, "uri": "data:application/octet-stream;base64,{}" }}],
+    "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+    "accessors": [
+      {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [0, 0, 0] }}
+    ],
+    "meshes": [
+      {{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }},
+      {{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}, {{ "attributes": {{ "POSITION": 0 }} }}] }}
+    ],
+    "nodes": [
+      {{ "translation": [1, 0, 0], "children": [1, 2] }},
+      {{ "mesh": 0, "translation": [0, 2, 0] }},
+      {{ "mesh": 1, "scale": [2, 2, 2], "children": [3] }},
+      {{ "translation": [0, 0, 3] }},
+      {{ "mesh": 0 }}
+    ],
+    "scenes": [{{ "nodes": [4] }}, {{ "nodes": [0] }}],
+    "scene": 1
+  }}"#, "A".repeat(48))
+}
+
+#[test]
+fn create_scene_keeps_the_node_hierarchy() {
+  let context = HeadlessContext::new(4, 4).expect("glTF tests need a headless GL context");
+  context.make_current().expect("Failed to make the headless context current");
+  let mut program = Program::new();
+  program
+    .add_shader(VERTEX, gli::VERTEX_SHADER)
+    .add_shader(FRAGMENT, gli::FRAGMENT_SHADER)
+    .compile();
+  let program = Rc::new(program);
+
+  let data = GltfData::from_slice(hierarchy().as_bytes(), None).unwrap();
+  let result = data.create_scene(&program, &HashMap::new(), &AttributeNames::default(), MORPH_WEIGHTS_UNIFORM).unwrap();
+  let scene = &result.scene;
+  let node = |index: usize| result.nodes[index].expect("Node is missing from the scene");
+  assert_eq!(result.nodes[4], None);
+  assert_eq!(scene.roots(), &[node(0)]);
+  assert_eq!(scene.parent(node(1)), Some(node(0)));
+  assert_eq!(scene.parent(node(2)), Some(node(0)));
+  assert_eq!(scene.parent(node(3)), Some(node(2)));
+
+  // A single primitive is drawn by its node, several by extra children
+  assert!(result.drawables[0].is_empty());
+  assert_eq!(result.drawables[1], vec![node(1)]);
+  assert!(scene.node(node(1)).is_some());
+  assert_eq!(result.drawables[2].len(), 2);
+  assert!(scene.node(node(2)).is_none());
+  let mut children = result.drawables[2].clone();
+  children.push(node(3));
+  assert_eq!(scene.children(node(2)), children.as_slice());
+
+  // World matrices are up to date once the scene is built
+  let origin = |index: usize| scene.world_matrix(node(index)).transform_point(Vec3::ZERO);
+  assert_eq!(origin(1), Vec3::new(1.0, 2.0, 0.0));
+  assert_eq!(origin(3), Vec3::new(1.0, 0.0, 6.0));
+}
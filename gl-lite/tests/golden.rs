@@ -0,0 +1,65 @@
+#![cfg(all(feature = "image", any(target_os = "linux", feature = "app")))]
+
+use gllite::gli;
+use gllite::golden::{GoldenHarness, SKIP_ENV};
+use gllite::node::Node;
+use gllite::program::Program;
+use std::env;
+use std::rc::Rc;
+
+const VERTEX: &str = "#version 330
+in vec2 a_position;
+in vec3 a_color;
+out vec3 v_color;
+
+void main() {
+  v_color = a_color;
+  gl_Position = vec4(a_position, 0, 1);
+}";
+
+const FRAGMENT: &str = "#version 330
+in vec3 v_color;
+out vec4 outColor;
+
+void main() {
+  outColor = vec4(v_color, 1);
+}";
+
+fn harness() -> Option<GoldenHarness> {
+  let references = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+  let output = concat!(env!("CARGO_MANIFEST_DIR"), "/target/golden");
+  match GoldenHarness::new(64, 64, references, output) {
+    Ok(harness) => Some(harness),
+    Err(e) if env::var_os(SKIP_ENV).is_some() => {
+      eprintln!("Skipping golden image test: {}", e);
+      None
+    },
+    Err(e) => panic!("{} (set {} to skip golden image tests)", e, SKIP_ENV),
+  }
+}
+
+#[test]
+fn colored_triangle() {
+  let harness = match harness() {
+    Some(harness) => harness,
+    None => return,
+  };
+  let mut program = Program::new();
+  program
+    .add_shader(VERTEX, gli::VERTEX_SHADER)
+    .add_shader(FRAGMENT, gli::FRAGMENT_SHADER)
+    .compile();
+  let mut node = Node::for_program(Rc::new(program));
+  node.add_attribute(String::from("a_position"));
+  node.add_attribute(String::from("a_color"));
+  let vertices: [f32; 15] = [
+    0.0, 0.8, 1.0, 0.0, 0.0,
+    -0.8, -0.8, 0.0, 1.0, 0.0,
+    0.8, -0.8, 0.0, 0.0, 1.0,
+  ];
+  node.buffer_data(&vertices);
+
+  if let Err(e) = harness.check_node("colored_triangle", &mut node) {
+    panic!("{}", e);
+  }
+}
@@ -0,0 +1,93 @@
+#![cfg(any(target_os = "linux", feature = "app"))]
+
+use gllite::context::HeadlessContext;
+use gllite::gli;
+use gllite::mesh::{AttributeNames, MeshData, VertexAttribute};
+use gllite::program::Program;
+
+const VERTEX: &str = "#version 330
+in vec3 a_position;
+in vec4 a_color;
+in uvec4 a_joints;
+out vec4 v_color;
+flat out uvec4 v_joints;
+
+void main() {
+  v_color = a_color;
+  v_joints = a_joints;
+  gl_Position = vec4(a_position, 1);
+}";
+
+const FRAGMENT: &str = "#version 330
+in vec4 v_color;
+flat in uvec4 v_joints;
+out vec4 outColor;
+
+void main() {
+  outColor = vec4(v_color.rgb, float(v_joints.x + v_joints.w) / 255.0);
+}";
+
+#[test]
+fn typed_attributes_reach_the_shader() {
+  let context = HeadlessContext::new(4, 4).expect("Mesh tests need a headless GL context");
+  context.make_current().expect("Failed to make the headless context current");
+  let mut program = Program::new();
+  program
+    .add_shader(VERTEX, gli::VERTEX_SHADER)
+    .add_shader(FRAGMENT, gli::FRAGMENT_SHADER)
+    .compile();
+
+  // One triangle covering the whole viewport
+  let mut data = MeshData::new();
+  data.positions = vec![[-1.0, -1.0, 0.0], [3.0, -1.0, 0.0], [-1.0, 3.0, 0.0]];
+  data.indices = vec![0, 1, 2];
+  data.attributes.push(VertexAttribute {
+    component_type: gli::UNSIGNED_BYTE,
+    normalized: true,
+    ..VertexAttribute::new("a_color", 3, [1.0, 0.0, 128.0 / 255.0].repeat(3))
+  });
+  // Joint indices as u16, read by the uvec4 without conversion
+  data.attributes.push(VertexAttribute {
+    component_type: gli::UNSIGNED_SHORT,
+    ..VertexAttribute::new("a_joints", 4, [180.0, 1.0, 2.0, 20.0].repeat(3))
+  });
+  let mut geometry = data.to_geometry(&program, &AttributeNames::default());
+
+  context.framebuffer().bind();
+  gli::clear_color(0.0, 0.0, 0.0, 0.0);
+  gli::clear(gli::COLOR_BUFFER_BIT);
+  program.make_current();
+  geometry.draw();
+  let pixels = context.read_pixels();
+  assert_eq!(&pixels.data[..4], &[255, 0, 128, 200]);
+}
+
+#[test]
+fn dropping_geometry_frees_its_vertex_array_and_index_buffer() {
+  let context = HeadlessContext::new(4, 4).expect("Mesh tests need a headless GL context");
+  context.make_current().expect("Failed to make the headless context current");
+  let mut program = Program::new();
+  program
+    .add_shader(VERTEX, gli::VERTEX_SHADER)
+    .add_shader(FRAGMENT, gli::FRAGMENT_SHADER)
+    .compile();
+  let mut data = MeshData::new();
+  data.positions = vec![[-1.0, -1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, 1.0, 0.0]];
+  data.indices = vec![0, 1, 2];
+  let geometry = data.to_geometry(&program, &AttributeNames::default());
+
+  let vao = geometry.vertex_array();
+  let mut index = 0;
+  unsafe {
+    gl::BindVertexArray(vao);
+    gl::GetIntegerv(gl::ELEMENT_ARRAY_BUFFER_BINDING, &mut index);
+    gl::BindVertexArray(0);
+    assert_eq!(gl::IsVertexArray(vao), gl::TRUE);
+    assert_eq!(gl::IsBuffer(index as u32), gl::TRUE);
+  }
+  drop(geometry);
+  unsafe {
+    assert_eq!(gl::IsVertexArray(vao), gl::FALSE);
+    assert_eq!(gl::IsBuffer(index as u32), gl::FALSE);
+  }
+}
@@ -0,0 +1,96 @@
+#![cfg(any(target_os = "linux", feature = "app"))]
+
+use gllite::context::HeadlessContext;
+use gllite::format::{Channels, TextureFormat};
+use gllite::gli;
+use gllite::postprocess::{identity_lut, ColorGrade, Effect, EffectChain, FullscreenPass, RenderTarget};
+use gllite::texture::{FilterMode, Texture};
+use gllite::texture_3d::Texture3D;
+use std::rc::Rc;
+
+const SOLID_SHADER: &str = "#version 330
+in vec2 v_uv;
+out vec4 outColor;
+
+void main() {
+  outColor = vec4(0.25, 0.5, 0.75, 1.0);
+}";
+
+fn context() -> HeadlessContext {
+  let context = HeadlessContext::new(4, 4).expect("Post-processing tests need a headless GL context");
+  context.make_current().expect("Failed to make the headless context current");
+  context
+}
+
+// A 4x4 RGBA8 texture with a different color in every texel
+fn input_pixels() -> Vec<u8> {
+  (0..16u8).flat_map(|i| vec![i * 16, 255 - i * 16, i.wrapping_mul(37), 255]).collect()
+}
+
+fn input_texture() -> Texture {
+  let mut texture = Texture::new();
+  texture.set_data(TextureFormat::Unorm8(Channels::RGBA), 4, 4, &input_pixels());
+  texture.set_filter_mode(FilterMode::Nearest, FilterMode::Nearest);
+  texture
+}
+
+// A 2x2x2 LUT mapping every color to its inverse
+fn inverting_lut() -> Texture3D {
+  let mut data = Vec::new();
+  for b in 0..2u8 {
+    for g in 0..2u8 {
+      for r in 0..2u8 {
+        data.extend_from_slice(&[255 - r * 255, 255 - g * 255, 255 - b * 255, 255]);
+      }
+    }
+  }
+  let mut lut = Texture3D::new();
+  lut.set_from_bytes(gli::RGBA8, 2, 2, 2, gli::RGBA, &data);
+  lut
+}
+
+fn assert_pixels_close(actual: &[u8], expected: &[u8]) {
+  assert_eq!(actual.len(), expected.len());
+  for (i, (a, e)) in actual.iter().zip(expected.iter()).enumerate() {
+    assert!((*a as i32 - *e as i32).abs() <= 2, "Byte {} is {}, expected {}", i, a, e);
+  }
+}
+
+#[test]
+fn fullscreen_pass_covers_the_target() {
+  let context = context();
+  context.clear([0.0, 0.0, 0.0, 0.0]);
+  let mut pass = FullscreenPass::new(SOLID_SHADER);
+  pass.draw_to(RenderTarget::Framebuffer(context.framebuffer()));
+  let expected: Vec<u8> = (0..16).flat_map(|_| vec![64, 128, 191, 255]).collect();
+  assert_pixels_close(&context.read_pixels().data, &expected);
+}
+
+#[test]
+fn identity_lut_round_trips_colors() {
+  let context = context();
+  let input = input_texture();
+  // The effect owns the only handle to its LUT
+  let mut grade = ColorGrade::new(Rc::new(identity_lut(16)));
+  grade.apply(&input, RenderTarget::Framebuffer(context.framebuffer()));
+  assert_pixels_close(&context.read_pixels().data, &input_pixels());
+}
+
+#[test]
+fn effect_chain_runs_every_effect_in_order() {
+  let context = context();
+  let input = input_texture();
+  let lut = Rc::new(inverting_lut());
+  let mut chain = EffectChain::new();
+  // Three steps go through both scratch framebuffers
+  chain
+    .push(ColorGrade::new(lut.clone()))
+    .push(ColorGrade::new(lut.clone()))
+    .push(ColorGrade::new(lut));
+  chain.apply(&input, RenderTarget::Framebuffer(context.framebuffer()));
+  let expected: Vec<u8> = input_pixels()
+    .chunks(4)
+    .flat_map(|p| vec![255 - p[0], 255 - p[1], 255 - p[2], p[3]])
+    .collect();
+  assert_pixels_close(&context.read_pixels().data, &expected);
+}
@@ -0,0 +1,24 @@
+#![cfg(any(target_os = "linux", feature = "app"))]
+
+use gllite::context::HeadlessContext;
+use gllite::format::{Channels, TextureFormat};
+use gllite::render_graph::{RenderGraph, TextureDesc};
+use gllite::texture::Texture;
+
+#[test]
+fn writes_to_imported_textures_are_not_culled() {
+  let context = HeadlessContext::new(4, 4).expect("Render graph tests need a headless GL context");
+  context.make_current().expect("Failed to make the headless context current");
+  let format = TextureFormat::Unorm8(Channels::RGBA);
+  let mut history = Texture::new();
+  history.allocate(format, 4, 4);
+
+  let mut graph = RenderGraph::new();
+  let scratch = graph.create_texture("scratch", TextureDesc::new(format));
+  let unused = graph.create_texture("unused", TextureDesc::new(format));
+  let history = graph.import_texture("history", &history);
+  graph.add_pass("scene").write(scratch).execute(|_| ());
+  graph.add_pass("resolve").read(scratch).write(history).execute(|_| ());
+  graph.add_pass("debug").write(unused).execute(|_| ());
+  assert_eq!(graph.pass_order(), vec!["scene", "resolve"]);
+}
@@ -0,0 +1,64 @@
+#![cfg(any(target_os = "linux", feature = "app"))]
+
+use gllite::context::HeadlessContext;
+use gllite::gli;
+use gllite::material::Material;
+use gllite::math::Vec3;
+use gllite::node::Node;
+use gllite::program::Program;
+use gllite::render_state::RenderState;
+use gllite::scene::Scene;
+use gllite::uniforms::UniformValue;
+use std::rc::Rc;
+
+const VERTEX: &str = "#version 330
+in vec2 a_position;
+uniform mat4 u_model;
+
+void main() {
+  gl_Position = u_model * vec4(a_position, 0, 1);
+}";
+
+const FRAGMENT: &str = "#version 330
+uniform vec4 u_color;
+out vec4 outColor;
+
+void main() {
+  outColor = u_color;
+}";
+
+fn covering_node(program: &Rc<Program>, color: [f32; 4], state: RenderState) -> Node {
+  let material = Material::new(program.clone());
+  material.set_uniform(String::from("u_color"), UniformValue::FloatVec4(color[0], color[1], color[2], color[3]));
+  material.set_render_state(state);
+  let mut node = Node::for_program(program.clone());
+  node.set_material(Rc::new(material));
+  node.add_attribute(String::from("a_position"));
+  node.buffer_data(&[-1.0f32, -1.0, 3.0, -1.0, -1.0, 3.0]);
+  node
+}
+
+#[test]
+fn nodes_draw_with_their_render_state() {
+  let context = HeadlessContext::new(4, 4).expect("Scene tests need a headless GL context");
+  context.make_current().expect("Failed to make the headless context current");
+  let mut program = Program::new();
+  program
+    .add_shader(VERTEX, gli::VERTEX_SHADER)
+    .add_shader(FRAGMENT, gli::FRAGMENT_SHADER)
+    .compile();
+  let program = Rc::new(program);
+
+  let mut scene = Scene::new();
+  scene.add(covering_node(&program, [1.0, 0.0, 0.0, 1.0], RenderState::opaque()));
+  let glass = scene.add(covering_node(&program, [0.0, 0.0, 1.0, 0.5], RenderState::transparent()));
+  // In front of the opaque node, so it passes the depth test
+  scene.set_translation(glass, Vec3::new(0.0, 0.0, -0.5));
+  context.clear([0.0, 0.0, 0.0, 1.0]);
+  scene.draw();
+
+  let pixel = &context.read_pixels().data[..4];
+  for (actual, expected) in pixel.iter().zip([128u8, 0, 128, 191].iter()) {
+    assert!((*actual as i32 - *expected as i32).abs() <= 1, "Blended to {:?}", pixel);
+  }
+}
@@ -0,0 +1,48 @@
+#![cfg(any(target_os = "linux", feature = "app"))]
+
+use gllite::context::HeadlessContext;
+use gllite::format::{Channels, TextureFormat};
+use gllite::pixel_buffer::PixelUploadBuffer;
+use gllite::texture::Texture;
+use std::panic::{self, AssertUnwindSafe};
+
+fn context() -> HeadlessContext {
+  let context = HeadlessContext::new(4, 4).expect("Texture tests need a headless GL context");
+  context.make_current().expect("Failed to make the headless context current");
+  context
+}
+
+#[test]
+fn float_region_accepted_for_half_float_texture() {
+  let _context = context();
+  let mut texture = Texture::new();
+  texture.set_data(TextureFormat::Float16(Channels::RGBA), 4, 4, &[0.0f32; 64]);
+  texture.update_region(1, 1, 2, 2, &[1.0f32; 16]);
+}
+
+#[test]
+#[should_panic(expected = "Expected at least 64 bytes for a 2x2 region, got 32")]
+fn short_float_region_for_half_float_texture_panics() {
+  let _context = context();
+  let mut texture = Texture::new();
+  texture.set_data(TextureFormat::Float16(Channels::RGBA), 4, 4, &[0.0f32; 64]);
+  // Enough values for 2x2 half floats, but f32 data needs twice as many bytes
+  texture.update_region(1, 1, 2, 2, &[1.0f32; 8]);
+}
+
+#[test]
+fn invalid_buffered_region_leaves_no_unpack_buffer_bound() {
+  let _context = context();
+  let mut texture = Texture::new();
+  texture.set_data(TextureFormat::Unorm8(Channels::RGBA), 4, 4, &[0u8; 64]);
+  let mut upload = PixelUploadBuffer::new(2);
+  let result = panic::catch_unwind(AssertUnwindSafe(|| upload.update_region(&texture, 0, 0, 2, 2, &[255u8; 8])));
+  assert!(result.is_err());
+  let mut bound = -1;
+  unsafe {
+    gl::GetIntegerv(gl::PIXEL_UNPACK_BUFFER_BINDING, &mut bound);
+  }
+  assert_eq!(bound, 0);
+
+  upload.update_region(&texture, 0, 0, 2, 2, &[255u8; 16]);
+}